@@ -0,0 +1,152 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use drawrs::{
+    choices::TracingStrategy,
+    drawing::{
+        components::{auto_grid_size, find_connected_components, grid_size_for_step, SpatialIndex},
+        config::DrawConfig,
+        extraction::get_black_pixels_adaptive,
+    },
+    utils::geometry::Point,
+};
+use image::{GrayImage, Luma};
+use rayon::prelude::*;
+use std::{collections::HashSet, hint::black_box};
+
+/// Line art-like fixture: concentric rings a few pixels thick on white
+fn rings(size: u32) -> GrayImage {
+    let center = size as f64 / 2.0;
+
+    GrayImage::from_fn(size, size, |x, y| {
+        let radius = ((x as f64 - center).powi(2) + (y as f64 - center).powi(2)).sqrt();
+        Luma([if radius % 40.0 < 4.0 { 0 } else { 255 }])
+    })
+}
+
+/// The previous approach: every sampled coordinate into a `Vec`, filtered in
+/// parallel, then collected into a set on a single thread
+fn collect_black_pixels(img: &GrayImage, step: u32) -> HashSet<Point> {
+    let coords: Vec<(u32, u32)> = (0..img.height())
+        .step_by(step as usize)
+        .flat_map(|y| (0..img.width()).step_by(step as usize).map(move |x| (x, y)))
+        .collect();
+
+    let points: Vec<Point> = coords
+        .par_iter()
+        .filter(|&&(x, y)| img.get_pixel(x, y)[0] == 0)
+        .map(|&(x, y)| Point::new(x as i32, y as i32))
+        .collect();
+
+    points.into_iter().collect()
+}
+
+fn black_pixel_sampling(c: &mut Criterion) {
+    let img = rings(4000);
+    let mut group = c.benchmark_group("black pixel sampling 4000x4000");
+    group.sample_size(10);
+
+    for step in [1, 2, 4] {
+        group.bench_with_input(
+            BenchmarkId::new("parallel fold", step),
+            &step,
+            |b, &step| b.iter(|| get_black_pixels_adaptive(black_box(&img), step, None, None)),
+        );
+        group.bench_with_input(BenchmarkId::new("collect", step), &step, |b, &step| {
+            b.iter(|| collect_black_pixels(black_box(&img), step as u32))
+        });
+    }
+
+    group.finish();
+}
+
+/// Tracing cost for a few fixed cell sizes against the one picked by
+/// [`auto_grid_size`]
+///
+/// Cells much smaller than the connection distance make each neighbor lookup
+/// visit many near-empty cells, much larger ones make it scan many points
+/// that are out of reach.
+fn component_extraction(c: &mut Criterion) {
+    let config = DrawConfig::default();
+    let max_distance = config.connection_distance();
+    let (points, _) = get_black_pixels_adaptive(&rings(1000), config.effective_step(), None, None);
+
+    let mut group = c.benchmark_group("component extraction 1000x1000");
+    group.sample_size(10);
+
+    let auto = auto_grid_size(&points, max_distance);
+    let grid_sizes = [1, 2, 4, 8, 16, 32]
+        .map(|grid_size| (grid_size.to_string(), grid_size))
+        .into_iter()
+        .chain([(format!("auto ({})", auto), auto)]);
+
+    for (label, grid_size) in grid_sizes {
+        group.bench_with_input(
+            BenchmarkId::new("grid size", label),
+            &grid_size,
+            |b, &grid_size| {
+                b.iter_batched(
+                    || (points.clone(), SpatialIndex::new(&points, grid_size)),
+                    |(points, index)| {
+                        find_connected_components(
+                            points,
+                            max_distance,
+                            Some(index),
+                            None,
+                            TracingStrategy::Greedy,
+                            false,
+                        )
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Sampling and tracing together, indexing the points during the sampling
+/// scan against indexing them in a second pass over the sampled set
+fn sampling_and_tracing(c: &mut Criterion) {
+    let img = rings(2000);
+    let config = DrawConfig::default();
+    let step = config.effective_step();
+    let max_distance = config.connection_distance();
+    let trace = |points, index| {
+        find_connected_components(
+            points,
+            max_distance,
+            index,
+            None,
+            TracingStrategy::Greedy,
+            false,
+        )
+    };
+
+    let mut group = c.benchmark_group("sampling and tracing 2000x2000");
+    group.sample_size(10);
+
+    group.bench_function("index while scanning", |b| {
+        b.iter(|| {
+            let grid_size = grid_size_for_step(step, max_distance);
+            let (points, index) =
+                get_black_pixels_adaptive(black_box(&img), step, None, Some(grid_size));
+            trace(points, index)
+        })
+    });
+    group.bench_function("index after scanning", |b| {
+        b.iter(|| {
+            let (points, _) = get_black_pixels_adaptive(black_box(&img), step, None, None);
+            trace(points, None)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    black_pixel_sampling,
+    component_extraction,
+    sampling_and_tracing
+);
+criterion_main!(benches);
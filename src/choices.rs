@@ -1,10 +1,23 @@
-use crate::utils::duration::DurExt;
+use crate::utils::{duration::DurExt, status};
+use inquire::error::InquireResult;
 use inquiry::Choice;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum ImageSource {
+    /// File - Pick an image file from disk
+    #[default]
+    File,
+
+    /// Clipboard - Use the image currently copied to the clipboard
+    Clipboard,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
 pub enum ImageProcessingMethod {
     /// Otsu's Method - Best for most use-cases (default)
+    #[default]
     Otsu,
 
     /// Kapur's Entropy - Best for textured/heterogeneous images
@@ -18,19 +31,78 @@ pub enum ImageProcessingMethod {
 
     /// Sauvola's Method - Best for images with noisy/textured backgrounds
     Sauvola,
+
+    /// Adaptive Mean - Fast local threshold, best for clean line art
+    AdaptiveMean,
+
+    /// Phansalkar's Method - Best for faded/stained low-contrast documents
+    Phansalkar,
+}
+
+impl ImageProcessingMethod {
+    /// Every available processing method, in menu order
+    pub const ALL: [ImageProcessingMethod; 7] = [
+        ImageProcessingMethod::Otsu,
+        ImageProcessingMethod::Kapur,
+        ImageProcessingMethod::Wolfs,
+        ImageProcessingMethod::Bernsens,
+        ImageProcessingMethod::Sauvola,
+        ImageProcessingMethod::AdaptiveMean,
+        ImageProcessingMethod::Phansalkar,
+    ];
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum BitDepth {
+    /// 16-bit - Highest fidelity, slower and uses more memory (default)
+    #[default]
+    Sixteen,
+
+    /// 8-bit - Much faster and lighter, lossless for most (8-bit) images
+    Eight,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum ThresholdMode {
+    /// Automatic - Let the processing method compute the threshold (default)
+    #[default]
+    Automatic,
+
+    /// Manual - Enter a fixed threshold yourself
+    Manual,
+
+    /// Offset - Nudge the automatically computed threshold up or down
+    Offset,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum CombineOp {
+    /// Or - Black wherever either method found black, keeping the detail of both
+    #[default]
+    Or,
+
+    /// And - Black only where both methods agree, dropping the noise of either
+    And,
 }
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
 pub enum ScalingMode {
     /// Stretch - Fills entire region (may distort)
     Stretch,
 
     /// Fit - Scales to fit within region (maintains aspect ratio)
+    #[default]
     Fit,
 
     /// Fill - Scales to fill region completely (may crop edges)
     Fill,
 
+    /// Fit Width - Scales to match the region's width (height follows the aspect ratio)
+    FitWidth,
+
+    /// Fit Height - Scales to match the region's height (width follows the aspect ratio)
+    FitHeight,
+
     /// Center - Original size, centered in region
     Center,
 
@@ -38,28 +110,186 @@ pub enum ScalingMode {
     Tile,
 }
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
 pub enum RegionPickMode {
     /// Interactive - Interactively choose 2 coordinates to select the region
     Interactive,
 
     /// Manual - Input 2 coordinates to select the region
     Manual,
+
+    /// Percent - Input the region's edges as percentages of the screen size
+    Percent,
+
+    /// Full Screen - Use the whole primary monitor as the region
+    #[default]
+    FullScreen,
+
+    /// Active Window - Use the window that is focused when 'S' is pressed
+    ActiveWindow,
 }
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
+pub enum ParkCorner {
+    /// Top Left - Park the cursor near the top-left corner of the screen
+    #[default]
+    TopLeft,
+
+    /// Top Right - Park the cursor near the top-right corner of the screen
+    TopRight,
+
+    /// Bottom Left - Park the cursor near the bottom-left corner of the screen
+    BottomLeft,
+
+    /// Bottom Right - Park the cursor near the bottom-right corner of the screen
+    BottomRight,
+}
+
+impl ParkCorner {
+    /// Distance (in pixels) the cursor is parked from the screen edges, so it
+    /// doesn't trigger hot corners like GNOME's Activities overview
+    pub const INSET: i32 = 8;
+
+    /// Position of the corner on a display of the given size, [`Self::INSET`]
+    /// pixels inside the screen edges
+    pub fn position(self, (width, height): (i32, i32)) -> (i32, i32) {
+        let left = Self::INSET.min((width - 1).max(0));
+        let top = Self::INSET.min((height - 1).max(0));
+        let (right, bottom) = (
+            (width - 1 - Self::INSET).max(left),
+            (height - 1 - Self::INSET).max(top),
+        );
+
+        match self {
+            ParkCorner::TopLeft => (left, top),
+            ParkCorner::TopRight => (right, top),
+            ParkCorner::BottomLeft => (left, bottom),
+            ParkCorner::BottomRight => (right, bottom),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum SimplifyMode {
+    /// Fixed - Simplify every line with the same tolerance
+    #[default]
+    Fixed,
+
+    /// Length Scaled - Simplify long lines more than short, detailed ones
+    LengthScaled,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum PenInput {
+    /// Left Click - Hold the left mouse button while drawing
+    #[default]
+    LeftClick,
+
+    /// Right Click - Hold the right mouse button while drawing
+    RightClick,
+
+    /// Middle Click - Hold the middle mouse button while drawing
+    MiddleClick,
+
+    /// Key - Hold a keyboard key while drawing
+    Key,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
+pub enum FillMode {
+    /// Trace - Follow the black pixels of the processed image
+    #[default]
+    Trace,
+
+    /// Hatch - Shade dark areas with parallel lines spaced by how dark they are
+    Hatch,
+
+    /// Cross-Hatch - Hatch at several angles, crossing more lines in darker areas
+    CrossHatch,
+
+    /// Stipple - Dot the image with clicks, placing more dots in darker areas
+    Stipple,
+
+    /// Contours - Draw lines of equal brightness, like a topographic map
+    Contours,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
+pub enum StrokeStart {
+    /// Press - Put the pen down once at the start of each line
+    #[default]
+    Press,
+
+    /// Double Tap - Tap the pen and put it down again, for apps that drop the first press
+    DoubleTap,
+
+    /// Circle - Draw a tiny circle at the start of each line so it registers a dab
+    Circle,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
+pub enum TracingStrategy {
+    /// Greedy - Always jump to the nearest unvisited point
+    #[default]
+    Greedy,
+
+    /// Spanning Tree - Follow the shortest connections and split off side branches
+    SpanningTree,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
+pub enum CoordinateRounding {
+    /// Round - Move to the nearest screen pixel
+    #[default]
+    Round,
+
+    /// Floor - Round down, towards the top-left of the screen
+    Floor,
+
+    /// Ceil - Round up, towards the bottom-right of the screen
+    Ceil,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
+pub enum SamplingPattern {
+    /// Grid - Sample every few pixels on a regular grid
+    #[default]
+    Grid,
+
+    /// Adaptive - Sample detailed areas more finely than flat ones
+    Adaptive,
+
+    /// Poisson Disk - Scatter samples evenly without a grid, avoiding aliasing
+    PoissonDisk,
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
 pub enum DrawingAccuracy {
     /// Fast - Makes the drawing go faster at the cost of accuracy
     Fast,
 
     /// Balanced - Balances speed and accuracy
+    #[default]
     Balanced,
 
     /// Accurate - Makes the drawing more accurate at the cost of speed
     Accurate,
+
+    /// Ultra Fine - Super-samples small images for extra detail, slowest
+    UltraFine,
 }
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+impl DrawingAccuracy {
+    /// Every accuracy level, from fastest to most detailed
+    pub const ALL: [DrawingAccuracy; 4] = [
+        DrawingAccuracy::Fast,
+        DrawingAccuracy::Balanced,
+        DrawingAccuracy::Accurate,
+        DrawingAccuracy::UltraFine,
+    ];
+}
+
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq)]
 pub enum DrawingSpeed {
     /// Universe Annihilating (1ps/line) (BREAKS SOME APPS)
     UniverseAnnihilating,
@@ -68,6 +298,7 @@ pub enum DrawingSpeed {
     UltraFast,
 
     /// Fast (10ms/line)
+    #[default]
     Fast,
 
     /// Medium (50ms/line)
@@ -77,40 +308,156 @@ pub enum DrawingSpeed {
     Slow,
 }
 
-#[derive(Debug, Clone, Copy, Choice, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Choice, PartialEq, Serialize, Deserialize)]
 pub enum LineOrder {
     /// In Order - Draw each line in order
+    #[default]
     InOrder,
 
     /// Shuffled - Shuffle the order of each drawn line before starting
     Shuffled,
+
+    /// As Extracted - Draw lines top to bottom in the order they were traced
+    AsExtracted,
+
+    /// By Brightness - Draw lines over the darkest parts of the source image first
+    ByBrightness,
 }
 
 pub fn get_step(drawing_accuracy: DrawingAccuracy) -> i32 {
     match drawing_accuracy {
         DrawingAccuracy::Fast => 3,
         DrawingAccuracy::Balanced => 2,
-        DrawingAccuracy::Accurate => 1,
+        DrawingAccuracy::Accurate | DrawingAccuracy::UltraFine => 1,
+    }
+}
+
+pub fn get_interpolation_density(drawing_accuracy: DrawingAccuracy) -> f64 {
+    match drawing_accuracy {
+        DrawingAccuracy::Fast => 0.5,
+        DrawingAccuracy::Balanced => 0.75,
+        DrawingAccuracy::Accurate => 1.0,
+        DrawingAccuracy::UltraFine => 1.5,
+    }
+}
+
+/// Images whose larger side is below this many pixels are super-sampled with
+/// [`DrawingAccuracy::UltraFine`]
+pub const SUPERSAMPLE_MAX_SIZE: u32 = 512;
+
+/// Resolution small images are processed at with [`DrawingAccuracy::UltraFine`],
+/// relative to the screen region
+pub const SUPERSAMPLE_FACTOR: f64 = 2.0;
+
+/// Resolution an `image_size` image is processed at relative to the screen
+/// region, see [`DrawConfig::density_multiplier`](crate::drawing::config::DrawConfig::density_multiplier)
+///
+/// The step can't go below one pixel, so `UltraFine` instead upscales small
+/// images to [`SUPERSAMPLE_FACTOR`] times the region's size and samples them
+/// every pixel, which amounts to a step of a fraction of a screen pixel. An
+/// icon-sized source stretched over a large region is otherwise traced along
+/// the blocky edges of its scaled-up pixels; at the finer resolution the
+/// smoothly interpolated edges are followed instead. Large images are left at
+/// the region's resolution, as they already carry more detail than the region
+/// can show.
+pub fn get_density_multiplier(drawing_accuracy: DrawingAccuracy, image_size: (u32, u32)) -> f64 {
+    let small = image_size.0.max(image_size.1) < SUPERSAMPLE_MAX_SIZE;
+
+    match drawing_accuracy {
+        DrawingAccuracy::UltraFine if small => SUPERSAMPLE_FACTOR,
+        _ => 1.0,
     }
 }
 
-pub fn get_speed(speed: DrawingSpeed) -> Duration {
-    match speed {
+/// Delay between inputs for a drawing speed
+///
+/// The `UniverseAnnihilating` speed asks for confirmation first, unless
+/// `assume_yes` is set.
+///
+/// # Returns
+/// The delay, or `None` if that confirmation was declined
+///
+/// # Errors
+/// Fails if the confirmation can't be read, e.g. because it was cancelled.
+pub fn get_speed(speed: DrawingSpeed, assume_yes: bool) -> InquireResult<Option<Duration>> {
+    speed_delay(speed, assume_yes, || {
+        const WARNING: &str = "This will break things. Are you sure you want to use this speed?";
+        match status::default_answer(WARNING, false) {
+            Some(answer) => Ok(answer),
+            None => inquire::prompt_confirmation(WARNING),
+        }
+    })
+}
+
+/// [`get_speed`] asking for confirmation through `confirm`
+fn speed_delay(
+    speed: DrawingSpeed,
+    assume_yes: bool,
+    confirm: impl FnOnce() -> InquireResult<bool>,
+) -> InquireResult<Option<Duration>> {
+    Ok(Some(match speed {
         DrawingSpeed::UniverseAnnihilating => {
-            let confirmation = inquire::prompt_confirmation(
-                "This will break things. Are you sure you want to use this speed?",
-            )
-            .expect("Failed to read confirmation");
-
-            if confirmation {
-                Duration::from_picos(1)
-            } else {
-                panic!("Cancelled operation");
+            if !(assume_yes || confirm()?) {
+                return Ok(None);
             }
+            Duration::from_picos(1)
         }
         DrawingSpeed::UltraFast => Duration::from_micros(1),
         DrawingSpeed::Fast => Duration::from_micros(10),
         DrawingSpeed::Medium => Duration::from_micros(50),
         DrawingSpeed::Slow => Duration::from_micros(200),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inquire::InquireError;
+
+    fn unreachable_prompt() -> InquireResult<bool> {
+        panic!("the speed confirmation should not be asked for")
+    }
+
+    #[test]
+    fn assume_yes_skips_the_confirmation() {
+        assert_eq!(
+            speed_delay(DrawingSpeed::UniverseAnnihilating, true, unreachable_prompt).unwrap(),
+            Some(Duration::from_picos(1))
+        );
+        assert_eq!(
+            speed_delay(DrawingSpeed::Fast, false, unreachable_prompt).unwrap(),
+            Some(Duration::from_micros(10))
+        );
+    }
+
+    #[test]
+    fn declining_is_not_an_error_but_cancelling_is() {
+        let declined = speed_delay(DrawingSpeed::UniverseAnnihilating, false, || Ok(false));
+        assert_eq!(declined.unwrap(), None);
+
+        let interrupted = speed_delay(DrawingSpeed::UniverseAnnihilating, false, || {
+            Err(InquireError::OperationInterrupted)
+        });
+        assert!(matches!(
+            interrupted,
+            Err(InquireError::OperationInterrupted)
+        ));
+    }
+
+    #[test]
+    fn ultra_fine_super_samples_only_small_images() {
+        let icon = (64, 48);
+        let photo = (4000, 3000);
+
+        assert_eq!(
+            get_density_multiplier(DrawingAccuracy::UltraFine, icon),
+            SUPERSAMPLE_FACTOR
+        );
+        assert_eq!(
+            get_density_multiplier(DrawingAccuracy::UltraFine, photo),
+            1.0
+        );
+        assert_eq!(get_density_multiplier(DrawingAccuracy::Accurate, icon), 1.0);
+        assert_eq!(get_step(DrawingAccuracy::UltraFine), 1);
     }
 }
@@ -0,0 +1,263 @@
+use crate::{
+    drawing::progress::{ProgressConfig, DEFAULT_CHARS, DEFAULT_TEMPLATE},
+    utils::screen::RegionSpec,
+};
+use image::Rgb;
+
+/// Usage text printed for `--help` and on invalid arguments
+pub const USAGE: &str = "\
+Usage: drawrs [OPTIONS] [IMAGE]
+
+Arguments:
+  [IMAGE]  Image file to draw, or '-' to read image data from stdin
+
+Options:
+      --compare-methods     Save the result of every processing method next to the image and exit
+      --gui                 Pick the image and processing settings in a window with a live preview
+      --test-pattern        Draw a calibration pattern instead of an image to check the setup
+      --no-cache            Always reprocess the image instead of reusing a cached result
+      --render-paths <FILE> Save a PNG of the extracted paths colored by draw order (blue to red)
+      --record <FILE>       Log every simulated mouse and key event with timestamps to a file
+      --resume-from <INDEX> Skip the first INDEX lines of the drawing, to continue an interrupted run
+      --dump-histogram <FILE>
+                            Save the grayscale histogram as CSV, marking the chosen threshold
+      --mask <FILE>         Only draw where this black and white image is white
+      --background <COLOR>  Color transparent pixels are treated as, as hex RRGGBB (default: ffffff)
+      --region <EDGES>      Draw into LEFT,TOP,RIGHT,BOTTOM instead of asking for a region, in pixels
+                            or as percentages of the screen (e.g. 25%,25%,75%,75%)
+      --accuracy-stats      Pick the region first and show how many points and lines each accuracy level draws in it
+      --repeat-last         Draw with the settings of the last run instead of asking for them
+      --pick-recent         Pick the settings of one of the last runs instead of asking for them
+  -y, --yes                 Skip confirming the Universe Annihilating drawing speed
+      --timings             Print how long each stage of the pipeline took
+      --json                Print status as newline-delimited JSON events instead of text,
+                            answering each prompt with its default (no for yes or no questions,
+                            reported as a prompt_defaulted event) and drawing without waiting for
+                            'D'; exits with code 2 if the image file or coordinates are needed
+      --no-progress         Print plain progress lines instead of a progress bar, e.g. for logs
+      --progress-template <TEMPLATE>
+                            Indicatif template of the progress bar
+                            (default: '{wide_bar} line {pos}/{len} ({eta}) {msg}')
+      --progress-chars <CHARS>
+                            Filled, current and empty characters of the progress bar (default: '=>-')
+  -h, --help                Print this help message
+
+Hotkeys:
+  Keys are read globally, so they work while another window (e.g. your drawing
+  app) is focused.
+
+  S / E  Mark the start and end corners of the region
+  D      Start drawing, or skip the countdown once started
+  P      Pause or resume drawing between strokes
+  + / -  Speed drawing up or slow it down while drawing
+  Q      Quit, or cancel a drawing in progress";
+
+/// Where the image to draw comes from when given on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageInput {
+    /// Path to an image file
+    Path(String),
+
+    /// Encoded image data piped through stdin
+    Stdin,
+}
+
+/// Command-line arguments accepted by drawrs
+///
+/// Every argument is optional; anything not provided is asked for interactively.
+#[derive(Debug, Clone, Default)]
+pub struct CliArgs {
+    /// Print the usage text and exit
+    pub help: bool,
+
+    /// Run every processing method on the image, save the results and exit
+    pub compare_methods: bool,
+
+    /// Pick the image and processing settings in the GUI
+    pub gui: bool,
+
+    /// Draw a calibration pattern instead of an image
+    pub test_pattern: bool,
+
+    /// Skip the processed-image cache
+    pub no_cache: bool,
+
+    /// Where to save a visualization of the extracted paths in draw order
+    pub render_paths: Option<String>,
+
+    /// Where to log every simulated input event
+    pub record: Option<String>,
+
+    /// Number of lines to skip at the start of the first drawing
+    pub resume_from: Option<usize>,
+
+    /// Where to save the grayscale histogram of the image as CSV
+    pub dump_histogram: Option<String>,
+
+    /// Black and white image restricting drawing to its white areas
+    pub mask: Option<String>,
+
+    /// Color transparent pixels are composited onto before processing
+    pub background: Option<Rgb<u8>>,
+
+    /// Pick the region first and show how many points and lines each accuracy
+    /// level draws in it
+    pub accuracy_stats: bool,
+
+    /// Reuse the drawing settings of the last run
+    pub repeat_last: bool,
+
+    /// Pick the drawing settings of a recent run
+    pub pick_recent: bool,
+
+    /// Skip the confirmation of the dangerous drawing speed, for scripted runs
+    pub assume_yes: bool,
+
+    /// Print the duration of each pipeline stage
+    pub timings: bool,
+
+    /// Print status as JSON lines, answering prompts with their defaults
+    pub json: bool,
+
+    /// Replace the progress bar with plain progress lines
+    pub no_progress: bool,
+
+    /// Custom progress bar template
+    pub progress_template: Option<String>,
+
+    /// Custom progress bar characters
+    pub progress_chars: Option<String>,
+
+    /// Image to draw, bypassing the source prompt and file dialog
+    pub image: Option<ImageInput>,
+
+    /// Region to draw into, from `--region` or the GUI, bypassing the region
+    /// prompt
+    pub region: Option<RegionSpec>,
+}
+
+impl CliArgs {
+    /// Parses the arguments the process was started with
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parses a list of arguments, excluding the program name
+    ///
+    /// # Returns
+    /// - `Ok(CliArgs)`: The parsed arguments
+    /// - `Err`: A message describing the first invalid argument
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => cli.help = true,
+                "--compare-methods" => cli.compare_methods = true,
+                "--gui" => cli.gui = true,
+                "--test-pattern" => cli.test_pattern = true,
+                "--no-cache" => cli.no_cache = true,
+                "--accuracy-stats" => cli.accuracy_stats = true,
+                "--repeat-last" => cli.repeat_last = true,
+                "--pick-recent" => cli.pick_recent = true,
+                "-y" | "--yes" => cli.assume_yes = true,
+                "--timings" => cli.timings = true,
+                "--json" => cli.json = true,
+                "--no-progress" => cli.no_progress = true,
+                "--progress-template" => {
+                    cli.progress_template = Some(Self::value(&mut args, &arg)?)
+                }
+                "--progress-chars" => cli.progress_chars = Some(Self::value(&mut args, &arg)?),
+                "--render-paths" => cli.render_paths = Some(Self::value(&mut args, &arg)?),
+                "--record" => cli.record = Some(Self::value(&mut args, &arg)?),
+                "--mask" => cli.mask = Some(Self::value(&mut args, &arg)?),
+                "--dump-histogram" => cli.dump_histogram = Some(Self::value(&mut args, &arg)?),
+                "--background" => {
+                    let value = Self::value(&mut args, &arg)?;
+                    let color = Self::parse_color(&value)
+                        .ok_or_else(|| format!("Invalid color '{}', expected RRGGBB", value))?;
+                    cli.background = Some(color);
+                }
+                "--region" => {
+                    let value = Self::value(&mut args, &arg)?;
+                    let region = value
+                        .parse()
+                        .map_err(|e| format!("Invalid region '{}': {}", value, e))?;
+                    cli.region = Some(region);
+                }
+                "--resume-from" => {
+                    let value = Self::value(&mut args, &arg)?;
+                    let index = value
+                        .parse()
+                        .map_err(|_| format!("Invalid line index '{}'", value))?;
+                    cli.resume_from = Some(index);
+                }
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("Unknown option '{}'", flag));
+                }
+                input => {
+                    if cli.image.is_some() {
+                        return Err("Only one image can be given".to_string());
+                    }
+
+                    cli.image = Some(if input == "-" {
+                        ImageInput::Stdin
+                    } else {
+                        ImageInput::Path(input.to_string())
+                    });
+                }
+            }
+        }
+
+        Ok(cli)
+    }
+
+    /// How drawing progress should be shown according to the progress options
+    ///
+    /// JSON mode reports progress as events, so it never shows a bar.
+    pub fn progress_config(&self) -> ProgressConfig {
+        if self.no_progress || self.json {
+            return ProgressConfig::Disabled;
+        }
+
+        ProgressConfig::Bar {
+            template: self
+                .progress_template
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string()),
+            chars: self
+                .progress_chars
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CHARS.to_string()),
+        }
+    }
+
+    /// Color transparent pixels are composited onto, white unless `--background` is given
+    pub fn background(&self) -> Rgb<u8> {
+        self.background.unwrap_or(Rgb([255, 255, 255]))
+    }
+
+    /// Parses a hex `RRGGBB` color, with or without a leading `#`
+    fn parse_color(value: &str) -> Option<Rgb<u8>> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+    }
+
+    /// Takes the value following a flag
+    fn value<I>(args: &mut I, flag: &str) -> Result<String, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        args.next()
+            .ok_or_else(|| format!("Missing value for '{}'", flag))
+    }
+}
@@ -1,302 +1,3123 @@
 use {
     crate::{
         choices::*,
-        drawing::components::find_connected_components,
-        image_processing::{ImageProcessor, ImageScaler},
-        utils::geometry::Point,
+        cli::{CliArgs, ImageInput, USAGE},
+        drawing::{
+            checkpoint::{self, Checkpoint, CHECKPOINT_INTERVAL},
+            config::{DrawConfig, FillStyle, PenAction, Simplification},
+            contour::{contour_levels, extract_contours},
+            error::DrawError,
+            extraction::extract_paths,
+            hatch::{cross_hatch_lines, hatch_lines, HatchPass},
+            history::{History, HistoryEntry},
+            input::{EnigoBackend, InputBackend},
+            jitter::Jitter,
+            opacity::OpacityKeys,
+            ordering::{
+                average_brightness, filter_by_length, keep_longest, merge_collinear,
+                order_by_brightness, path_length, shuffle_seeded,
+            },
+            pattern::{calibration_pattern, frame_lines},
+            progress::DrawProgress,
+            recording::RecordingBackend,
+            simplify::simplify_douglas_peucker,
+            smoothing::smooth_moving_average,
+            stipple::generate_stipple,
+            strokes::{draw_stroke, pen, press_key_times, sweep},
+            svg::svg_to_polylines,
+            visualize::render_paths_ordered,
+        },
+        image_processing::{
+            compute_histogram, quantize, without_background, write_histogram_csv, ImageLoader,
+            ImageProcessor, ImageScaler, ProcessingCache, ProcessingOptions, ThresholdSource,
+            SUPPORTED_EXTENSIONS,
+        },
+        utils::{
+            geometry::{lock_aspect_ratio, normalize_region, Point, Polygon},
+            polling::PollBackoff,
+            screen::{active_window_region, full_screen_region, RegionSpec, ScreenRegion},
+            status::{self, StatusEvent},
+            timing,
+        },
     },
     device_query::{DeviceQuery, DeviceState, Keycode},
-    enigo::{Enigo, Mouse, Settings},
-    image::{ImageBuffer, Luma},
-    indicatif::{ProgressBar, ProgressStyle},
-    inquire::{error::InquireResult, prompt_u32},
+    enigo::{Button, Direction, Key},
+    image::{DynamicImage, GrayImage, ImageBuffer, Luma, Primitive, Rgb},
+    inquire::{
+        error::{CustomUserError, InquireResult},
+        validator::Validation,
+        CustomType, Select,
+    },
+    log::{info, warn},
     native_dialog::DialogBuilder,
-    rand::{rng, seq::SliceRandom},
-    rayon::{iter::ParallelIterator, prelude::IntoParallelRefIterator},
-    std::{collections::HashSet, thread, time::Duration},
+    rand::{rng, Rng},
+    std::{
+        fmt::Debug,
+        io::Write,
+        path::{Path, PathBuf},
+        str::FromStr,
+        thread,
+        time::{Duration, Instant},
+    },
 };
 
-pub struct DrawingApp {
-    enigo: Enigo,
-    device_state: DeviceState,
+#[cfg(feature = "overlay")]
+use crate::drawing::overlay::SelectionOverlay;
+
+/// Asks a yes or no question, answered no in JSON mode, see
+/// [`status::default_answer`]
+fn prompt_confirmation(message: &str) -> InquireResult<bool> {
+    match status::default_answer(message, false) {
+        Some(answer) => Ok(answer),
+        None => inquire::prompt_confirmation(message),
+    }
+}
+
+/// Asks for a whole number, see [`status::require_interactive`]
+fn prompt_u32(message: &str) -> InquireResult<u32> {
+    status::require_interactive(message);
+    inquire::prompt_u32(message)
+}
+
+/// Asks to pick one of the variants of a [`Choice`](inquiry::Choice) enum,
+/// picking its default in JSON mode, see [`status::default_answer`]
+fn choose<T: Default + Debug>(
+    choice: fn(&str) -> InquireResult<T>,
+    message: &str,
+) -> InquireResult<T> {
+    match status::default_answer(message, T::default()) {
+        Some(answer) => Ok(answer),
+        None => choice(message),
+    }
+}
+
+/// Binarized images with a larger share of black pixels are likely a failed
+/// threshold that would trace into one giant blob
+const MAX_BLACK_FRACTION: f64 = 0.9;
+
+/// Binarized images with a smaller share of black pixels are likely a failed
+/// threshold with next to nothing to draw
+const MIN_BLACK_FRACTION: f64 = 0.001;
+
+/// Binarized images at least this symmetric left to right are offered to be
+/// drawn as their left half only, see
+/// [`ImageProcessor::detect_horizontal_symmetry`]
+const MIN_SYMMETRY_SCORE: f64 = 0.95;
+
+/// Pause after each move of a practice run, short enough to sweep over a
+/// drawing quickly but long enough for the cursor to be seen moving, whatever
+/// the drawing speed
+const PRACTICE_DELAY: Duration = Duration::from_millis(1);
+
+/// Factor the drawing speed changes by with each press of '+' or '-'
+const SPEED_STEP: f64 = 1.25;
+
+/// Slowest and fastest drawing speeds reachable with '+' and '-', relative to
+/// the configured one
+const SPEED_RANGE: (f64, f64) = (0.1, 10.0);
+
+/// Smallest width and height (in pixels) accepted for the drawing region
+const MIN_REGION_SPAN: i32 = 10;
+
+type BinaryImage<T> = ImageBuffer<Luma<T>, Vec<T>>;
+
+/// Whether a binarized image whose share of black pixels is `black_fraction`
+/// likely comes from a failed threshold
+fn threshold_likely_failed(black_fraction: f64) -> bool {
+    !(MIN_BLACK_FRACTION..=MAX_BLACK_FRACTION).contains(&black_fraction)
+}
+
+/// Frames of a loaded image and the path of its file, if it came from one
+type LoadedImage = (Vec<DynamicImage>, Option<String>);
+
+/// 16-bit grayscale version of the source image, used for tonal effects
+type ToneImage = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+type ProcessFn<T> = fn(
+    &DynamicImage,
+    ImageProcessingMethod,
+    &ProcessingOptions,
+) -> Result<BinaryImage<T>, Box<dyn std::error::Error>>;
+
+/// Drawing settings asked for once per image, or once for every frame of an
+/// animation
+struct DrawPlan {
+    scaling_mode: ScalingMode,
+    config: DrawConfig,
+
+    /// Part of the image to draw, `None` for all of it
+    crop: Option<(Point, Point)>,
+
+    /// `--mask` image restricting drawing to its white areas
+    mask: Option<GrayImage>,
+
+    /// Region picked before the settings, see [`CliArgs::accuracy_stats`]
+    region: Option<ScreenRegion>,
+}
+
+/// Labels of the [`DrawingAccuracy`] choices for a scaling mode, in the order
+/// of [`DrawingAccuracy::ALL`]
+type AccuracyLabels<'a> = &'a dyn Fn(ScalingMode) -> Vec<String>;
+
+/// Interactive drawing session
+///
+/// Hotkeys are read with `device_query`, which reports the global keyboard
+/// state rather than keys sent to the terminal, so they keep working after
+/// focus is moved to the target app.
+///
+/// Simulated input goes through `B`, which moves the real cursor by default,
+/// and hotkeys are read from `K`.
+pub struct DrawingApp<B: InputBackend = EnigoBackend, K: DeviceQuery = DeviceState> {
+    input: B,
+    device_state: K,
+    args: CliArgs,
+
+    /// Processing method and options to use instead of asking for them
+    processing: Option<(ImageProcessingMethod, ProcessingOptions)>,
+
+    /// Where the drawing settings of each run are recorded
+    history: History,
+
+    /// Directory checkpoints of interrupted drawings are saved in
+    checkpoint_dir: PathBuf,
 }
 
 impl DrawingApp {
     pub fn new() -> Self {
+        Self::with_args(CliArgs::default())
+    }
+
+    pub fn with_args(args: CliArgs) -> Self {
+        Self::with_backend(
+            args,
+            EnigoBackend::new().expect("Failed to initialize enigo"),
+        )
+    }
+
+    pub fn run() {
+        let args = match CliArgs::from_env() {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                return;
+            }
+        };
+
+        if args.help {
+            println!("{}", USAGE);
+            return;
+        }
+
+        if args.timings {
+            timing::enable();
+        }
+
+        if args.json {
+            status::enable_json();
+        }
+
+        let (args, processing) = if args.gui {
+            match Self::gui_selection(args) {
+                Some((args, processing)) => (args, Some(processing)),
+                None => return,
+            }
+        } else {
+            (args, None)
+        };
+
+        let Some(record_path) = args.record.clone() else {
+            DrawingApp::with_args(args)
+                .with_processing(processing)
+                .execute();
+            return;
+        };
+
+        let backend = EnigoBackend::new().expect("Failed to initialize enigo");
+        match RecordingBackend::to_file(backend, &record_path) {
+            Ok(backend) => {
+                info!("Recording input events to {}", record_path);
+                DrawingApp::with_backend(args, backend)
+                    .with_processing(processing)
+                    .execute();
+            }
+            Err(e) => eprintln!("Failed to create the recording file: {}", e),
+        }
+    }
+
+    /// Lets the user pick the image, processing settings and region in the GUI
+    ///
+    /// # Returns
+    /// The arguments pointing at the picked image and region along with the
+    /// picked processing method and options, or `None` if nothing was picked
+    #[cfg(feature = "gui")]
+    fn gui_selection(
+        mut args: CliArgs,
+    ) -> Option<(CliArgs, (ImageProcessingMethod, ProcessingOptions))> {
+        match crate::gui::run_gui(args.background()) {
+            Ok(Some(selection)) => {
+                args.image = Some(ImageInput::Path(selection.image_path));
+                args.region = selection.region;
+                Some((args, (selection.method, selection.options)))
+            }
+            Ok(None) => {
+                status::say("No image selected. Exiting...");
+                None
+            }
+            Err(e) => {
+                status::say(format!("Failed to open the GUI: {}", e));
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "gui"))]
+    fn gui_selection(
+        _args: CliArgs,
+    ) -> Option<(CliArgs, (ImageProcessingMethod, ProcessingOptions))> {
+        status::say("drawrs was built without the gui feature, rebuild it with `--features gui`");
+        None
+    }
+}
+
+impl<B: InputBackend> DrawingApp<B> {
+    pub fn with_backend(args: CliArgs, input: B) -> Self {
+        DrawingApp::with_devices(args, input, DeviceState::new())
+    }
+}
+
+impl<B: InputBackend, K: DeviceQuery> DrawingApp<B, K> {
+    /// Session simulating input through `input` and reading hotkeys from
+    /// `device_state`
+    pub fn with_devices(args: CliArgs, input: B, device_state: K) -> Self {
         DrawingApp {
-            enigo: Enigo::new(&Settings::default()).expect("Failed to initialize enigo"),
-            device_state: DeviceState::new(),
+            input,
+            device_state,
+            args,
+            processing: None,
+            history: History::default(),
+            checkpoint_dir: checkpoint::default_dir(),
         }
     }
 
-    pub fn run() {
-        let mut app = DrawingApp::new();
-        app.execute();
+    /// Uses the given processing method and options instead of asking for them
+    pub fn with_processing(
+        mut self,
+        processing: Option<(ImageProcessingMethod, ProcessingOptions)>,
+    ) -> Self {
+        self.processing = processing;
+        self
+    }
+
+    /// Records the drawing settings in `history` instead of the user's one
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Saves checkpoints in `dir` instead of [`checkpoint::default_dir`]
+    pub fn with_checkpoint_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.checkpoint_dir = dir.as_ref().to_path_buf();
+        self
     }
 
-    fn select_image() -> Option<String> {
+    /// Returns the image path given on the command line, falling back to a file
+    /// dialog when none was provided
+    fn select_image(&self) -> Option<String> {
+        if let Some(ImageInput::Path(path)) = &self.args.image {
+            return Some(path.clone());
+        }
+
+        status::require_interactive("Image file");
         DialogBuilder::file()
-            .add_filter("Image Files", ["png", "jpg", "jpeg"])
+            .add_filter("Image Files", SUPPORTED_EXTENSIONS)
             .open_single_file()
             .show()
             .expect("Failed")
             .map(|file| file.display().to_string())
     }
 
-    fn select_scaling_mode(&self) -> ScalingMode {
-        ScalingMode::choice("Please select a scaling method").expect("Failed to get user input")
+    /// Asks for a value, offering `default`, which is taken as is in JSON mode
+    fn prompt_value<T>(message: &str, default: T) -> InquireResult<T>
+    where
+        T: Clone + Debug + FromStr + ToString,
+    {
+        match status::default_answer(message, default.clone()) {
+            Some(answer) => Ok(answer),
+            None => CustomType::new(message).with_default(default).prompt(),
+        }
     }
 
-    fn capture_screen_region(&self) -> InquireResult<((i32, i32), (i32, i32))> {
-        let capture_method = RegionPickMode::choice("How would you like to select the region?")?;
+    fn configure_processing(method: ImageProcessingMethod) -> InquireResult<ProcessingOptions> {
+        let bit_depth = choose(
+            BitDepth::choice,
+            "Which bit depth should the image be processed at?",
+        )?;
 
-        let (start_pos, end_pos) = match capture_method {
-            RegionPickMode::Manual => {
-                let tlx = prompt_u32("Please input the X value of the top left corner")? as i32;
-                let tly = prompt_u32("Please input the Y value of the top left corner")? as i32;
-                let brx = prompt_u32("Please input the X value of the bottom right corner")? as i32;
-                let bry = prompt_u32("Please input the Y value of the bottom right corner")? as i32;
+        let mut options = Self::configure_method(method, bit_depth)?;
 
-                return Ok(((tlx, tly), (brx, bry)));
-            }
-            RegionPickMode::Interactive => {
-                println!("Press 'S' to start selecting region");
-                self.wait_for_key(Keycode::S);
+        if prompt_confirmation("Remove small specks from the binarized image?")? {
+            options.min_component_area = Some(Self::prompt_value(
+                "Smallest blob (in pixels) to keep",
+                4u32,
+            )?);
+        }
+
+        Ok(options)
+    }
+
+    /// Asks for the threshold, preprocessing and tuning options of `method`,
+    /// processing at the given `bit_depth`
+    fn configure_method(
+        method: ImageProcessingMethod,
+        bit_depth: BitDepth,
+    ) -> InquireResult<ProcessingOptions> {
+        let threshold_source =
+            match choose(ThresholdMode::choice, "How should the threshold be chosen?")? {
+                ThresholdMode::Automatic => ThresholdSource::Auto,
+                ThresholdMode::Manual => {
+                    ThresholdSource::Manual(Self::prompt_value("Threshold (0-65535)", 32768u16)?)
+                }
+                ThresholdMode::Offset => ThresholdSource::Offset(Self::prompt_value(
+                    "Offset added to the computed threshold",
+                    0i32,
+                )?),
+            };
+        let mut options = ProcessingOptions {
+            bit_depth,
+            threshold_source,
+            ..ProcessingOptions::default()
+        };
+
+        options.equalize = prompt_confirmation(
+            "Equalize the histogram before thresholding? (helps washed-out, low-contrast images)",
+        )?;
+
+        if prompt_confirmation(
+            "Boost local contrast with CLAHE before thresholding? (helps unevenly lit photos)",
+        )? {
+            options.clahe = Some((
+                (
+                    Self::prompt_value("Number of CLAHE tile columns", 8)?,
+                    Self::prompt_value("Number of CLAHE tile rows", 8)?,
+                ),
+                Self::prompt_value(
+                    "CLAHE clip limit (1 barely changes the image, higher boosts contrast more)",
+                    2.0,
+                )?,
+            ));
+        }
 
-                let start = self.device_state.get_mouse().coords;
-                println!("Start position captured: ({}, {})", start.0, start.1);
-                println!("Move to end position and press 'E'");
+        if prompt_confirmation("Sharpen edges before thresholding? (keeps fine lines)")? {
+            options.unsharp = Some((
+                Self::prompt_value("Blur radius (sigma, in pixels) of the unsharp mask", 2.0)?,
+                Self::prompt_value("Sharpening amount", 1.0)?,
+            ));
+        }
+
+        if matches!(options.threshold_source, ThresholdSource::Manual(_)) {
+            return Ok(options);
+        }
 
-                self.wait_for_key(Keycode::E);
-                let end = self.device_state.get_mouse().coords;
-                println!("End position captured: ({}, {})", end.0, end.1);
+        let tunable = matches!(
+            method,
+            ImageProcessingMethod::Otsu
+                | ImageProcessingMethod::Kapur
+                | ImageProcessingMethod::Bernsens
+                | ImageProcessingMethod::AdaptiveMean
+                | ImageProcessingMethod::Phansalkar
+        );
+        if !tunable || !prompt_confirmation("Tune the parameters of this method?")? {
+            return Ok(options);
+        }
 
-                (start, end)
+        match method {
+            ImageProcessingMethod::Otsu | ImageProcessingMethod::Kapur => {
+                let max_side: u32 = Self::prompt_value(
+                    "Compute the threshold on a copy at most this many pixels wide/tall (0 = full size)",
+                    0,
+                )?;
+                options.threshold_downscale = (max_side > 0).then_some(max_side);
             }
-        };
+            ImageProcessingMethod::AdaptiveMean => {
+                options.adaptive_window =
+                    Some(Self::prompt_value("Neighborhood radius (in pixels)", 15)?);
+                options.adaptive_c = Self::prompt_value(
+                    "Constant subtracted from the neighborhood mean (0-65535)",
+                    options.adaptive_c,
+                )?;
+            }
+            ImageProcessingMethod::Bernsens => {
+                options.bernsen_window =
+                    Self::prompt_value("Neighborhood radius (in pixels)", options.bernsen_window)?;
+                options.bernsen_contrast = Self::prompt_value(
+                    "Minimum local contrast (0-65535) below which an area counts as uniform",
+                    options.bernsen_contrast,
+                )?;
+            }
+            ImageProcessingMethod::Phansalkar => {
+                options.phansalkar_p = Self::prompt_value("p", options.phansalkar_p)?;
+                options.phansalkar_q = Self::prompt_value("q", options.phansalkar_q)?;
+                options.phansalkar_k = Self::prompt_value("k", options.phansalkar_k)?;
+                options.phansalkar_r = Self::prompt_value("R", options.phansalkar_r)?;
+            }
+            _ => {}
+        }
 
-        Ok((start_pos, end_pos))
+        Ok(options)
     }
 
-    fn wait_for_key(&self, target_key: Keycode) {
-        loop {
-            let keys = self.device_state.get_keys();
-            if keys.contains(&target_key) {
-                self.wait_for_key_release(target_key);
-                break;
-            }
+    fn configure_advanced(config: &mut DrawConfig) -> InquireResult<()> {
+        if !prompt_confirmation("Configure advanced drawing options?")? {
+            return Ok(());
+        }
 
-            thread::sleep(Duration::from_millis(10));
+        config.interpolation_density = Self::prompt_value(
+            "Mouse moves per pixel when interpolating along a line",
+            config.interpolation_density,
+        )?;
+
+        config.density_multiplier = Self::prompt_value(
+            "Drawing resolution relative to the region's pixels (e.g. 2 for twice the detail)",
+            config.density_multiplier,
+        )?
+        .max(0.1);
+
+        if prompt_confirmation("Snap points to the pixels of a zoomed-in pixel-art canvas?")? {
+            let cell = Self::prompt_value("Screen pixels per canvas pixel", 8u32)?.max(1);
+            config.canvas_cell_size = Some(cell);
+            config.density_multiplier = 1.0 / cell as f64;
         }
-    }
 
-    fn wait_for_key_release(&self, target_key: Keycode) {
-        while self.device_state.get_keys().contains(&target_key) {
-            thread::sleep(Duration::from_millis(5));
+        if config.canvas_cell_size.is_none() {
+            config.rounding = choose(
+                CoordinateRounding::choice,
+                "How should positions between pixels be rounded?",
+            )?;
+        }
+
+        config.brush_size = Self::prompt_value(
+            "Brush diameter (in pixels) used in the target app",
+            config.brush_size,
+        )?;
+
+        config.tracing = choose(
+            TracingStrategy::choice,
+            "How should sampled points be joined into lines?",
+        )?;
+
+        if config.tracing == TracingStrategy::Greedy {
+            config.split_junctions =
+                prompt_confirmation("Split lines where strokes cross, keeping each one straight?")?;
+        }
+
+        config.grid_size = Some(Self::prompt_value(
+            "Spatial index cell size in pixels, a performance tweak (0 picks one from the point density)",
+            config.grid_size.unwrap_or(0),
+        )?)
+        .filter(|&grid_size| grid_size > 0);
+
+        if prompt_confirmation("Bridge small gaps in dashed or dithered lines?")? {
+            config.bridge_radius =
+                Some(Self::prompt_value("Maximum gap (in pixels) to bridge", 8)?);
+        }
+
+        config.pen_action = match choose(PenInput::choice, "How should the pen be put down?")? {
+            PenInput::LeftClick => PenAction::MouseButton(Button::Left),
+            PenInput::RightClick => PenAction::MouseButton(Button::Right),
+            PenInput::MiddleClick => PenAction::MouseButton(Button::Middle),
+            PenInput::Key => PenAction::Key(Key::Unicode(Self::prompt_value(
+                "Key to hold while drawing",
+                'b',
+            )?)),
+        };
+
+        if prompt_confirmation("Vary the brush opacity per line with keyboard shortcuts?")? {
+            let defaults = OpacityKeys::default();
+            config.opacity = Some(OpacityKeys {
+                increase: Self::prompt_value("Key that increases the opacity", defaults.increase)?,
+                decrease: Self::prompt_value("Key that decreases the opacity", defaults.decrease)?,
+                steps: Self::prompt_value(
+                    "Number of opacity levels reachable with the keys",
+                    defaults.steps,
+                )?,
+                gamma: Self::prompt_value(
+                    "Opacity curve exponent (1 is linear, higher keeps midtones lighter)",
+                    defaults.gamma,
+                )?,
+            });
         }
+
+        config.park_cursor = if prompt_confirmation(
+            "Move the cursor near a screen corner once drawing finishes or is cancelled?",
+        )? {
+            Some(choose(
+                ParkCorner::choice,
+                "Which corner should the cursor be parked in?",
+            )?)
+        } else {
+            None
+        };
+
+        config.lift_every = Some(Self::prompt_value(
+            "Lift the pen every this many points of a line (0 never splits lines)",
+            config.lift_every.unwrap_or(0),
+        )?)
+        .filter(|&every| every > 0);
+
+        config.stroke_start = choose(
+            StrokeStart::choice,
+            "How should the pen be put down at the start of each line?",
+        )?;
+
+        config.min_move = Self::prompt_value(
+            "Skip pen moves shorter than this many pixels",
+            config.min_move,
+        )?;
+
+        config.countdown = Duration::from_secs_f64(
+            Self::prompt_value(
+                "Countdown (in seconds) before drawing starts",
+                config.countdown.as_secs_f64(),
+            )?
+            .max(0.0),
+        );
+
+        config.line_delay = Duration::from_micros(Self::prompt_value(
+            "Delay (in µs) at the start and end of each line, independent of the drawing speed",
+            config.line_delay.as_micros() as u64,
+        )?);
+
+        config.settle_delay = Duration::from_millis(Self::prompt_value(
+            "Delay (in ms) before pressing the button at the start of each line",
+            config.settle_delay.as_millis() as u64,
+        )?);
+
+        config.jitter_amplitude = Self::prompt_value(
+            "Random jitter (in pixels) applied to each point for a hand-drawn look",
+            config.jitter_amplitude,
+        )?;
+
+        if prompt_confirmation("Use a fixed random seed for reproducible runs?")? {
+            config.seed = Some(Self::prompt_value("Random seed", 0u64)?);
+        }
+
+        config.max_points = Some(Self::prompt_value(
+            "Ask for confirmation above this many points (0 disables the limit)",
+            config.max_points.unwrap_or(0),
+        )?)
+        .filter(|&max_points| max_points > 0);
+
+        if prompt_confirmation("Fit the drawing into a time budget by lowering its detail?")? {
+            let message = "Time budget in minutes";
+            status::require_interactive(message);
+            let minutes = CustomType::new(message)
+                .with_default(10.0)
+                .with_validator(|&minutes: &f64| -> Result<Validation, CustomUserError> {
+                    Ok(if minutes > 0.0 && minutes.is_finite() {
+                        Validation::Valid
+                    } else {
+                        Validation::Invalid("The budget must be longer than zero".into())
+                    })
+                })
+                .prompt()?;
+            config.time_budget = Some(Duration::from_secs_f64(minutes * 60.0));
+        }
+
+        if prompt_confirmation("Join nearly touching lines that continue in the same direction?")? {
+            config.merge_collinear = Some((
+                Self::prompt_value("Maximum angle (in degrees) between joined lines", 15.0)?,
+                Self::prompt_value("Maximum gap (in pixels) between joined lines", 4.0)?,
+            ));
+        }
+
+        config.max_lines = Some(Self::prompt_value(
+            "Only draw this many of the longest lines (0 draws every line)",
+            config.max_lines.unwrap_or(0),
+        )?)
+        .filter(|&max_lines| max_lines > 0);
+
+        if prompt_confirmation("Skip lines that are too short or too long?")? {
+            config.min_len = Some(Self::prompt_value(
+                "Minimum line length in pixels (0 keeps short lines)",
+                0.0,
+            )?)
+            .filter(|&min_len| min_len > 0.0);
+            config.max_len = Some(Self::prompt_value(
+                "Maximum line length in pixels (0 keeps long lines)",
+                0.0,
+            )?)
+            .filter(|&max_len| max_len > 0.0);
+        }
+
+        config.smoothing_window = Some(Self::prompt_value(
+            "Smooth lines by averaging each point over a window of this many points (0 keeps them as traced)",
+            config.smoothing_window.unwrap_or(0),
+        )?)
+        .filter(|&window| window > 1);
+
+        if prompt_confirmation("Simplify lines by dropping points that barely change them?")? {
+            config.simplification = Some(
+                match choose(SimplifyMode::choice, "How should the tolerance be chosen?")? {
+                    SimplifyMode::Fixed => Simplification::Fixed(Self::prompt_value(
+                        "Largest distance (in pixels) a dropped point may lie from the line",
+                        1.0,
+                    )?),
+                    SimplifyMode::LengthScaled => Simplification::LengthScaled {
+                        ratio: Self::prompt_value(
+                            "Tolerance per pixel of line length (e.g. 0.005 allows 5 pixels on a 1000 pixel line)",
+                            0.005,
+                        )?,
+                        max_epsilon: Self::prompt_value("Largest tolerance in pixels", 3.0)?,
+                    },
+                },
+            );
+        }
+
+        if prompt_confirmation("Only draw inside a polygon, e.g. a speech bubble?")? {
+            status::require_interactive("Polygon vertices");
+            config.clip_polygon = Some(
+                CustomType::<Polygon>::new("Polygon vertices")
+                    .with_help_message(
+                        "Space-separated x,y pairs in pixels from the region's top-left corner, e.g. 0,0 200,0 100,150",
+                    )
+                    .prompt()?,
+            );
+        }
+
+        if prompt_confirmation("Split the drawing into a grid of separately placed tiles?")? {
+            config.tile_grid = (
+                Self::prompt_value("Number of tile columns", 2)?,
+                Self::prompt_value("Number of tile rows", 2)?,
+            );
+        }
+
+        if prompt_confirmation("Smoothly interpolate long pen-up jumps between lines?")? {
+            config.travel_threshold = Some(Self::prompt_value(
+                "Minimum jump distance (in pixels) to interpolate",
+                50,
+            )?);
+            config.travel_steps =
+                Self::prompt_value("Number of moves per interpolated jump", config.travel_steps)?;
+        }
+
+        Ok(())
     }
 
-    fn get_black_pixels_adaptive(
-        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
-        step: i32,
-    ) -> HashSet<Point> {
-        let coordinates: Vec<(u32, u32)> = (0..img.height())
-            .step_by(step as usize)
-            .flat_map(|y| (0..img.width()).step_by(step as usize).map(move |x| (x, y)))
-            .collect();
+    /// Asks how lines should be generated, along with the settings of the
+    /// picked style
+    fn prompt_fill_style() -> InquireResult<FillStyle> {
+        Ok(
+            match choose(FillMode::choice, "How should the image be filled?")? {
+                FillMode::Trace => FillStyle::Trace,
+                FillMode::Hatch => FillStyle::Hatch {
+                    angle: Self::prompt_value("Hatching angle (in degrees)", 45.0)?,
+                    min_spacing: Self::prompt_value(
+                        "Spacing (in pixels) of the hatch lines in black areas",
+                        3.0,
+                    )?,
+                    max_spacing: Self::prompt_value(
+                        "Widest spacing (in pixels) before an area is left blank",
+                        24.0,
+                    )?,
+                },
+                FillMode::CrossHatch => {
+                    let defaults = HatchPass::defaults();
+                    let count: usize = Self::prompt_value("Number of hatching angles", 2)?;
+                    let passes = (0..count.max(1))
+                        .map(|index| {
+                            let default = defaults.get(index).copied().unwrap_or(HatchPass {
+                                angle: 0.0,
+                                min_darkness: 0.9,
+                            });
 
-        let black_pixels: Vec<Point> = coordinates
-            .par_iter()
-            .filter_map(|&(x, y)| {
-                if img.get_pixel(x, y)[0] == 0 {
-                    Some(Point::new(x as i32, y as i32))
-                } else {
-                    None
+                            Ok(HatchPass {
+                                angle: Self::prompt_value(
+                                    &format!("Angle (in degrees) of pass {}", index + 1),
+                                    default.angle,
+                                )?,
+                                min_darkness: Self::prompt_value(
+                                    &format!(
+                                        "Darkness (0 to 1) an area needs to get pass {}",
+                                        index + 1
+                                    ),
+                                    default.min_darkness,
+                                )?,
+                            })
+                        })
+                        .collect::<InquireResult<Vec<_>>>()?;
+
+                    FillStyle::CrossHatch {
+                        passes,
+                        min_spacing: Self::prompt_value(
+                            "Spacing (in pixels) of the hatch lines in black areas",
+                            3.0,
+                        )?,
+                        max_spacing: Self::prompt_value(
+                            "Widest spacing (in pixels) before an area is left blank",
+                            24.0,
+                        )?,
+                    }
                 }
-            })
-            .collect();
+                FillMode::Contours => FillStyle::Contours {
+                    levels: Self::prompt_value("Number of brightness levels to trace", 8)?,
+                },
+                FillMode::Stipple => FillStyle::Stipple {
+                    density: Self::prompt_value("Dots per pixel in black areas", 0.05_f64)?
+                        .max(0.0),
+                },
+            },
+        )
+    }
+
+    /// Loads the `--mask` image, if any, at the size of the binarized image
+    ///
+    /// Transparent parts of the mask count as masked out.
+    fn load_mask(&self, size: (u32, u32)) -> Result<Option<GrayImage>, Box<dyn std::error::Error>> {
+        let Some(path) = &self.args.mask else {
+            return Ok(None);
+        };
 
-        black_pixels.into_iter().collect()
+        let mask = ImageLoader::flatten_alpha(ImageLoader::load(path)?, Rgb([0, 0, 0])).to_luma8();
+        Ok(Some(ImageScaler::fit_mask(&mask, size)?))
     }
 
-    fn draw_image(
-        &mut self,
-        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
-        start_pos: (i32, i32),
-        drawing_speed: Duration,
-        step: i32,
-        line_order: LineOrder,
-    ) {
-        println!("Drawing will start in 3 seconds. Keep your cursor still!");
-        thread::sleep(Duration::from_secs(3));
-
-        let black_pixels = Self::get_black_pixels_adaptive(img, step);
-        println!("Found {} black pixels to draw", black_pixels.len());
-
-        if black_pixels.is_empty() {
-            println!("No black pixels found to draw!");
-            return;
+    /// Offers to draw only the left half of an image that mirrors itself left to
+    /// right, for paint apps that can mirror strokes on their own
+    ///
+    /// # Returns
+    /// The crop covering the left half (including the center column) if the
+    /// user accepted, `None` otherwise
+    fn prompt_symmetric_half<T: Primitive + Send + Sync>(
+        bw_img: &BinaryImage<T>,
+    ) -> InquireResult<Option<(Point, Point)>> {
+        let symmetry = {
+            let _timer = timing::stage("symmetry detection");
+            ImageProcessor::detect_horizontal_symmetry(bw_img)
+        };
+        info!("Horizontal symmetry score: {:.3}", symmetry);
+
+        if symmetry < MIN_SYMMETRY_SCORE {
+            return Ok(None);
         }
 
-        let mut lines = find_connected_components(black_pixels, 3);
-        println!("Generated {} drawing paths", lines.len());
-        let progress_style = ProgressStyle::default_bar()
-            .template("{wide_bar} {pos}/{len} ({eta})")
-            .expect("Invalid progress style template")
-            .progress_chars("=>-");
-        let pb = ProgressBar::new(lines.len() as u64);
-        pb.set_style(progress_style);
+        status::say(format!(
+            "The image is {:.1}% symmetric left to right. Turning on vertical-axis symmetry \
+             in your paint app and drawing only the left half halves the drawing time.",
+            symmetry * 100.0
+        ));
+        if !prompt_confirmation("Draw only the left half?")? {
+            return Ok(None);
+        }
 
-        let total_lines = lines.len();
-        let mut rng = rng();
+        status::say("Select the left half of the area, the app mirrors it into the right one");
+        let (width, height) = bw_img.dimensions();
+        Ok(Some((
+            Point::new(0, 0),
+            Point::new(((width - 1) / 2) as i32, height as i32 - 1),
+        )))
+    }
 
-        if line_order == LineOrder::Shuffled {
-            lines.shuffle(&mut rng);
+    /// Asks whether only part of the image should be drawn and, if so, for the
+    /// corners of that part, re-prompting until they lie within the image
+    fn prompt_crop(image_size: (u32, u32)) -> InquireResult<Option<(Point, Point)>> {
+        if !prompt_confirmation("Draw only part of the image?")? {
+            return Ok(None);
         }
 
-        for line in lines.iter() {
-            if line.len() < 2 {
-                pb.inc(1);
-                continue;
+        status::say(format!(
+            "The image is {}x{} pixels, (0, 0) being its top-left corner",
+            image_size.0, image_size.1
+        ));
+
+        loop {
+            let top_left = Point::new(
+                prompt_u32("X of the top left corner of the part to draw")? as i32,
+                prompt_u32("Y of the top left corner of the part to draw")? as i32,
+            );
+            let bottom_right = Point::new(
+                prompt_u32("X of the bottom right corner of the part to draw")? as i32,
+                prompt_u32("Y of the bottom right corner of the part to draw")? as i32,
+            );
+
+            match ImageScaler::crop_bounds(image_size, (top_left, bottom_right)) {
+                Ok(_) => return Ok(Some((top_left, bottom_right))),
+                Err(e) => status::say(format!("{}, please try again", e)),
             }
+        }
+    }
 
-            let keys = self.device_state.get_keys();
+    /// Loads the image to draw from the command line input or an interactively
+    /// chosen source
+    ///
+    /// # Returns
+    /// - `Ok(Some((frames, path)))`: Every frame of an animated GIF, or just the
+    ///   loaded image otherwise, and its path if it came from a file
+    /// - `Ok(None)`: If the user didn't pick a file
+    /// - `Err`: If the image couldn't be loaded
+    fn load_image(&self) -> Result<Option<LoadedImage>, Box<dyn std::error::Error>> {
+        let source = match self.args.image {
+            Some(ImageInput::Stdin) => return Ok(Some((vec![ImageLoader::load_stdin()?], None))),
+            Some(ImageInput::Path(_)) => ImageSource::File,
+            None => choose(ImageSource::choice, "Where should the image come from?")?,
+        };
 
-            if keys.contains(&Keycode::Q) {
-                pb.finish_with_message("Cancelled");
-                std::process::exit(0);
+        match source {
+            ImageSource::File => {
+                if self.args.image.is_none() {
+                    status::say("Please select an image file");
+                }
+
+                match self.select_image() {
+                    Some(path) => Ok(Some((Self::load_frames(&path)?, Some(path)))),
+                    None => Ok(None),
+                }
+            }
+            ImageSource::Clipboard => Ok(Some((vec![ImageLoader::load_clipboard()?], None))),
+        }
+    }
+
+    /// Loads the image file at `path`, decoding every frame if it's a GIF
+    ///
+    /// GIFs whose frames can't be decoded fall back to their first frame.
+    fn load_frames(path: &str) -> Result<Vec<DynamicImage>, Box<dyn std::error::Error>> {
+        if ImageLoader::has_extension(path, "gif") {
+            match ImageLoader::load_gif_frames(path) {
+                Ok(frames) if !frames.is_empty() => return Ok(frames),
+                Ok(_) => warn!("The GIF has no frames, decoding it as a still image"),
+                Err(e) => warn!(
+                    "Failed to decode the GIF's frames, drawing the first one: {}",
+                    e
+                ),
             }
+        }
+
+        Ok(vec![ImageLoader::load(path)?])
+    }
 
-            let abs_start_x = start_pos.0 + line[0].x;
-            let abs_start_y = start_pos.1 + line[0].y;
+    fn select_scaling_mode(&self) -> InquireResult<ScalingMode> {
+        choose(ScalingMode::choice, "Please select a scaling method")
+    }
+
+    /// Asks the user for the region to draw into, unless one was already given
+    /// in the arguments
+    ///
+    /// `image_size` is used to optionally lock an interactively picked region to
+    /// the image's aspect ratio. The returned corners are always ordered
+    /// top-left first; regions smaller than [`MIN_REGION_SPAN`] in either
+    /// direction are rejected and the user is asked again.
+    fn capture_screen_region(&self, image_size: (u32, u32)) -> InquireResult<ScreenRegion> {
+        if let Some(spec) = self.args.region {
+            let resolved = self
+                .input
+                .display_size()
+                .map_err(|e| format!("Failed to query the screen size: {}", e))
+                .and_then(|size| spec.resolve(size))
+                .and_then(|(start, end)| normalize_region(start, end, MIN_REGION_SPAN));
+            match resolved {
+                Ok(region) => return Ok(region),
+                Err(e) => warn!("{}, please select the region instead", e),
+            }
+        }
 
-            self.enigo
-                .move_mouse(abs_start_x, abs_start_y, enigo::Coordinate::Abs)
-                .expect("Failed to move mouse");
-            thread::sleep(drawing_speed);
+        loop {
+            let (start, end) = self.pick_screen_region(image_size)?;
 
-            self.enigo
-                .button(enigo::Button::Left, enigo::Direction::Press)
-                .expect("Failed to click mouse button");
+            match normalize_region(start, end, MIN_REGION_SPAN) {
+                Ok(region) => return Ok(region),
+                Err(e) => status::say(format!("{}, please select the region again", e)),
+            }
+        }
+    }
 
-            for points_chunk in line.windows(2) {
-                let current = points_chunk[0];
-                let next = points_chunk[1];
+    /// Asks how to select the region and selects it
+    ///
+    /// If the screen size needed for the selection can't be queried, the user
+    /// is asked again; in JSON mode, where the default answer would fail the
+    /// same way, the run stops with [`status::require_interactive`] instead.
+    fn pick_screen_region(&self, image_size: (u32, u32)) -> InquireResult<ScreenRegion> {
+        loop {
+            let capture_method = choose(
+                RegionPickMode::choice,
+                "How would you like to select the region?",
+            )?;
 
-                let distance = current.distance_squared(&next);
-                if distance > 1 {
-                    let steps = (distance as f64).sqrt() as i32;
-                    for step in 1..=steps {
-                        let t = step as f64 / steps as f64;
-                        let interp_x = current.x as f64 + t * (next.x - current.x) as f64;
-                        let interp_y = current.y as f64 + t * (next.y - current.y) as f64;
+            let (start_pos, end_pos) = match capture_method {
+                RegionPickMode::Manual => {
+                    let tlx = prompt_u32("Please input the X value of the top left corner")? as i32;
+                    let tly = prompt_u32("Please input the Y value of the top left corner")? as i32;
+                    let brx =
+                        prompt_u32("Please input the X value of the bottom right corner")? as i32;
+                    let bry =
+                        prompt_u32("Please input the Y value of the bottom right corner")? as i32;
 
-                        let abs_x = start_pos.0 + interp_x as i32;
-                        let abs_y = start_pos.1 + interp_y as i32;
-                        self.enigo
-                            .move_mouse(abs_x, abs_y, enigo::Coordinate::Abs)
-                            .expect("Failed to move mouse");
+                    return Ok(((tlx, tly), (brx, bry)));
+                }
+                RegionPickMode::Percent => {
+                    let spec = RegionSpec::Percent {
+                        left: Self::prompt_value("Left edge (percent of the screen width)", 25.0)?,
+                        top: Self::prompt_value("Top edge (percent of the screen height)", 25.0)?,
+                        right: Self::prompt_value(
+                            "Right edge (percent of the screen width)",
+                            75.0,
+                        )?,
+                        bottom: Self::prompt_value(
+                            "Bottom edge (percent of the screen height)",
+                            75.0,
+                        )?,
+                    };
 
-                        thread::sleep(drawing_speed);
+                    let resolved = self
+                        .input
+                        .display_size()
+                        .map_err(|e| format!("Failed to query the screen size: {}", e))
+                        .and_then(|size| spec.resolve(size));
+                    match resolved {
+                        Ok(region) => region,
+                        Err(e) => {
+                            warn!("{}", e);
+                            status::require_interactive("How would you like to select the region?");
+                            continue;
+                        }
                     }
-                } else {
-                    let abs_x = start_pos.0 + next.x;
-                    let abs_y = start_pos.1 + next.y;
-                    self.enigo
-                        .move_mouse(abs_x, abs_y, enigo::Coordinate::Abs)
-                        .expect("Failed to move mouse");
-
-                    if !drawing_speed.is_zero() {
-                        thread::sleep(drawing_speed);
+                }
+                RegionPickMode::FullScreen => match self.input.display_size() {
+                    Ok(size) => full_screen_region(size),
+                    Err(e) => {
+                        warn!("Failed to query the screen size: {}", e);
+                        status::require_interactive("How would you like to select the region?");
+                        continue;
+                    }
+                },
+                RegionPickMode::ActiveWindow => {
+                    status::say("Focus the window to draw in and press 'S'");
+                    self.wait_for_key(Keycode::S);
+
+                    match active_window_region() {
+                        Ok(region) => region,
+                        Err(e) => {
+                            warn!("{}, falling back to the full screen", e);
+                            match self.input.display_size() {
+                                Ok(size) => full_screen_region(size),
+                                Err(e) => {
+                                    warn!("Failed to query the screen size: {}", e);
+                                    ((0, 0), (0, 0))
+                                }
+                            }
+                        }
                     }
                 }
-            }
+                RegionPickMode::Interactive => {
+                    let lock_aspect =
+                        prompt_confirmation("Lock the region to the image's aspect ratio?")?;
+
+                    status::say("Press 'S' to start selecting region");
+                    self.wait_for_key(Keycode::S);
+
+                    let start = self.device_state.get_mouse().coords;
+                    status::say(format!(
+                        "Start position captured: ({}, {})",
+                        start.0, start.1
+                    ));
+                    status::say("Move to end position and press 'E'");
+
+                    #[cfg(feature = "overlay")]
+                    match self.input.display_size() {
+                        Ok((width, height)) => {
+                            let mut overlay =
+                                SelectionOverlay::new((width as usize, height as usize));
+                            self.wait_for_key_with(Keycode::E, || {
+                                overlay.update(start, self.device_state.get_mouse().coords)
+                            });
+                        }
+                        Err(e) => {
+                            warn!(
+                                "Failed to query the screen size, not showing the overlay: {}",
+                                e
+                            );
+                            self.wait_for_key(Keycode::E);
+                        }
+                    }
+                    #[cfg(not(feature = "overlay"))]
+                    self.wait_for_key(Keycode::E);
+
+                    let mut end = self.device_state.get_mouse().coords;
+                    status::say(format!("End position captured: ({}, {})", end.0, end.1));
 
-            self.enigo
-                .button(enigo::Button::Left, enigo::Direction::Release)
-                .expect("Failed to release mouse button");
-            thread::sleep(drawing_speed);
-            pb.inc(1);
+                    if lock_aspect {
+                        end = lock_aspect_ratio(start, end, image_size);
+                        status::say(format!(
+                            "Adjusted region to ({}, {}) -> ({}, {}) to match the image's aspect ratio",
+                            start.0, start.1, end.0, end.1
+                        ));
+                    }
+
+                    (start, end)
+                }
+            };
+
+            break Ok((start_pos, end_pos));
         }
+    }
 
-        pb.finish_with_message(format!(
-            "Drawing completed! Drew {} paths with {} total points",
-            total_lines,
-            lines.iter().map(|l| l.len()).sum::<usize>()
-        ));
+    fn wait_for_key(&self, target_key: Keycode) {
+        self.wait_for_key_with(target_key, || {});
     }
 
-    fn wait_for_drawing_command(
-        &mut self,
-        scaled_img: &ImageBuffer<Luma<u16>, Vec<u16>>,
-        start_pos: (i32, i32),
-        drawing_speed: Duration,
-        step: i32,
-        line_order: LineOrder,
-    ) {
+    /// Waits for `target_key` to be pressed and released, calling `on_tick`
+    /// between polls
+    fn wait_for_key_with(&self, target_key: Keycode, mut on_tick: impl FnMut()) {
+        let mut backoff = PollBackoff::default();
+        let mut last_mouse = self.device_state.get_mouse().coords;
+
         loop {
             let keys = self.device_state.get_keys();
-            if keys.contains(&Keycode::D) {
-                self.draw_image(scaled_img, start_pos, drawing_speed, step, line_order);
-                break;
-            } else if keys.contains(&Keycode::Q) {
-                println!("Quitting!");
+            if keys.contains(&target_key) {
+                self.wait_for_key_release(target_key);
                 break;
             }
 
-            thread::sleep(drawing_speed);
+            on_tick();
+            backoff.wait(self.input_active(&keys, &mut last_mouse));
         }
     }
 
-    fn execute(&mut self) {
-        println!("Please select an image file");
-        let image_path = match Self::select_image() {
-            Some(path) => path,
-            None => {
-                println!("No image selected. Exiting...");
-                return;
-            }
-        };
+    /// Blocks until drawing is resumed with 'P' or cancelled with 'Q'
+    ///
+    /// # Returns
+    /// `true` if drawing should resume, `false` if it was cancelled
+    fn wait_while_paused(&self) -> bool {
+        self.wait_for_key_release(Keycode::P);
 
-        let processing_method =
-            ImageProcessingMethod::choice("Please select a method for processing the image")
-                .expect("Failed to get user input");
-        let bw_img = match ImageProcessor::process_image(&image_path, processing_method) {
-            Ok(img) => img,
-            Err(e) => {
-                println!("Error processing image: {}", e);
-                return;
+        let mut backoff = PollBackoff::default();
+        let mut last_mouse = self.device_state.get_mouse().coords;
+
+        loop {
+            let keys = self.device_state.get_keys();
+            if keys.contains(&Keycode::P) {
+                self.wait_for_key_release(Keycode::P);
+                return true;
+            } else if keys.contains(&Keycode::Q) {
+                return false;
             }
-        };
 
-        println!("Image processed successfully!");
+            backoff.wait(self.input_active(&keys, &mut last_mouse));
+        }
+    }
 
-        let scaling_mode = self.select_scaling_mode();
-        let step = get_step(
-            DrawingAccuracy::choice("Please select a desired accuracy for the drawing")
-                .expect("Failed to get user input"),
-        );
-        let drawing_speed = get_speed(
-            DrawingSpeed::choice("How fast should the image be drawn?")
-                .expect("Failed to get user input"),
-        );
-        let line_order = LineOrder::choice("What order should each line be drawn in?")
-            .expect("Failed to get user input");
+    /// Returns whether any key is held or the mouse moved since `last_mouse`,
+    /// updating it to the current position
+    fn input_active(&self, keys: &[Keycode], last_mouse: &mut (i32, i32)) -> bool {
+        let mouse = self.device_state.get_mouse().coords;
+        let moved = mouse != *last_mouse;
+        *last_mouse = mouse;
 
-        println!("Move your cursor to select the region where you want to draw.");
-        let (start_pos, end_pos) = self
-            .capture_screen_region()
-            .expect("Failed to capture screen region");
-        let scaled_img =
-            ImageScaler::scale_image_to_region(&bw_img, start_pos, end_pos, scaling_mode);
+        moved || !keys.is_empty()
+    }
 
-        println!("Ready to draw! Press 'D' to start drawing or 'Q' to quit");
-        self.wait_for_drawing_command(&scaled_img, start_pos, drawing_speed, step, line_order);
+    fn wait_for_key_release(&self, target_key: Keycode) {
+        while self.device_state.get_keys().contains(&target_key) {
+            thread::sleep(Duration::from_millis(5));
+        }
     }
-}
 
-impl Default for DrawingApp {
+    /// Traces the corners of the target region with the pen up so the user can
+    /// verify where the drawing will land, then asks whether to continue
+    ///
+    /// # Returns
+    /// `true` if the user confirmed the region, `false` otherwise
+    ///
+    /// # Errors
+    /// Fails if moving the cursor keeps failing, see [`sweep`], or if the
+    /// answer can't be read.
+    fn preview_region(
+        &mut self,
+        start_pos: (i32, i32),
+        end_pos: (i32, i32),
+    ) -> Result<bool, DrawError> {
+        status::say("Previewing the drawing region...");
+
+        let corners = [
+            (start_pos.0, start_pos.1),
+            (end_pos.0, start_pos.1),
+            (end_pos.0, end_pos.1),
+            (start_pos.0, end_pos.1),
+            (start_pos.0, start_pos.1),
+        ];
+
+        sweep(&mut self.input, corners, Duration::from_millis(500))?;
+
+        Ok(prompt_confirmation("Did the region look correct?")?)
+    }
+
+    /// Extracts the lines of a scaled image and draws them with its top-left
+    /// corner at `start_pos`
+    ///
+    /// `frame` is the size of the region to draw a frame around after the
+    /// image (see [`DrawConfig::draw_frame`]), at the virtual resolution.
+    /// `progress` is the progress of a drawing the image is one part of, if
+    /// any, see [`draw_lines`](Self::draw_lines).
+    ///
+    /// # Errors
+    /// Fails if simulating input keeps failing, see [`draw_lines`](Self::draw_lines).
+    fn draw_image<T: Primitive + Send + Sync>(
+        &mut self,
+        img: &BinaryImage<T>,
+        tone: Option<&ToneImage>,
+        start_pos: (i32, i32),
+        frame: Option<(u32, u32)>,
+        config: &DrawConfig,
+        progress: Option<&mut DrawProgress>,
+    ) -> Result<(), DrawError> {
+        let (fitted, budget_lines) = match (config.time_budget, &config.fill_style) {
+            (Some(budget), FillStyle::Trace) if !budget.is_zero() => {
+                let (fitted, lines) = Self::fit_time_budget(img, budget, config);
+                (Some(fitted), Some(lines))
+            }
+            (Some(_), FillStyle::Trace) => {
+                warn!("Ignoring the empty time budget");
+                (None, None)
+            }
+            (Some(_), _) => {
+                warn!("The time budget only applies to traced drawings, ignoring it");
+                (None, None)
+            }
+            (None, _) => (None, None),
+        };
+        let config = fitted.as_ref().unwrap_or(config);
+
+        let mut lines = match (&config.fill_style, tone) {
+            (FillStyle::Trace, _) => budget_lines.unwrap_or_else(|| extract_paths(img, config)),
+            (
+                &FillStyle::Hatch {
+                    angle,
+                    min_spacing,
+                    max_spacing,
+                },
+                Some(tone),
+            ) => {
+                let _timer = timing::stage("hatching");
+                hatch_lines(tone, angle, min_spacing, max_spacing, 0.0)
+            }
+            (
+                FillStyle::CrossHatch {
+                    passes,
+                    min_spacing,
+                    max_spacing,
+                },
+                Some(tone),
+            ) => {
+                let _timer = timing::stage("hatching");
+                cross_hatch_lines(tone, passes, *min_spacing, *max_spacing)
+            }
+            (&FillStyle::Contours { levels }, Some(tone)) => {
+                let _timer = timing::stage("contour tracing");
+                extract_contours(tone, &contour_levels(levels))
+            }
+            (&FillStyle::Stipple { density }, Some(tone)) => {
+                let _timer = timing::stage("stippling");
+                generate_stipple(tone, density, config.seed)
+                    .into_iter()
+                    .map(|dot| vec![dot])
+                    .collect()
+            }
+            (_, None) => {
+                warn!("Shading needs the grayscale source, tracing black pixels instead");
+                extract_paths(img, config)
+            }
+        };
+
+        // Traced paths were clipped while sampling, shading is clipped here
+        if let Some(clip) = config
+            .clip_polygon
+            .as_ref()
+            .filter(|_| config.fill_style != FillStyle::Trace)
+        {
+            let clip = clip.scaled(config.density_multiplier);
+            lines = lines.iter().flat_map(|line| clip.clip_line(line)).collect();
+        }
+        info!("Generated {} drawing paths", lines.len());
+        status::emit(StatusEvent::PathsGenerated {
+            lines: lines.len(),
+            points: lines.iter().map(Vec::len).sum(),
+        });
+
+        if lines.is_empty() && frame.is_none() {
+            status::say("Nothing found to draw!");
+            if let Some(progress) = progress {
+                progress.start_part(0);
+            }
+            return Ok(());
+        }
+
+        if let Some((angle_tol, gap_tol)) = config.merge_collinear {
+            let _timer = timing::stage("merging");
+            lines = merge_collinear(lines, angle_tol, gap_tol);
+            info!("Merged into {} paths", lines.len());
+        }
+
+        if config.min_len.is_some() || config.max_len.is_some() {
+            filter_by_length(&mut lines, config.min_len, config.max_len);
+            info!("Kept {} paths within the length range", lines.len());
+        }
+
+        if let Some(max_lines) = config.max_lines {
+            keep_longest(&mut lines, max_lines);
+            info!("Kept the {} longest paths", lines.len());
+        }
+
+        if let Some(window) = config.smoothing_window {
+            let _timer = timing::stage("smoothing");
+            for line in &mut lines {
+                *line = smooth_moving_average(line, window);
+            }
+        }
+
+        if let Some(simplification) = config.simplification {
+            let _timer = timing::stage("simplification");
+            for line in &mut lines {
+                let epsilon = simplification.epsilon(path_length(line));
+                *line = simplify_douglas_peucker(line, epsilon);
+            }
+            info!(
+                "Simplified down to {} points",
+                lines.iter().map(Vec::len).sum::<usize>()
+            );
+        }
+
+        if let (LineOrder::ByBrightness, Some(tone)) = (config.line_order, tone) {
+            order_by_brightness(&mut lines, tone);
+        }
+
+        if let Some((width, height)) = frame {
+            let inset = (config.frame_inset as f64 * config.density_multiplier).round() as u32;
+            lines.extend(frame_lines(width, height, inset));
+        }
+
+        let drawn = self.draw_lines(lines, tone, img.dimensions(), start_pos, config, progress);
+        self.park_cursor(config);
+        drawn
+    }
+
+    /// Picks the settings to trace `img` with so that it fits `budget`, see
+    /// [`DrawConfig::fit_time_budget`], and reports them
+    ///
+    /// # Returns
+    /// The settings and the paths traced with them
+    fn fit_time_budget<T: Primitive + Send + Sync>(
+        img: &BinaryImage<T>,
+        budget: Duration,
+        config: &DrawConfig,
+    ) -> (DrawConfig, Vec<Vec<Point>>) {
+        let _timer = timing::stage("time budget");
+        let (fitted, lines) = config.fit_time_budget(budget, |candidate| {
+            let lines = extract_paths(img, candidate);
+            let points = lines.iter().map(Vec::len).sum();
+            (candidate.estimate_duration(points, lines.len()), lines)
+        });
+
+        status::say(format!(
+            "To fit into {:.0?}: sampling every {} pixels with a {:?} point delay and {:?} line delay",
+            budget, fitted.step, fitted.point_delay, fitted.line_delay
+        ));
+
+        let points = lines.iter().map(Vec::len).sum();
+        let expected = fitted.estimate_duration(points, lines.len());
+        if expected > budget {
+            status::say(format!(
+                "Warning: even at the lowest detail the drawing takes about {:.0?}",
+                expected
+            ));
+        }
+
+        (fitted, lines)
+    }
+
+    /// Moves the cursor out of the way to the configured corner of the screen,
+    /// see [`DrawConfig::park_cursor`]
+    fn park_cursor(&mut self, config: &DrawConfig) {
+        let Some(corner) = config.park_cursor else {
+            return;
+        };
+
+        let parked = self.input.display_size().and_then(|size| {
+            let (x, y) = corner.position(size);
+            self.input.move_to(x, y)
+        });
+        if let Err(e) = parked {
+            warn!("Failed to park the cursor: {}", e);
+        }
+    }
+
+    /// Draws already extracted lines, given in the coordinates of an image of
+    /// `size` pixels whose top-left corner is drawn at `start_pos`
+    ///
+    /// `tone` is the matching grayscale source, if any, used for tonal effects.
+    /// The lines are counted as the next part of `progress` if given, e.g. for
+    /// one tile of a mural, or on their own otherwise.
+    ///
+    /// # Errors
+    /// Fails if simulating input still fails after a retry. The pen is lifted
+    /// and a checkpoint saved first, so the drawing can be resumed. Also fails
+    /// if asking whether to draw a drawing over the point limit or to resume an
+    /// earlier run is cancelled.
+    fn draw_lines(
+        &mut self,
+        mut lines: Vec<Vec<Point>>,
+        tone: Option<&ToneImage>,
+        size: (u32, u32),
+        start_pos: (i32, i32),
+        config: &DrawConfig,
+        progress: Option<&mut DrawProgress>,
+    ) -> Result<(), DrawError> {
+        let (mut point_delay, mut line_delay) = (config.point_delay, config.line_delay);
+        let mut speed = 1.0;
+        let mut speed_key_held = false;
+
+        let total_points: usize = lines.iter().map(Vec::len).sum();
+        if let Some(max_points) = config.max_points {
+            if total_points > max_points
+                && !Self::confirm_large_drawing(total_points, lines.len(), max_points, config)?
+            {
+                status::say("Drawing cancelled");
+                return Ok(());
+            }
+        }
+
+        let total_lines = lines.len();
+        let mut last_pos: Option<(i32, i32)> = None;
+
+        // Keyed by the lines before shuffling, so the seed saved with it
+        // reproduces the same order
+        let checkpoint = Checkpoint::for_drawing(&self.checkpoint_dir, &lines, start_pos);
+        let saved = checkpoint.load();
+        let saved_seed = saved.and_then(|saved| saved.seed);
+
+        let seed = config.seed.or(saved_seed).unwrap_or_else(|| rng().random());
+        let randomized = config.line_order == LineOrder::Shuffled || config.jitter_amplitude > 0.0;
+        if randomized && config.seed.is_none() && saved_seed.is_none() {
+            status::say(format!("Using random seed {}", seed));
+        }
+        let checkpoint = checkpoint.with_seed(seed);
+
+        if config.line_order == LineOrder::Shuffled {
+            shuffle_seeded(&mut lines, seed);
+        }
+
+        // Only the first drawing of a run resumes, later tiles start from scratch
+        let saved_line = saved
+            .map(|saved| saved.next_line)
+            .filter(|&index| index > 0 && index < total_lines);
+        let resume_from = match (self.args.resume_from.take(), saved_line) {
+            (Some(index), _) => index,
+            (None, Some(index)) => {
+                let resume = prompt_confirmation(&format!(
+                    "An earlier run of this drawing stopped at line {} of {}. Resume from there?",
+                    index, total_lines
+                ))?;
+                if resume {
+                    index
+                } else {
+                    0
+                }
+            }
+            (None, None) => 0,
+        }
+        .min(total_lines);
+
+        if resume_from > 0
+            && config.line_order == LineOrder::Shuffled
+            && config.seed.is_none()
+            && saved_seed.is_none()
+        {
+            status::say(
+                "Warning: no seed was saved for this shuffled drawing, so it resumes in a different order",
+            );
+        }
+
+        if config.practice && !self.practice_run(&lines[resume_from..], start_pos, config)? {
+            status::say("Drawing cancelled");
+            return Ok(());
+        }
+
+        if !self.countdown(config.countdown) {
+            status::say("Drawing cancelled");
+            return Ok(());
+        }
+
+        status::emit(StatusEvent::DrawingStarted {
+            lines: total_lines,
+            points: total_points,
+            resume_from,
+            seed,
+        });
+        let mut own_progress = None;
+        let pb = match progress {
+            Some(progress) => {
+                progress.start_part(total_lines as u64);
+                progress
+            }
+            None => own_progress.insert(DrawProgress::new(&config.progress, total_lines as u64)),
+        };
+
+        if resume_from > 0 {
+            status::say(format!(
+                "Resuming from line {} of {}",
+                resume_from, total_lines
+            ));
+            pb.set_position(resume_from as u64);
+        }
+
+        // Jitter is applied at the virtual resolution, so scale it to stay in
+        // screen pixels
+        let mut jitter = Jitter::new(config.jitter_amplitude * config.density_multiplier, seed);
+        let opacity = config.opacity.as_ref().zip(tone);
+        let mut opacity_level = 0;
+        if let Some((opacity_keys, _)) = opacity {
+            press_key_times(&mut self.input, opacity_keys.decrease, opacity_keys.steps)?;
+        }
+
+        if let Some(path) = &self.args.render_paths {
+            match render_paths_ordered(&lines, size.0, size.1).save(path) {
+                Ok(()) => info!("Saved path visualization to {}", path),
+                Err(e) => warn!("Failed to save path visualization: {}", e),
+            }
+        }
+
+        // Single points are stray pixels when tracing, but the whole drawing
+        // when stippling, where each one is clicked as a dot
+        let min_line_len = if matches!(config.fill_style, FillStyle::Stipple { .. }) {
+            1
+        } else {
+            2
+        };
+
+        let _timer = timing::stage("drawing");
+        for (index, line) in lines.iter().enumerate().skip(resume_from) {
+            if line.len() < min_line_len {
+                pb.inc();
+                continue;
+            }
+
+            let keys = self.device_state.get_keys();
+
+            if keys.contains(&Keycode::Q) {
+                self.cancel_drawing(&pb, &checkpoint, index, config);
+            }
+
+            if keys.contains(&Keycode::P) {
+                pb.println(format!(
+                    "Paused at line {}. Press 'P' to resume or 'Q' to quit",
+                    index
+                ));
+                if !self.wait_while_paused() {
+                    self.cancel_drawing(&pb, &checkpoint, index, config);
+                }
+
+                // The cursor may have been moved while paused
+                last_pos = None;
+            }
+
+            // Polled once per line, so a held key only counts once
+            let speed_factor = if keys.contains(&Keycode::Equal)
+                || keys.contains(&Keycode::NumpadAdd)
+            {
+                Some(SPEED_STEP)
+            } else if keys.contains(&Keycode::Minus) || keys.contains(&Keycode::NumpadSubtract) {
+                Some(1.0 / SPEED_STEP)
+            } else {
+                None
+            };
+            if let Some(factor) = speed_factor.filter(|_| !speed_key_held) {
+                speed = (speed * factor).clamp(SPEED_RANGE.0, SPEED_RANGE.1);
+                point_delay = config.point_delay.div_f64(speed);
+                line_delay = config.line_delay.div_f64(speed);
+                pb.set_message(format!("speed {:.2}x", speed));
+            }
+            speed_key_held = speed_factor.is_some();
+
+            if let Some((opacity_keys, tone)) = opacity {
+                let target = opacity_keys.level_for(average_brightness(line, tone));
+                let pressed = if target > opacity_level {
+                    press_key_times(
+                        &mut self.input,
+                        opacity_keys.increase,
+                        target - opacity_level,
+                    )
+                } else {
+                    press_key_times(
+                        &mut self.input,
+                        opacity_keys.decrease,
+                        opacity_level - target,
+                    )
+                };
+                if let Err(e) = pressed {
+                    return Err(self.abort_drawing(&pb, &checkpoint, index, config, e));
+                }
+                opacity_level = target;
+            }
+
+            let delays = (point_delay, line_delay);
+            if let Err(e) = draw_stroke(
+                &mut self.input,
+                line,
+                start_pos,
+                last_pos,
+                &mut jitter,
+                delays,
+                config,
+            ) {
+                return Err(self.abort_drawing(&pb, &checkpoint, index, config, e));
+            }
+            pb.inc();
+
+            let end = line[line.len() - 1];
+            last_pos = Some(config.to_screen(start_pos, end.x as f64, end.y as f64));
+
+            if (index + 1) % CHECKPOINT_INTERVAL == 0 {
+                if let Err(e) = checkpoint.save(index + 1) {
+                    warn!("Failed to save drawing checkpoint: {}", e);
+                }
+            }
+        }
+
+        checkpoint.clear();
+        status::emit(StatusEvent::DrawingCompleted {
+            lines: total_lines,
+            points: total_points,
+        });
+        pb.finish_with_message(format!(
+            "Drawing completed! Drew {} paths with {} total points",
+            total_lines, total_points
+        ));
+
+        Ok(())
+    }
+
+    /// Cleans up after simulating input failed at line `index`: lifts the pen
+    /// in case it was down and saves a checkpoint to resume from
+    ///
+    /// # Returns
+    /// `error`, to be passed on
+    fn abort_drawing(
+        &mut self,
+        pb: &DrawProgress,
+        checkpoint: &Checkpoint,
+        index: usize,
+        config: &DrawConfig,
+        error: DrawError,
+    ) -> DrawError {
+        // Best effort, the backend may well keep failing
+        if let Err(e) = pen(&mut self.input, config.pen_action, Direction::Release) {
+            warn!("{}", e);
+        }
+
+        pb.finish_with_message("Failed");
+        status::emit(StatusEvent::DrawingCancelled { line: index });
+        if let Err(e) = checkpoint.save(index) {
+            warn!("Failed to save drawing checkpoint: {}", e);
+        }
+
+        status::say(format!(
+            "Stopped at line {}, run again with '--resume-from {}' to continue",
+            index, index
+        ));
+        error
+    }
+
+    /// Stops the process after a drawing was cancelled at line `index`, saving
+    /// a checkpoint, parking the cursor and telling the user how to pick up
+    /// from there
+    fn cancel_drawing(
+        &mut self,
+        pb: &DrawProgress,
+        checkpoint: &Checkpoint,
+        index: usize,
+        config: &DrawConfig,
+    ) -> ! {
+        pb.finish_with_message("Cancelled");
+        self.park_cursor(config);
+        status::emit(StatusEvent::DrawingCancelled { line: index });
+        if let Err(e) = checkpoint.save(index) {
+            warn!("Failed to save drawing checkpoint: {}", e);
+        }
+
+        status::say(format!(
+            "Cancelled at line {}, run again with '--resume-from {}' to continue",
+            index, index
+        ));
+        std::process::exit(0);
+    }
+
+    /// Moves the cursor along every line with the pen up, pausing only
+    /// [`PRACTICE_DELAY`] after each point whatever the drawing speed, so the
+    /// user can see where the drawing will land, then asks
+    /// whether to draw for real
+    ///
+    /// # Returns
+    /// `true` if the drawing should go ahead, `false` if the user quit during
+    /// the sweep or declined afterwards
+    ///
+    /// # Errors
+    /// Fails if moving the cursor keeps failing, see [`sweep`], or if the
+    /// answer can't be read.
+    fn practice_run(
+        &mut self,
+        lines: &[Vec<Point>],
+        start_pos: (i32, i32),
+        config: &DrawConfig,
+    ) -> Result<bool, DrawError> {
+        status::say("Practice run: sweeping over the drawing with the pen up, press 'Q' to stop");
+
+        for line in lines {
+            if self.device_state.get_keys().contains(&Keycode::Q) {
+                self.wait_for_key_release(Keycode::Q);
+                return Ok(false);
+            }
+
+            let points = line
+                .iter()
+                .map(|point| config.to_screen(start_pos, point.x as f64, point.y as f64));
+            sweep(&mut self.input, points, PRACTICE_DELAY)?;
+        }
+
+        Ok(prompt_confirmation(
+            "Practice run finished. Draw for real?",
+        )?)
+    }
+
+    /// Counts down before drawing starts, letting the user start right away with
+    /// 'D' or cancel with 'Q'
+    ///
+    /// # Returns
+    /// `true` if drawing should start, `false` if it was cancelled
+    fn countdown(&self, duration: Duration) -> bool {
+        self.wait_for_key_release(Keycode::D);
+        status::say("Keep your cursor still! Press 'D' to start now or 'Q' to cancel");
+
+        // The ticking countdown rewrites its own line, which only makes sense
+        // for human output
+        let json = status::is_json();
+        let started = Instant::now();
+        let mut shown = None;
+
+        while let Some(remaining) = duration.checked_sub(started.elapsed()) {
+            let seconds = remaining.as_secs() + 1;
+            if shown != Some(seconds) && !json {
+                print!("\rDrawing will start in {} seconds... ", seconds);
+                let _ = std::io::stdout().flush();
+                shown = Some(seconds);
+            }
+
+            let keys = self.device_state.get_keys();
+            if keys.contains(&Keycode::Q) {
+                if !json {
+                    println!();
+                }
+                return false;
+            } else if keys.contains(&Keycode::D) {
+                self.wait_for_key_release(Keycode::D);
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        if !json {
+            println!();
+        }
+        true
+    }
+
+    /// Warns that a drawing exceeds the point limit and asks whether to draw it
+    /// anyway
+    fn confirm_large_drawing(
+        points: usize,
+        lines: usize,
+        max_points: usize,
+        config: &DrawConfig,
+    ) -> InquireResult<bool> {
+        status::say(format!(
+            "This drawing has {} points across {} lines, more than the limit of {}",
+            points, lines, max_points
+        ));
+        status::say(format!(
+            "It would take at least {:.0?}; a lower accuracy or larger brush size samples fewer points",
+            config.estimate_duration(points, lines)
+        ));
+
+        prompt_confirmation("Draw it anyway?")
+    }
+
+    /// Waits for the user to start drawing with 'D' (previewing the region
+    /// first if enabled) or to quit with 'Q'
+    ///
+    /// JSON mode starts right away, as if 'D' was pressed.
+    ///
+    /// # Returns
+    /// `true` if drawing should start, `false` if the user quit
+    ///
+    /// # Errors
+    /// Fails if the region preview can't move the cursor or read the answer,
+    /// see [`preview_region`](Self::preview_region).
+    fn wait_for_drawing_command(
+        &mut self,
+        start_pos: (i32, i32),
+        end_pos: (i32, i32),
+        config: &DrawConfig,
+    ) -> Result<bool, DrawError> {
+        let mut backoff = PollBackoff::default();
+        let mut last_mouse = self.device_state.get_mouse().coords;
+
+        loop {
+            let keys = self.device_state.get_keys();
+            if status::is_json() || keys.contains(&Keycode::D) {
+                if config.preview_region && !self.preview_region(start_pos, end_pos)? {
+                    status::say("Region rejected. Quitting!");
+                    return Ok(false);
+                }
+
+                return Ok(true);
+            } else if keys.contains(&Keycode::Q) {
+                status::say("Quitting!");
+                return Ok(false);
+            }
+
+            backoff.wait(self.input_active(&keys, &mut last_mouse));
+        }
+    }
+
+    /// Runs every processing method on the image, saving each result as
+    /// `<name>_<method>.png` next to it and printing per-method statistics
+    fn compare_methods(&self) {
+        let Some(image_path) = self.select_image() else {
+            status::say("No image selected. Exiting...");
+            return;
+        };
+
+        let results = match ImageProcessor::process_all_methods(
+            &image_path,
+            &ProcessingOptions::default(),
+            self.args.background(),
+        ) {
+            Ok(results) => results,
+            Err(e) => {
+                status::say(format!("Error processing image: {}", e));
+                return;
+            }
+        };
+
+        let path = Path::new(&image_path);
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+
+        for (method, binary_img, elapsed) in results {
+            let black_pixels = binary_img.pixels().filter(|p| p[0] == 0).count();
+            let method_name = format!("{:?}", method).to_lowercase();
+            let output = path.with_file_name(format!("{}_{}.png", stem, method_name));
+            let preview = ImageBuffer::from_fn(binary_img.width(), binary_img.height(), |x, y| {
+                Luma([if binary_img.get_pixel(x, y)[0] == 0 {
+                    0u8
+                } else {
+                    255u8
+                }])
+            });
+
+            match preview.save(&output) {
+                Ok(()) => status::say(format!(
+                    "{:?}: {} black pixels in {:.2?} -> {}",
+                    method,
+                    black_pixels,
+                    elapsed,
+                    output.display()
+                )),
+                Err(e) => status::say(format!("{:?}: failed to save result: {}", method, e)),
+            }
+        }
+    }
+
+    /// Loads, processes and draws an image, or runs the mode picked in the
+    /// arguments instead, stopping at the first cancelled prompt
+    fn execute(&mut self) {
+        if let Err(e) = self.try_execute() {
+            status::say(format!("Failed to get user input: {}", e));
+        }
+    }
+
+    /// Does the work of [`execute`](Self::execute)
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn try_execute(&mut self) -> InquireResult<()> {
+        if self.args.compare_methods {
+            self.compare_methods();
+            return Ok(());
+        }
+
+        if self.args.test_pattern {
+            return self.draw_test_pattern();
+        }
+
+        let (mut frames, image_path) = match self.load_image() {
+            Ok(Some(loaded)) => loaded,
+            Ok(None) => {
+                status::say("No image selected. Exiting...");
+                return Ok(());
+            }
+            Err(e) => {
+                status::say(format!("Error loading image: {}", e));
+                return Ok(());
+            }
+        };
+        status::emit(StatusEvent::ImageLoaded {
+            width: frames[0].width(),
+            height: frames[0].height(),
+        });
+
+        if let Some(path) = image_path
+            .as_deref()
+            .filter(|path| ImageLoader::has_extension(path, "svg"))
+        {
+            if prompt_confirmation("Draw the SVG's paths directly instead of rasterizing it?")? {
+                self.draw_svg_paths(path)?;
+                timing::report();
+                return Ok(());
+            }
+        }
+
+        if frames.len() > 1
+            && prompt_confirmation(&format!(
+                "The GIF has {} frames. Draw each of them, one after another?",
+                frames.len()
+            ))?
+        {
+            self.draw_frames(frames, image_path)?;
+            timing::report();
+            return Ok(());
+        }
+        let img = ImageLoader::flatten_alpha(frames.swap_remove(0), self.args.background());
+
+        if prompt_confirmation("Draw in color, one pass per palette color?")? {
+            let colors = Self::prompt_value("Number of colors", 4usize)?;
+            let passes = {
+                let _timer = timing::stage("quantize");
+                without_background(quantize(&img, colors), self.args.background())
+            };
+
+            self.draw_color_passes(passes)?;
+            timing::report();
+            return Ok(());
+        }
+
+        let processing = match self.processing.clone() {
+            Some(processing) => processing,
+            None => Self::prompt_processing()?,
+        };
+        if let Some(path) = &self.args.dump_histogram {
+            match Self::dump_histogram(&img, path, processing.0, &processing.1) {
+                Ok(()) => status::say(format!("Saved the grayscale histogram to {}", path)),
+                Err(e) => warn!("Failed to save histogram: {}", e),
+            }
+        }
+
+        let cache_key = image_path
+            .as_deref()
+            .filter(|_| !self.args.no_cache)
+            .map(|path| (path, 0));
+        self.process_and_draw(&img, cache_key, &processing, &mut None, None);
+
+        timing::report();
+        Ok(())
+    }
+
+    /// Saves the grayscale histogram of `img` at the processing bit depth, after
+    /// preprocessing, as CSV, marking the global threshold of the processing
+    /// method if it has one
+    fn dump_histogram(
+        img: &DynamicImage,
+        path: &str,
+        processing_method: ImageProcessingMethod,
+        processing_options: &ProcessingOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let threshold =
+            ImageProcessor::global_threshold(img, processing_method, processing_options);
+
+        let gray_img = ImageProcessor::preprocess(img, processing_options);
+
+        match processing_options.bit_depth {
+            BitDepth::Sixteen => write_histogram_csv(
+                path,
+                &compute_histogram(&gray_img),
+                threshold.map(usize::from),
+            ),
+            BitDepth::Eight => write_histogram_csv(
+                path,
+                &compute_histogram(&DynamicImage::ImageLuma16(gray_img).to_luma8()),
+                threshold.map(|threshold| threshold as usize / 257),
+            ),
+        }
+    }
+
+    /// Asks for the processing method and its options
+    fn prompt_processing() -> InquireResult<(ImageProcessingMethod, ProcessingOptions)> {
+        let processing_method = choose(
+            ImageProcessingMethod::choice,
+            "Please select a method for processing the image",
+        )?;
+        let mut processing_options = Self::configure_processing(processing_method)?;
+        processing_options.combine = Self::prompt_combine(processing_options.bit_depth)?;
+
+        Ok((processing_method, processing_options))
+    }
+
+    /// Asks whether the binarization should be combined with a second
+    /// processing method and, if so, which one, with which options and how
+    ///
+    /// The second method gets its own threshold and tuning options, processed
+    /// at the first method's `bit_depth`.
+    fn prompt_combine(
+        bit_depth: BitDepth,
+    ) -> InquireResult<Option<(ImageProcessingMethod, CombineOp, Box<ProcessingOptions>)>> {
+        if !prompt_confirmation("Combine with a second processing method?")? {
+            return Ok(None);
+        }
+
+        let method = choose(
+            ImageProcessingMethod::choice,
+            "Please select the second processing method",
+        )?;
+        let options = Self::configure_method(method, bit_depth)?;
+        let op = choose(CombineOp::choice, "How should the two results be combined?")?;
+
+        Ok(Some((method, op, Box::new(options))))
+    }
+
+    /// Binarizes an image, reusing a cached result for `cache_key` (the source
+    /// path and frame index) when available and caching fresh results otherwise
+    fn process_with_cache<T>(
+        &self,
+        img: &DynamicImage,
+        cache_key: Option<(&str, usize)>,
+        processing_method: ImageProcessingMethod,
+        processing_options: &ProcessingOptions,
+        process: ProcessFn<T>,
+        from_cached: fn(&DynamicImage) -> BinaryImage<T>,
+    ) -> Result<BinaryImage<T>, Box<dyn std::error::Error>>
+    where
+        T: Primitive,
+        BinaryImage<T>: Into<DynamicImage>,
+    {
+        let cached = cache_key.and_then(|(path, frame)| {
+            ProcessingCache::load(
+                path,
+                frame,
+                self.args.background(),
+                processing_method,
+                processing_options,
+            )
+        });
+
+        if let Some(cached) = cached {
+            info!("Reusing cached processed image");
+            return Ok(from_cached(&cached));
+        }
+
+        let bw_img = process(img, processing_method, processing_options)?;
+
+        if let Some((path, frame)) = cache_key {
+            let stored = ProcessingCache::store(
+                path,
+                frame,
+                self.args.background(),
+                processing_method,
+                processing_options,
+                &bw_img.clone().into(),
+            );
+
+            if let Err(e) = stored {
+                warn!("Failed to cache processed image: {}", e);
+            }
+        }
+
+        Ok(bw_img)
+    }
+
+    /// Binarizes `img`, reusing the cache entry of `cache_key` (the source path
+    /// and frame index) if any, and draws it, see [`Self::draw_processed`]
+    ///
+    /// # Returns
+    /// The region last drawn into, or `None` if nothing was drawn
+    fn process_and_draw(
+        &mut self,
+        img: &DynamicImage,
+        cache_key: Option<(&str, usize)>,
+        (processing_method, processing_options): &(ImageProcessingMethod, ProcessingOptions),
+        plan: &mut Option<DrawPlan>,
+        region: Option<ScreenRegion>,
+    ) -> Option<ScreenRegion> {
+        let drawn = match processing_options.bit_depth {
+            BitDepth::Sixteen => self
+                .process_with_cache(
+                    img,
+                    cache_key,
+                    *processing_method,
+                    processing_options,
+                    ImageProcessor::process_dynamic_image,
+                    DynamicImage::to_luma16,
+                )
+                .map(|bw_img| self.draw_processed(&bw_img, img, plan, region)),
+            BitDepth::Eight => self
+                .process_with_cache(
+                    img,
+                    cache_key,
+                    *processing_method,
+                    processing_options,
+                    ImageProcessor::process_dynamic_image_8bit,
+                    DynamicImage::to_luma8,
+                )
+                .map(|bw_img| self.draw_processed(&bw_img, img, plan, region)),
+        };
+
+        drawn.unwrap_or_else(|e| {
+            status::say(format!("Error processing image: {}", e));
+            None
+        })
+    }
+
+    /// Checks that a binarized image's threshold didn't fail, then asks for the
+    /// settings to draw it with
+    ///
+    /// # Returns
+    /// The settings, or `None` if the user gave up on the image
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn plan_drawing<T: Primitive + Send + Sync + 'static>(
+        &self,
+        bw_img: &BinaryImage<T>,
+    ) -> InquireResult<Option<DrawPlan>> {
+        let mask = match self.load_mask(bw_img.dimensions()) {
+            Ok(mask) => mask,
+            Err(e) => {
+                status::say(format!("Error loading mask: {}", e));
+                return Ok(None);
+            }
+        };
+        let masked = mask
+            .as_ref()
+            .map(|mask| ImageProcessor::apply_mask(bw_img, mask));
+        let bw_img = masked.as_ref().unwrap_or(bw_img);
+
+        let black_fraction = ImageProcessor::black_fraction(bw_img);
+        if threshold_likely_failed(black_fraction) {
+            status::say(format!(
+                "Warning: {:.2}% of the processed image is black, so the threshold likely failed",
+                black_fraction * 100.0
+            ));
+            status::say("Try a different processing method or a manual threshold");
+
+            if !prompt_confirmation("Continue anyway?")? {
+                return Ok(None);
+            }
+        }
+
+        // How much each accuracy level draws depends on the region's size, so
+        // the region is picked first to show it
+        let region = if self.args.accuracy_stats {
+            status::say("Move your cursor to select the region where you want to draw.");
+            Some(self.capture_screen_region(bw_img.dimensions())?)
+        } else {
+            None
+        };
+        let labels = region
+            .map(|region| move |scaling_mode| Self::accuracy_labels(bw_img, region, scaling_mode));
+        let (scaling_mode, config) = self.prompt_draw_config(
+            bw_img.dimensions(),
+            labels.as_ref().map(|labels| labels as AccuracyLabels),
+        )?;
+
+        let crop = match Self::prompt_crop(bw_img.dimensions())? {
+            Some(crop) => Some(crop),
+            None => Self::prompt_symmetric_half(bw_img)?,
+        };
+
+        Ok(Some(DrawPlan {
+            scaling_mode,
+            config,
+            crop,
+            mask,
+            region,
+        }))
+    }
+
+    /// Draws a binarized image with the settings of `plan`, asking for them
+    /// first if there is no plan yet
+    ///
+    /// `source` is the image `bw_img` was binarized from, used by settings that
+    /// depend on its tones. Each tile is drawn into `region`, or a region the
+    /// user picks for it if `None`.
+    ///
+    /// # Returns
+    /// The region last drawn into, or `None` if the user quit
+    fn draw_processed<T: Primitive + Send + Sync + 'static>(
+        &mut self,
+        bw_img: &BinaryImage<T>,
+        source: &DynamicImage,
+        plan: &mut Option<DrawPlan>,
+        region: Option<ScreenRegion>,
+    ) -> Option<ScreenRegion> {
+        status::say("Image processed successfully!");
+
+        if plan.is_none() {
+            match self.plan_drawing(bw_img) {
+                Ok(Some(planned)) => *plan = Some(planned),
+                Ok(None) => return None,
+                Err(e) => {
+                    status::say(format!("Failed to get user input: {}", e));
+                    return None;
+                }
+            }
+        }
+        let plan = plan.as_ref()?;
+        let DrawPlan {
+            config,
+            crop,
+            mask,
+            region: planned_region,
+            ..
+        } = plan;
+        let region = region.or(*planned_region);
+
+        let masked = mask
+            .as_ref()
+            .map(|mask| ImageProcessor::apply_mask(bw_img, mask));
+        let bw_img = masked.as_ref().unwrap_or(bw_img);
+        // Masked-out areas of the tone image turn white, so shading skips them
+        let tone = config.needs_tone().then(|| {
+            let tone = source.to_luma16();
+            match mask {
+                Some(mask) => ImageProcessor::apply_mask(&tone, mask),
+                None => tone,
+            }
+        });
+        let tiles = if config.tile_grid == (1, 1) {
+            vec![*crop]
+        } else {
+            let (width, height) = bw_img.dimensions();
+            let bounds = crop
+                .and_then(|crop| ImageScaler::crop_bounds((width, height), crop).ok())
+                .unwrap_or((0, 0, width, height));
+
+            ImageScaler::tile_crops(bounds, config.tile_grid)
+                .into_iter()
+                .map(Some)
+                .collect()
+        };
+
+        let total_tiles = tiles.len();
+        // One progress bar counts the lines of every tile
+        let mut progress = (total_tiles > 1)
+            .then(|| DrawProgress::for_parts(&config.progress, total_tiles as u64));
+        let mut drawn = None;
+        for (index, tile) in tiles.into_iter().enumerate() {
+            if total_tiles > 1 {
+                status::emit(StatusEvent::TileStarted {
+                    tile: index + 1,
+                    total: total_tiles,
+                });
+                status::say(format!(
+                    "Tile {}/{}: pan the canvas to where this tile should go",
+                    index + 1,
+                    total_tiles
+                ));
+            }
+
+            drawn = self.draw_region(bw_img, tone.as_ref(), tile, region, plan, progress.as_mut());
+            if drawn.is_none() {
+                if total_tiles > 1 {
+                    status::say(format!(
+                        "Stopped after drawing {}/{} tiles",
+                        index, total_tiles
+                    ));
+                }
+                break;
+            }
+
+            if total_tiles > 1 {
+                status::say(format!("Finished tile {}/{}", index + 1, total_tiles));
+            }
+        }
+
+        drawn
+    }
+
+    /// Draws each palette color's mask as a separate pass into a single region,
+    /// waiting between passes so the brush color can be changed
+    ///
+    /// Each pass only keeps what the `--mask` image, if any, lets through.
+    /// Otherwise masks are traced as they are: color passes aren't cropped or
+    /// split into tiles, and fill styles and settings that need the grayscale
+    /// source fall back to tracing.
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn draw_color_passes(&mut self, passes: Vec<(Rgb<u8>, GrayImage)>) -> InquireResult<()> {
+        let Some(size) = passes.first().map(|(_, mask)| mask.dimensions()) else {
+            status::say("No colors other than the background found to draw!");
+            return Ok(());
+        };
+        let passes: Vec<(Rgb<u8>, GrayImage)> = match self.load_mask(size) {
+            Ok(Some(mask)) => passes
+                .into_iter()
+                .map(|(color, pass)| (color, ImageProcessor::apply_mask(&pass, &mask)))
+                .collect(),
+            Ok(None) => passes,
+            Err(e) => {
+                status::say(format!("Error loading mask: {}", e));
+                return Ok(());
+            }
+        };
+
+        let (scaling_mode, config) = self.prompt_draw_config(size, None)?;
+        if config.tile_grid != (1, 1) {
+            status::say("Color passes are drawn into a single region, ignoring the tile grid");
+        }
+        if config.needs_tone() {
+            status::say(
+                "Color passes are traced from their masks, ignoring settings that need the grayscale source",
+            );
+        }
+
+        status::say("Move your cursor to select the region where you want to draw.");
+        let (start_pos, end_pos) = self.capture_screen_region(size)?;
+        let virtual_end = config.virtual_end(start_pos, end_pos);
+
+        let total_passes = passes.len();
+        for (index, (color, mask)) in passes.into_iter().enumerate() {
+            let scaled_mask = match ImageScaler::scale_image_to_region(
+                &mask,
+                None,
+                start_pos,
+                virtual_end,
+                scaling_mode,
+            ) {
+                Ok(scaled_mask) => scaled_mask,
+                Err(e) => {
+                    status::say(format!("Error scaling image: {}", e));
+                    return Ok(());
+                }
+            };
+
+            let [r, g, b] = color.0;
+            status::say(format!(
+                "Pass {}/{}: select the color #{:02x}{:02x}{:02x} in your app, then press 'D' to draw it or 'Q' to quit",
+                index + 1,
+                total_passes,
+                r,
+                g,
+                b
+            ));
+
+            // The frame goes with the last pass only
+            let frame = (config.draw_frame && index + 1 == total_passes)
+                .then(|| Self::frame_size(start_pos, end_pos, &config));
+            let drawn = match self.wait_for_drawing_command(start_pos, end_pos, &config) {
+                Ok(true) => self.draw_image(&scaled_mask, None, start_pos, frame, &config, None),
+                Ok(false) => return Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = drawn {
+                status::say(format!("Drawing failed: {}", e));
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws the outlines of the paths of the SVG at `path` as they are,
+    /// flattening curves with [`svg_to_polylines`] instead of tracing a raster
+    ///
+    /// The paths are fitted into the selected region keeping their aspect
+    /// ratio, whatever the scaling mode.
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn draw_svg_paths(&mut self, path: &str) -> InquireResult<()> {
+        let tree = match ImageLoader::load_svg_tree(path) {
+            Ok(tree) => tree,
+            Err(e) => {
+                status::say(format!("Error loading image: {}", e));
+                return Ok(());
+            }
+        };
+        let (svg_width, svg_height) = (tree.size().width(), tree.size().height());
+        let size = (svg_width.ceil() as u32, svg_height.ceil() as u32);
+
+        let tolerance: f32 = Self::prompt_value(
+            "Largest distance (in pixels) between a curve and the lines drawn for it",
+            0.25,
+        )?;
+        let (_, config) = self.prompt_draw_config(size, None)?;
+
+        status::say("Move your cursor to select the region where you want to draw.");
+        let (start_pos, end_pos) = self.capture_screen_region(size)?;
+        let (width, height) = Self::frame_size(start_pos, end_pos, &config);
+
+        let scale = (width as f32 / svg_width).min(height as f32 / svg_height);
+        let offset = Point::new(
+            ((width as f32 - svg_width * scale) / 2.0) as i32,
+            ((height as f32 - svg_height * scale) / 2.0) as i32,
+        );
+        let mut lines = {
+            let _timer = timing::stage("svg flattening");
+            svg_to_polylines(&tree, scale, tolerance * config.density_multiplier as f32)
+        };
+        for point in lines.iter_mut().flatten() {
+            *point = Point::new(point.x + offset.x, point.y + offset.y);
+        }
+        info!("Flattened the SVG into {} paths", lines.len());
+        status::emit(StatusEvent::PathsGenerated {
+            lines: lines.len(),
+            points: lines.iter().map(Vec::len).sum(),
+        });
+
+        if config.draw_frame {
+            let inset = (config.frame_inset as f64 * config.density_multiplier).round() as u32;
+            lines.extend(frame_lines(width, height, inset));
+        }
+
+        if lines.is_empty() {
+            status::say("Nothing found to draw!");
+            return Ok(());
+        }
+
+        status::say("Ready to draw! Press 'D' to start drawing or 'Q' to quit");
+        let drawn = match self.wait_for_drawing_command(start_pos, end_pos, &config) {
+            Ok(true) => {
+                let drawn = self.draw_lines(lines, None, (width, height), start_pos, &config, None);
+                self.park_cursor(&config);
+                drawn
+            }
+            Ok(false) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = drawn {
+            status::say(format!("Drawing failed: {}", e));
+        }
+
+        Ok(())
+    }
+
+    /// Draws each frame of an animation into a single region, waiting between
+    /// frames so a new frame or layer can be added in the target app
+    ///
+    /// Every frame goes through the same processing, caching and drawing as a
+    /// still image, with the settings and region picked for the first frame.
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn draw_frames(
+        &mut self,
+        frames: Vec<DynamicImage>,
+        image_path: Option<String>,
+    ) -> InquireResult<()> {
+        let processing = match self.processing.clone() {
+            Some(processing) => processing,
+            None => Self::prompt_processing()?,
+        };
+        let cache_path = image_path.filter(|_| !self.args.no_cache);
+
+        let mut plan = None;
+        let mut region = None;
+        let total_frames = frames.len();
+        for (index, img) in frames.into_iter().enumerate() {
+            status::emit(StatusEvent::FrameStarted {
+                frame: index + 1,
+                total: total_frames,
+            });
+            status::say(format!(
+                "Frame {}/{}: switch to a new frame or layer in your app before drawing it",
+                index + 1,
+                total_frames
+            ));
+
+            let img = ImageLoader::flatten_alpha(img, self.args.background());
+            let cache_key = cache_path.as_deref().map(|path| (path, index));
+            match self.process_and_draw(&img, cache_key, &processing, &mut plan, region) {
+                Some(drawn) => region = Some(drawn),
+                None => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Labels of the [`DrawingAccuracy`] choices with roughly how many points
+    /// and lines each level draws of `bw_img` scaled into `region`
+    ///
+    /// Paths are extracted with grid sampling at each level's resolution. The
+    /// crop and the advanced settings are only asked for afterwards, so these
+    /// are estimates rather than exact counts.
+    fn accuracy_labels<T: Primitive + Send + Sync + 'static>(
+        bw_img: &BinaryImage<T>,
+        (start_pos, end_pos): ScreenRegion,
+        scaling_mode: ScalingMode,
+    ) -> Vec<String> {
+        let _timer = timing::stage("accuracy stats");
+
+        DrawingAccuracy::ALL
+            .into_iter()
+            .map(|accuracy| {
+                let config = DrawConfig {
+                    step: get_step(accuracy),
+                    sampling: SamplingPattern::Grid,
+                    density_multiplier: get_density_multiplier(accuracy, bw_img.dimensions()),
+                    ..DrawConfig::default()
+                };
+                let virtual_end = config.virtual_end(start_pos, end_pos);
+
+                match ImageScaler::scale_image_to_region(
+                    bw_img,
+                    None,
+                    start_pos,
+                    virtual_end,
+                    scaling_mode,
+                ) {
+                    Ok(scaled) => {
+                        let lines = extract_paths(&scaled, &config);
+                        let points: usize = lines.iter().map(Vec::len).sum();
+
+                        format!("{:?} (~{} points, {} lines)", accuracy, points, lines.len())
+                    }
+                    Err(_) => format!("{:?}", accuracy),
+                }
+            })
+            .collect()
+    }
+
+    /// Asks for the scaling mode and every drawing setting for an image of
+    /// `image_size` pixels, or reuses past settings with `--repeat-last` and
+    /// `--pick-recent`
+    ///
+    /// The accuracy choices are shown with `accuracy_labels` if given. The
+    /// settings are recorded in the [`History`] either way.
+    fn prompt_draw_config(
+        &self,
+        image_size: (u32, u32),
+        accuracy_labels: Option<AccuracyLabels>,
+    ) -> InquireResult<(ScalingMode, DrawConfig)> {
+        let (scaling_mode, config) = match self.recent_draw_config()? {
+            Some(recent) => recent,
+            None => self.prompt_new_draw_config(image_size, accuracy_labels)?,
+        };
+
+        let entry = HistoryEntry {
+            scaling_mode,
+            config: config.clone(),
+        };
+        if let Err(e) = self.history.record(entry) {
+            warn!("Failed to save the drawing settings to the history: {}", e);
+        }
+
+        Ok((scaling_mode, config))
+    }
+
+    /// Past drawing settings picked with `--repeat-last` or `--pick-recent`,
+    /// `None` if neither was given or there are none
+    ///
+    /// # Errors
+    /// Fails if picking the settings is cancelled.
+    fn recent_draw_config(&self) -> InquireResult<Option<(ScalingMode, DrawConfig)>> {
+        if !self.args.repeat_last && !self.args.pick_recent {
+            return Ok(None);
+        }
+
+        let mut entries = match self.history.load() {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to load the drawing settings history: {}", e);
+                return Ok(None);
+            }
+        };
+        if entries.is_empty() {
+            status::say("No previous drawing settings yet, asking for new ones");
+            return Ok(None);
+        }
+
+        let index = if self.args.pick_recent {
+            let summaries: Vec<String> = entries.iter().map(HistoryEntry::summary).collect();
+            status::require_interactive("Which settings should be reused?");
+            Select::new("Which settings should be reused?", summaries)
+                .raw_prompt()?
+                .index
+        } else {
+            0
+        };
+
+        let entry = entries.swap_remove(index);
+        status::say(format!("Reusing settings: {}", entry.summary()));
+
+        // The clip polygon was drawn over the past run's region, which this
+        // run's doesn't have to match
+        if entry.config.clip_polygon.is_some() {
+            status::say(
+                "The clipping polygon of those settings is not reused, draw a new one if needed",
+            );
+        }
+
+        // Progress output follows this run's flags rather than the past run's
+        let config = DrawConfig {
+            progress: self.args.progress_config(),
+            clip_polygon: None,
+            ..entry.config
+        };
+
+        Ok(Some((entry.scaling_mode, config)))
+    }
+
+    fn prompt_new_draw_config(
+        &self,
+        image_size: (u32, u32),
+        accuracy_labels: Option<AccuracyLabels>,
+    ) -> InquireResult<(ScalingMode, DrawConfig)> {
+        let scaling_mode = self.select_scaling_mode()?;
+        let message = "Please select a desired accuracy for the drawing";
+        // JSON mode has no menu to show the labels in
+        let accuracy = match accuracy_labels.filter(|_| !status::is_json()) {
+            Some(labels) => Select::new(message, labels(scaling_mode))
+                .raw_prompt()
+                .map(|choice| DrawingAccuracy::ALL[choice.index]),
+            None => choose(DrawingAccuracy::choice, message),
+        }?;
+        let step = get_step(accuracy);
+        let density_multiplier = get_density_multiplier(accuracy, image_size);
+        if density_multiplier > 1.0 {
+            info!(
+                "Super-sampling the {}x{} image at {}x the region's resolution",
+                image_size.0, image_size.1, density_multiplier
+            );
+        }
+        let sampling = choose(SamplingPattern::choice, "How should the image be sampled?")?;
+        let drawing_speed = self.prompt_speed("How fast should the image be drawn?")?;
+        let fill_style = Self::prompt_fill_style()?;
+        let line_order = choose(
+            LineOrder::choice,
+            "What order should each line be drawn in?",
+        )?;
+        let preview_region = prompt_confirmation("Preview the drawing region before drawing?")?;
+        let practice = prompt_confirmation(
+            "Sweep over the whole drawing with the pen up before drawing for real?",
+        )?;
+        let draw_frame = prompt_confirmation("Draw a frame around the region?")?;
+        let frame_inset = if draw_frame {
+            Self::prompt_value("Frame inset (in pixels) from the region's border", 0)?
+        } else {
+            0
+        };
+
+        let mut config = DrawConfig {
+            point_delay: drawing_speed,
+            line_delay: drawing_speed,
+            step,
+            sampling,
+            interpolation_density: get_interpolation_density(accuracy),
+            density_multiplier,
+            fill_style,
+            line_order,
+            preview_region,
+            practice,
+            draw_frame,
+            frame_inset,
+            progress: self.args.progress_config(),
+            ..DrawConfig::default()
+        };
+        Self::configure_advanced(&mut config)?;
+
+        Ok((scaling_mode, config))
+    }
+
+    /// Picks a screen region for the (optionally cropped) image unless one is
+    /// given, then scales and draws it with the scaling mode and settings of
+    /// `plan` once the user is ready
+    ///
+    /// `progress` is the progress of a drawing the image is one part of, if
+    /// any, see [`draw_lines`](Self::draw_lines).
+    ///
+    /// # Returns
+    /// The region the image was drawn into, or `None` if the user quit instead
+    fn draw_region<T: Primitive + Send + Sync + 'static>(
+        &mut self,
+        bw_img: &BinaryImage<T>,
+        tone: Option<&ToneImage>,
+        crop: Option<(Point, Point)>,
+        region: Option<ScreenRegion>,
+        plan: &DrawPlan,
+        progress: Option<&mut DrawProgress>,
+    ) -> Option<ScreenRegion> {
+        let DrawPlan {
+            scaling_mode,
+            config,
+            ..
+        } = plan;
+        let scaling_mode = *scaling_mode;
+        let draw_size = crop
+            .and_then(|crop| ImageScaler::crop_bounds(bw_img.dimensions(), crop).ok())
+            .map_or(bw_img.dimensions(), |(_, _, width, height)| (width, height));
+
+        let selected = match region {
+            Some(region) => region,
+            None => {
+                status::say("Move your cursor to select the region where you want to draw.");
+                match self.capture_screen_region(draw_size) {
+                    Ok(region) => region,
+                    Err(e) => {
+                        status::say(format!("Failed to get user input: {}", e));
+                        return None;
+                    }
+                }
+            }
+        };
+        let (start_pos, end_pos) = selected;
+        // Images are scaled to the virtual resolution, and mapped back onto the
+        // region while drawing
+        let virtual_end = config.virtual_end(start_pos, end_pos);
+        let scaled = {
+            let _timer = timing::stage("scale");
+            ImageScaler::scale_image_to_region(bw_img, crop, start_pos, virtual_end, scaling_mode)
+                .and_then(|scaled_img| {
+                    let scaled_tone = tone
+                        .map(|tone| {
+                            ImageScaler::scale_image_to_region(
+                                tone,
+                                crop,
+                                start_pos,
+                                virtual_end,
+                                scaling_mode,
+                            )
+                        })
+                        .transpose()?;
+
+                    Ok((scaled_img, scaled_tone))
+                })
+        };
+        let (scaled_img, scaled_tone) = match scaled {
+            Ok(scaled) => scaled,
+            Err(e) => {
+                status::say(format!("Error scaling image: {}", e));
+                return None;
+            }
+        };
+
+        // Fitting one side leaves the other free, so the drawing's real extent
+        // replaces the selected one
+        let end_pos = match scaling_mode {
+            ScalingMode::FitWidth | ScalingMode::FitHeight => {
+                let (width, height) = scaled_img.dimensions();
+                let end = config.to_screen(start_pos, width as f64, height as f64);
+                info!(
+                    "Drawing spans ({}, {}) -> ({}, {})",
+                    start_pos.0, start_pos.1, end.0, end.1
+                );
+                end
+            }
+            _ => end_pos,
+        };
+
+        status::say("Ready to draw! Press 'D' to start drawing or 'Q' to quit");
+        status::say("Hotkeys work from any window; press 'P' while drawing to pause");
+        let frame = config
+            .draw_frame
+            .then(|| Self::frame_size(start_pos, end_pos, config));
+        let drawn = match self.wait_for_drawing_command(start_pos, end_pos, config) {
+            Ok(true) => self.draw_image(
+                &scaled_img,
+                scaled_tone.as_ref(),
+                start_pos,
+                frame,
+                config,
+                progress,
+            ),
+            Ok(false) => return None,
+            Err(e) => Err(e),
+        };
+        match drawn {
+            Ok(()) => Some(selected),
+            Err(e) => {
+                status::say(format!("Drawing failed: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Size of the screen region from `start_pos` to `end_pos` at the virtual
+    /// resolution
+    fn frame_size(start_pos: (i32, i32), end_pos: (i32, i32), config: &DrawConfig) -> (u32, u32) {
+        let virtual_end = config.virtual_end(start_pos, end_pos);
+
+        (
+            (virtual_end.0 - start_pos.0).unsigned_abs() + 1,
+            (virtual_end.1 - start_pos.1).unsigned_abs() + 1,
+        )
+    }
+
+    /// Asks for a drawing speed until one is confirmed, see [`get_speed`]
+    ///
+    /// Declining the confirmation asks for another speed, while cancelling a
+    /// prompt is returned as an error.
+    fn prompt_speed(&self, message: &str) -> InquireResult<Duration> {
+        loop {
+            let speed = choose(DrawingSpeed::choice, message)?;
+
+            match get_speed(speed, self.args.assume_yes)? {
+                Some(delay) => return Ok(delay),
+                None => status::say(
+                    "Cancelled the Universe Annihilating speed, please pick another speed",
+                ),
+            }
+        }
+    }
+
+    /// Draws a calibration pattern into a selected region, bypassing image
+    /// loading and processing
+    ///
+    /// # Errors
+    /// Fails if a prompt is cancelled.
+    fn draw_test_pattern(&mut self) -> InquireResult<()> {
+        let drawing_speed = self.prompt_speed("How fast should the pattern be drawn?")?;
+        let config = DrawConfig {
+            point_delay: drawing_speed,
+            line_delay: drawing_speed,
+            progress: self.args.progress_config(),
+            ..DrawConfig::default()
+        };
+
+        status::say("Move your cursor to select the region where you want to draw.");
+        let (start_pos, end_pos) = self.capture_screen_region((1, 1))?;
+        let size = (
+            (end_pos.0 - start_pos.0) as u32 + 1,
+            (end_pos.1 - start_pos.1) as u32 + 1,
+        );
+
+        status::say("Ready to draw the test pattern! Press 'D' to start drawing or 'Q' to quit");
+        let drawn = match self.wait_for_drawing_command(start_pos, end_pos, &config) {
+            Ok(true) => {
+                let drawn = self.draw_lines(
+                    calibration_pattern(size.0, size.1),
+                    None,
+                    size,
+                    start_pos,
+                    &config,
+                    None,
+                );
+                self.park_cursor(&config);
+                drawn
+            }
+            Ok(false) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = drawn {
+            status::say(format!("Drawing failed: {}", e));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for DrawingApp {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::{
+        checkpoint::SavedProgress, input::MockBackend, progress::ProgressConfig,
+        recording::InputEvent,
+    };
+    use device_query::MouseState;
+
+    /// Binarizes a uniform image of the given brightness with Otsu's method
+    fn binarize_uniform(value: u8) -> BinaryImage<u8> {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_pixel(32, 32, Luma([value])));
+        ImageProcessor::process_dynamic_image_8bit(
+            &img,
+            ImageProcessingMethod::Otsu,
+            &ProcessingOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn all_black_image_is_flagged() {
+        let fraction = ImageProcessor::black_fraction(&binarize_uniform(0));
+
+        assert_eq!(fraction, 1.0);
+        assert!(threshold_likely_failed(fraction));
+    }
+
+    #[test]
+    fn all_white_image_is_flagged() {
+        let fraction = ImageProcessor::black_fraction(&binarize_uniform(255));
+
+        assert_eq!(fraction, 0.0);
+        assert!(threshold_likely_failed(fraction));
+    }
+
+    #[test]
+    fn regular_drawing_is_not_flagged() {
+        assert!(!threshold_likely_failed(0.1));
+    }
+
+    /// Hotkeys of a session where no key is ever pressed
+    struct NoKeys;
+
+    impl DeviceQuery for NoKeys {
+        fn get_mouse(&self) -> MouseState {
+            MouseState::default()
+        }
+
+        fn get_keys(&self) -> Vec<Keycode> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn json_mode_draws_with_the_default_settings() {
+        let dir = std::env::temp_dir().join(format!("drawrs-json-run-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("square.png");
+        GrayImage::from_fn(32, 32, |x, y| {
+            let inside = (8..24).contains(&x) && (8..24).contains(&y);
+            Luma([if inside { 0 } else { 255 }])
+        })
+        .save(&image_path)
+        .unwrap();
+
+        // The default settings, reused so the countdown can be skipped
+        let history_path = dir.join("history.json");
+        History::at(&history_path)
+            .record(HistoryEntry {
+                scaling_mode: ScalingMode::default(),
+                config: DrawConfig {
+                    countdown: Duration::ZERO,
+                    progress: ProgressConfig::Disabled,
+                    ..DrawConfig::default()
+                },
+            })
+            .unwrap();
+
+        let args = CliArgs {
+            image: Some(ImageInput::Path(image_path.display().to_string())),
+            region: Some(RegionSpec::Pixels(((100, 100), (300, 300)))),
+            no_cache: true,
+            repeat_last: true,
+            json: true,
+            ..CliArgs::default()
+        };
+        let _mode = status::test_mode(true);
+        let mut app = DrawingApp::with_devices(args, MockBackend::new(), NoKeys)
+            .with_history(History::at(&history_path))
+            .with_checkpoint_dir(dir.join("checkpoints"));
+        app.execute();
+
+        let events = status::emitted();
+        let recorded = History::at(&history_path).load().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(events.contains(&StatusEvent::ImageLoaded {
+            width: 32,
+            height: 32
+        }));
+        assert!(events.contains(&StatusEvent::PromptDefaulted {
+            prompt: "Draw in color, one pass per palette color?".to_string(),
+            answer: "false".to_string(),
+        }));
+        assert!(events.contains(&StatusEvent::PromptDefaulted {
+            prompt: "Please select a method for processing the image".to_string(),
+            answer: "Otsu".to_string(),
+        }));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, StatusEvent::PathsGenerated { lines, .. } if *lines > 0)));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, StatusEvent::DrawingStarted { .. })));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, StatusEvent::DrawingCompleted { .. })));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, StatusEvent::PromptRequired { .. })));
+
+        assert!(app
+            .input
+            .events
+            .contains(&InputEvent::ButtonPress(Button::Left)));
+        // Reusing the settings records them again rather than adding an entry
+        assert_eq!(recorded.len(), 1);
+    }
+
+    #[test]
+    fn tiles_share_one_progress_count() {
+        // A square in each half, one per tile
+        let img = GrayImage::from_fn(40, 20, |x, y| {
+            let inside = (5..15).contains(&(x % 20)) && (5..15).contains(&y);
+            Luma([if inside { 0 } else { 255 }])
+        });
+        let plan = DrawPlan {
+            scaling_mode: ScalingMode::default(),
+            config: DrawConfig {
+                point_delay: Duration::ZERO,
+                line_delay: Duration::ZERO,
+                settle_delay: Duration::ZERO,
+                countdown: Duration::ZERO,
+                tile_grid: (2, 1),
+                progress: ProgressConfig::Disabled,
+                ..DrawConfig::default()
+            },
+            crop: None,
+            mask: None,
+            region: None,
+        };
+
+        let dir = std::env::temp_dir().join(format!("drawrs-tiles-{}", std::process::id()));
+        let _mode = status::test_mode(true);
+        let mut app = DrawingApp::with_devices(CliArgs::default(), MockBackend::new(), NoKeys)
+            .with_checkpoint_dir(&dir);
+        let drawn = app.draw_processed(
+            &img,
+            &DynamicImage::ImageLuma8(img.clone()),
+            &mut Some(plan),
+            Some(((100, 100), (179, 139))),
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let events = status::emitted();
+        let progress: Vec<(u64, u64)> = events
+            .iter()
+            .filter_map(|event| match event {
+                StatusEvent::Progress { line, total } => Some((*line, *total)),
+                _ => None,
+            })
+            .collect();
+        let tiles = events
+            .iter()
+            .filter(|event| matches!(event, StatusEvent::TileStarted { .. }))
+            .count();
+        let tile_lines: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                StatusEvent::DrawingStarted { lines, .. } => Some(*lines),
+                _ => None,
+            })
+            .collect();
+
+        assert!(drawn.is_some());
+        assert_eq!(tiles, 2);
+        assert_eq!(tile_lines.len(), 2);
+        assert!(tile_lines.iter().all(|&lines| lines > 0));
+        // Lines count on through the second tile, up to the total of both
+        let lines: Vec<u64> = progress.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, (1..=lines.len() as u64).collect::<Vec<_>>());
+        assert_eq!(lines.len(), tile_lines.iter().sum::<usize>());
+        assert_eq!(progress.last().unwrap().1, lines.len() as u64);
+    }
+
+    #[test]
+    fn oversized_drawing_asks_for_confirmation_by_default() {
+        let lines: Vec<Vec<Point>> = (0..500)
+            .map(|y| (0..500).map(|x| Point::new(x, y)).collect())
+            .collect();
+        let config = DrawConfig {
+            countdown: Duration::ZERO,
+            ..DrawConfig::default()
+        };
+
+        let _mode = status::test_mode(true);
+        let mut app = DrawingApp::with_devices(CliArgs::default(), MockBackend::new(), NoKeys);
+        app.draw_lines(lines, None, (500, 500), (0, 0), &config, None)
+            .unwrap();
+
+        // JSON mode declines, so nothing is drawn
+        assert!(status::emitted().contains(&StatusEvent::PromptDefaulted {
+            prompt: "Draw it anyway?".to_string(),
+            answer: "false".to_string(),
+        }));
+        assert!(app.input.events.is_empty());
+    }
+
+    #[test]
+    fn failing_mid_stroke_releases_the_pen_and_saves_a_checkpoint() {
+        let lines = vec![
+            (0..5).map(|x| Point::new(x * 4, 0)).collect::<Vec<_>>(),
+            (0..5).map(|x| Point::new(x * 4, 10)).collect(),
+        ];
+        let start_pos = (40, 1409);
+        let config = DrawConfig {
+            point_delay: Duration::ZERO,
+            line_delay: Duration::ZERO,
+            settle_delay: Duration::ZERO,
+            countdown: Duration::ZERO,
+            seed: Some(7),
+            ..DrawConfig::default()
+        };
+
+        let _mode = status::test_mode(false);
+        let mut first_line = MockBackend::new();
+        draw_stroke(
+            &mut first_line,
+            &lines[0],
+            start_pos,
+            None,
+            &mut Jitter::new(0.0, 7),
+            (Duration::ZERO, Duration::ZERO),
+            &config,
+        )
+        .unwrap();
+
+        // The second line moves to its start and presses the pen, then its
+        // first move and the retry fail before the backend recovers
+        let input = MockBackend::failing_after(first_line.events.len() + 2).recovering_after(2);
+        let args = CliArgs {
+            resume_from: Some(0),
+            ..CliArgs::default()
+        };
+        let dir =
+            std::env::temp_dir().join(format!("drawrs-failing-stroke-{}", std::process::id()));
+        let mut app = DrawingApp::with_devices(args, input, NoKeys).with_checkpoint_dir(&dir);
+        let result = app.draw_lines(lines.clone(), None, (20, 20), start_pos, &config, None);
+
+        let saved = Checkpoint::for_drawing(&dir, &lines, start_pos).load();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(DrawError::Input {
+                action: "move the mouse",
+                ..
+            })
+        ));
+        assert_eq!(
+            app.input.events.last(),
+            Some(&InputEvent::ButtonRelease(Button::Left))
+        );
+        let presses = app
+            .input
+            .events
+            .iter()
+            .filter(|event| **event == InputEvent::ButtonPress(Button::Left))
+            .count();
+        assert_eq!(presses, 2);
+        assert_eq!(
+            saved,
+            Some(SavedProgress {
+                next_line: 1,
+                seed: Some(7),
+            })
+        );
+    }
+}
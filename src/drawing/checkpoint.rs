@@ -0,0 +1,129 @@
+use crate::utils::{geometry::Point, hash::StableHasher};
+use std::path::{Path, PathBuf};
+
+/// Number of completed lines between two checkpoint writes
+pub const CHECKPOINT_INTERVAL: usize = 25;
+
+/// How far an interrupted drawing got, as read back from its checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SavedProgress {
+    /// Index of the first line that wasn't drawn yet
+    pub next_line: usize,
+
+    /// Seed the drawing's shuffle and jitter were made with, if recorded
+    pub seed: Option<u64>,
+}
+
+/// On-disk record of how far a drawing got
+///
+/// Checkpoints live in [`default_dir`] unless told otherwise and are keyed by
+/// the lines being drawn (before any shuffling) and where they're drawn, so a checkpoint
+/// is only ever offered for a drawing that would retrace the same strokes. The
+/// random seed is stored along with the progress, so a shuffled drawing is
+/// resumed in the same order. The key is a [`StableHasher`] hash, so a rebuilt
+/// binary still finds the checkpoints of an older one.
+pub struct Checkpoint {
+    path: PathBuf,
+    seed: Option<u64>,
+}
+
+/// Directory checkpoints are kept in by default, in the system temp directory
+pub fn default_dir() -> PathBuf {
+    std::env::temp_dir().join("drawrs-checkpoints")
+}
+
+impl Checkpoint {
+    /// Checkpoint in `dir` for drawing `lines` with their origin at `start_pos`
+    pub fn for_drawing(dir: impl AsRef<Path>, lines: &[Vec<Point>], start_pos: (i32, i32)) -> Self {
+        let mut hasher = StableHasher::new();
+        for line in lines {
+            hasher.write(&(line.len() as u64).to_le_bytes());
+            for point in line {
+                hasher
+                    .write(&point.x.to_le_bytes())
+                    .write(&point.y.to_le_bytes());
+            }
+        }
+        hasher
+            .write(&start_pos.0.to_le_bytes())
+            .write(&start_pos.1.to_le_bytes());
+
+        Checkpoint {
+            path: dir.as_ref().join(format!("{:016x}.txt", hasher.finish())),
+            seed: None,
+        }
+    }
+
+    /// Records `seed` with every later [`save`](Self::save)
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Progress of an earlier run of this drawing, if a checkpoint exists
+    pub fn load(&self) -> Option<SavedProgress> {
+        let contents = std::fs::read_to_string(&self.path).ok()?;
+        let mut fields = contents.split_whitespace();
+
+        Some(SavedProgress {
+            next_line: fields.next()?.parse().ok()?,
+            seed: fields.next().and_then(|seed| seed.parse().ok()),
+        })
+    }
+
+    /// Records that every line before `next_line` has been drawn
+    pub fn save(&self, next_line: usize) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let contents = match self.seed {
+            Some(seed) => format!("{} {}", next_line, seed),
+            None => next_line.to_string(),
+        };
+        std::fs::write(&self.path, contents)
+    }
+
+    /// Removes the checkpoint once the drawing is complete
+    pub fn clear(&self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_round_trips_with_seed() {
+        let dir = std::env::temp_dir().join(format!(
+            "drawrs-checkpoint-round-trip-{}",
+            std::process::id()
+        ));
+        let lines = vec![vec![Point::new(-1323, 7), Point::new(5, 5)]];
+        let checkpoint = Checkpoint::for_drawing(&dir, &lines, (-1323, 1)).with_seed(99);
+
+        checkpoint.save(12).unwrap();
+        let saved = checkpoint.load();
+        checkpoint.clear();
+        let cleared = checkpoint.load();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            saved,
+            Some(SavedProgress {
+                next_line: 12,
+                seed: Some(99)
+            })
+        );
+        assert_eq!(cleared, None);
+    }
+
+    #[test]
+    fn key_does_not_change_between_builds() {
+        let lines = vec![vec![Point::new(0, 0), Point::new(1, 2)]];
+        let checkpoint = Checkpoint::for_drawing(default_dir(), &lines, (3, 4));
+
+        assert_eq!(checkpoint.path, default_dir().join("bab3fe3692bcd923.txt"));
+    }
+}
@@ -1,7 +1,57 @@
-use crate::drawing::pathfinding::trace_line;
+use crate::choices::TracingStrategy;
+use crate::drawing::{pathfinding::trace_line, spanning_tree::trace_spanning_tree};
 use crate::utils::geometry::Point;
 use std::collections::{HashMap, HashSet};
 
+/// Average number of points per cell [`auto_grid_size`] aims for
+const TARGET_POINTS_PER_CELL: f64 = 4.0;
+
+/// Points bucketed into square cells, so the neighbors of a point are found
+/// without scanning every point
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    /// Side (in pixels) of each cell
+    pub grid_size: i32,
+
+    /// Points of each cell, keyed by the cell's coordinates
+    pub cells: HashMap<(i32, i32), Vec<Point>>,
+}
+
+impl SpatialIndex {
+    /// Indexes `points` into cells of `grid_size` pixels
+    pub fn new(points: &HashSet<Point>, grid_size: i32) -> Self {
+        let grid_size = grid_size.max(1);
+
+        SpatialIndex {
+            grid_size,
+            cells: build_spatial_index(points, grid_size),
+        }
+    }
+
+    /// Empty index with cells of `grid_size` pixels, filled with [`insert`](Self::insert)
+    pub fn empty(grid_size: i32) -> Self {
+        SpatialIndex {
+            grid_size: grid_size.max(1),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Adds a point to the cell it falls in
+    pub fn insert(&mut self, point: Point) {
+        self.cells
+            .entry((point.x / self.grid_size, point.y / self.grid_size))
+            .or_default()
+            .push(point);
+    }
+
+    /// Adds every point of another index built with the same cell size
+    pub fn merge(&mut self, other: SpatialIndex) {
+        for (cell, points) in other.cells {
+            self.cells.entry(cell).or_default().extend(points);
+        }
+    }
+}
+
 pub fn build_spatial_index(
     points: &HashSet<Point>,
     grid_size: i32,
@@ -20,8 +70,115 @@ pub fn build_spatial_index(
     spatial_index
 }
 
-pub fn find_connected_components(points: HashSet<Point>, max_distance: i32) -> Vec<Vec<Point>> {
-    let spatial_index = build_spatial_index(&points, max_distance.max(1));
+/// Picks a spatial index cell size for `points` from their density
+///
+/// Every search for the next point of a line scans enough cells to cover
+/// `max_distance`, so the cell size trades the number of cells visited against
+/// the number of points checked in each. Cells much smaller than the point
+/// spacing are mostly empty and just add lookups, while cells much larger than
+/// `max_distance` hold many points that are too far away anyway. The size
+/// aims for `TARGET_POINTS_PER_CELL` points per cell given the average spacing
+/// over the points' bounding box, capped at `max_distance` so sparse drawings
+/// keep scanning a single ring of cells.
+pub fn auto_grid_size(points: &HashSet<Point>, max_distance: i32) -> i32 {
+    let max_distance = max_distance.max(1);
+    if points.is_empty() {
+        return max_distance;
+    }
+
+    let (min_x, min_y, max_x, max_y) = points.iter().fold(
+        (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+        |(min_x, min_y, max_x, max_y), point| {
+            (
+                min_x.min(point.x),
+                min_y.min(point.y),
+                max_x.max(point.x),
+                max_y.max(point.y),
+            )
+        },
+    );
+
+    let area = (max_x - min_x + 1) as f64 * (max_y - min_y + 1) as f64;
+    let spacing = (area / points.len() as f64).sqrt();
+
+    ((spacing * TARGET_POINTS_PER_CELL.sqrt()).round() as i32).clamp(1, max_distance)
+}
+
+/// Picks a spatial index cell size for points sampled every `step` pixels,
+/// before sampling them
+///
+/// Grid-sampled points are `step` pixels apart wherever the image is black,
+/// which is the spacing [`auto_grid_size`] measures for a solid area, so the
+/// index can be filled during the same scan that samples the points.
+pub fn grid_size_for_step(step: i32, max_distance: i32) -> i32 {
+    ((step.max(1) as f64 * TARGET_POINTS_PER_CELL.sqrt()).round() as i32)
+        .clamp(1, max_distance.max(1))
+}
+
+/// Traces points into lines, sorted from longest to shortest
+///
+/// Lines of equal length are ordered by their first point (top to bottom, then
+/// left to right), so the result is fully deterministic for a given input.
+pub fn find_connected_components(
+    points: HashSet<Point>,
+    max_distance: i32,
+    spatial_index: Option<SpatialIndex>,
+    bridge_radius: Option<i32>,
+    strategy: TracingStrategy,
+    split_junctions: bool,
+) -> Vec<Vec<Point>> {
+    let mut lines = trace_components(
+        points,
+        max_distance,
+        spatial_index,
+        bridge_radius,
+        strategy,
+        split_junctions,
+    );
+
+    lines.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then_with(|| (a[0].y, a[0].x).cmp(&(b[0].y, b[0].x)))
+    });
+    lines
+}
+
+/// Traces points into lines in extraction order
+///
+/// Tracing starts from the top-most, left-most unvisited point each time, so
+/// lines come out roughly top to bottom. Lines of two points or fewer are
+/// dropped, except for the short side branches of a spanning tree.
+///
+/// `spatial_index` must index exactly `points`, e.g. when it was built while
+/// sampling them; when `None`, one is built with a cell size picked by
+/// [`auto_grid_size`]. The spanning tree `strategy`
+/// hands off to [`trace_spanning_tree`], while greedy tracing can split lines
+/// at junctions, see [`trace_line`].
+pub fn trace_components(
+    points: HashSet<Point>,
+    max_distance: i32,
+    spatial_index: Option<SpatialIndex>,
+    bridge_radius: Option<i32>,
+    strategy: TracingStrategy,
+    split_junctions: bool,
+) -> Vec<Vec<Point>> {
+    let SpatialIndex {
+        grid_size,
+        cells: spatial_index,
+    } = spatial_index
+        .unwrap_or_else(|| SpatialIndex::new(&points, auto_grid_size(&points, max_distance)));
+
+    if strategy == TracingStrategy::SpanningTree {
+        return trace_spanning_tree(
+            points,
+            max_distance,
+            &spatial_index,
+            grid_size,
+            bridge_radius,
+        );
+    }
+
     let mut visited = HashSet::new();
     let mut lines = Vec::new();
 
@@ -30,7 +187,15 @@ pub fn find_connected_components(points: HashSet<Point>, max_distance: i32) -> V
 
     for start_point in sorted_points {
         if !visited.contains(&start_point) {
-            let line = trace_line(start_point, &spatial_index, &mut visited, max_distance);
+            let line = trace_line(
+                start_point,
+                &spatial_index,
+                grid_size,
+                &mut visited,
+                max_distance,
+                bridge_radius,
+                split_junctions,
+            );
 
             if line.len() > 2 {
                 lines.push(line);
@@ -38,6 +203,80 @@ pub fn find_connected_components(points: HashSet<Point>, max_distance: i32) -> V
         }
     }
 
-    lines.sort_by_key(|line| std::cmp::Reverse(line.len()));
     lines
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::ordering::path_length;
+
+    /// Several equally long horizontal strokes, so only the tiebreaker orders them
+    fn equal_strokes() -> HashSet<Point> {
+        (0..6)
+            .flat_map(|row| (0..8).map(move |x| Point::new(x + row * 3, row * 10)))
+            .collect()
+    }
+
+    #[test]
+    fn order_is_deterministic_across_runs() {
+        let trace = || {
+            find_connected_components(
+                equal_strokes(),
+                3,
+                None,
+                None,
+                TracingStrategy::Greedy,
+                false,
+            )
+        };
+
+        let first = trace();
+        assert_eq!(first.len(), 6);
+        for _ in 0..5 {
+            assert_eq!(trace(), first);
+        }
+    }
+
+    #[test]
+    fn as_extracted_keeps_top_to_bottom_order() {
+        let mut points = equal_strokes();
+        points.extend((0..20).map(|x| Point::new(x, 100)));
+
+        let lines = trace_components(points, 3, None, None, TracingStrategy::Greedy, false);
+        let rows: Vec<_> = lines.iter().map(|line| line[0].y).collect();
+
+        assert_eq!(rows, [0, 10, 20, 30, 40, 50, 100]);
+    }
+
+    #[test]
+    fn grid_size_for_step_matches_a_solid_area() {
+        let solid: HashSet<Point> = (0..40)
+            .flat_map(|y| (0..40).map(move |x| Point::new(x * 3, y * 3)))
+            .collect();
+
+        assert_eq!(grid_size_for_step(3, 20), auto_grid_size(&solid, 20));
+        assert_eq!(grid_size_for_step(3, 4), 4);
+    }
+
+    #[test]
+    fn spanning_tree_traces_a_filled_area_shorter_than_greedy() {
+        // Greedy tracing cuts diagonally across the disc and jumps over points
+        // it already drew
+        let disc: HashSet<Point> = (-6..=6)
+            .flat_map(|y| (-6..=6).map(move |x| Point::new(x, y)))
+            .filter(|p| p.x * p.x + p.y * p.y <= 36)
+            .collect();
+        let trace = |strategy| trace_components(disc.clone(), 2, None, None, strategy, false);
+        let total_length =
+            |lines: &[Vec<Point>]| lines.iter().map(|line| path_length(line)).sum::<f64>();
+
+        let greedy = total_length(&trace(TracingStrategy::Greedy));
+        let spanning_tree = trace(TracingStrategy::SpanningTree);
+
+        let drawn: HashSet<Point> = spanning_tree.iter().flatten().copied().collect();
+        assert_eq!(drawn, disc);
+        let spanning_tree = total_length(&spanning_tree);
+        assert!(spanning_tree < greedy, "{} >= {}", spanning_tree, greedy);
+    }
+}
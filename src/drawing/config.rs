@@ -0,0 +1,631 @@
+use crate::{
+    choices::{
+        CoordinateRounding, LineOrder, ParkCorner, SamplingPattern, StrokeStart, TracingStrategy,
+    },
+    drawing::{hatch::HatchPass, opacity::OpacityKeys, progress::ProgressConfig},
+    utils::geometry::Polygon,
+};
+use enigo::{Button, Key};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Smallest connection radius, used when the step and brush are too small to
+/// matter
+const DEFAULT_CONNECTION_DISTANCE: i32 = 3;
+
+/// Coarsest sampling step [`DrawConfig::fit_time_budget`] goes up to before
+/// speeding up the delays instead
+const MAX_BUDGET_STEP: i32 = 12;
+
+/// Smallest factor [`DrawConfig::fit_time_budget`] shortens the delays by, as
+/// apps drop input that comes in much faster than configured
+const MIN_BUDGET_DELAY_FACTOR: f64 = 0.25;
+
+/// Input held down while a line is drawn and released to lift the pen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PenAction {
+    /// Hold a mouse button
+    MouseButton(Button),
+
+    /// Hold a keyboard key, for apps where clicking pans or selects
+    Key(Key),
+}
+
+/// How the lines of a drawing are generated from the image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FillStyle {
+    /// Trace the black pixels of the binarized image
+    Trace,
+
+    /// Cover dark areas of the grayscale source with parallel hatch lines, see
+    /// [`hatch_lines`](crate::drawing::hatch::hatch_lines)
+    Hatch {
+        /// Direction of the lines in degrees, 0 being horizontal
+        angle: f64,
+
+        /// Spacing (in pixels) of the lines in fully black areas
+        min_spacing: f64,
+
+        /// Widest spacing (in pixels) before an area is left blank
+        max_spacing: f64,
+    },
+
+    /// Hatch at several angles, adding angles as areas get darker, see
+    /// [`cross_hatch_lines`](crate::drawing::hatch::cross_hatch_lines)
+    CrossHatch {
+        /// Angle and darkness threshold of each pass
+        passes: Vec<HatchPass>,
+
+        /// Spacing (in pixels) of the lines in fully black areas
+        min_spacing: f64,
+
+        /// Widest spacing (in pixels) before an area is left blank
+        max_spacing: f64,
+    },
+
+    /// Click single dots whose density follows the darkness of the grayscale
+    /// source, see [`generate_stipple`](crate::drawing::stipple::generate_stipple)
+    Stipple {
+        /// Dots per pixel in fully black areas
+        density: f64,
+    },
+
+    /// Draw iso-brightness lines of the grayscale source, see
+    /// [`extract_contours`](crate::drawing::contour::extract_contours)
+    Contours {
+        /// Number of evenly spaced brightness levels traced
+        levels: u32,
+    },
+}
+
+/// How much lines are simplified before drawing, see
+/// [`simplify_douglas_peucker`](crate::drawing::simplify::simplify_douglas_peucker)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Simplification {
+    /// The same tolerance (in pixels) for every line
+    Fixed(f64),
+
+    /// A tolerance proportional to each line's length, so large shapes lose
+    /// most of their points while short detail lines keep theirs
+    LengthScaled {
+        /// Tolerance per pixel of line length
+        ratio: f64,
+
+        /// Largest tolerance (in pixels), however long the line
+        max_epsilon: f64,
+    },
+}
+
+impl Simplification {
+    /// Tolerance (in pixels) used for a line `length` pixels long
+    pub fn epsilon(&self, length: f64) -> f64 {
+        match *self {
+            Simplification::Fixed(epsilon) => epsilon,
+            Simplification::LengthScaled { ratio, max_epsilon } => {
+                (length * ratio).min(max_epsilon)
+            }
+        }
+    }
+}
+
+/// Settings that control how extracted paths are drawn on screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawConfig {
+    /// Delay applied after each mouse movement along a line (and along an
+    /// interpolated pen-up jump)
+    pub point_delay: Duration,
+
+    /// Delay applied after moving to the start of a line and after lifting the
+    /// pen at its end, giving the target app time to register the stroke
+    /// boundaries independently of how fast lines are drawn
+    pub line_delay: Duration,
+
+    /// Time given to switch to the target app after starting a drawing
+    pub countdown: Duration,
+
+    /// Pause between moving to the start of a line and pressing the button,
+    /// giving high-latency targets time to register the new cursor position
+    pub settle_delay: Duration,
+
+    /// Sampling step (in pixels) used when collecting black pixels
+    pub step: i32,
+
+    /// How black pixels are sampled: on a grid every `step` pixels, adaptively
+    /// (see [`adaptive_step_range`](Self::adaptive_step_range)), or as blue
+    /// noise with samples at least `step` pixels apart
+    pub sampling: SamplingPattern,
+
+    /// Number of mouse moves issued per pixel when interpolating between two
+    /// distant points of the same line
+    pub interpolation_density: f64,
+
+    /// Resolution the image is processed at relative to the screen region
+    ///
+    /// The image is scaled to `density_multiplier` times the region's size,
+    /// paths are extracted at that virtual resolution, and each virtual point
+    /// `p` is then drawn at `start + p / density_multiplier` on screen. Values
+    /// above 1 sample more points per screen pixel, which helps on zoomed-in
+    /// canvases.
+    pub density_multiplier: f64,
+
+    /// How fractional positions are turned into screen pixels
+    pub rounding: CoordinateRounding,
+
+    /// Size (in screen pixels) of one pixel of a zoomed-in pixel-art canvas
+    /// (`None` draws at screen pixels)
+    ///
+    /// The image is scaled to one pixel per canvas cell, i.e. a region `w`
+    /// screen pixels wide holds `w / cell` image pixels, with
+    /// [`density_multiplier`](Self::density_multiplier) set to `1 / cell`. Each
+    /// point is then snapped to the center of its cell instead of being
+    /// [`rounded`](Self::rounding), so strokes never straddle two canvas pixels.
+    pub canvas_cell_size: Option<u32>,
+
+    /// Diameter (in pixels) of the brush used in the target app
+    pub brush_size: u32,
+
+    /// Maximum random offset (in pixels) applied to each drawn point
+    pub jitter_amplitude: f64,
+
+    /// Seed for all randomized behavior (`None` picks a random one per run for
+    /// the line order and jitter, while sampling and stippling fall back to
+    /// fixed seeds)
+    pub seed: Option<u64>,
+
+    /// How sampled points are joined into lines
+    pub tracing: TracingStrategy,
+
+    /// End greedily traced lines at junctions unless they can carry on
+    /// straight, so crossing strokes stay separate
+    pub split_junctions: bool,
+
+    /// Cell size (in pixels) of the spatial index used to connect sampled
+    /// points (`None` picks one from the sampling step for grid sampling, see
+    /// [`grid_size_for_step`](crate::drawing::components::grid_size_for_step),
+    /// or from the point density otherwise, see
+    /// [`auto_grid_size`](crate::drawing::components::auto_grid_size))
+    pub grid_size: Option<i32>,
+
+    /// Radius (in pixels) searched for a continuation when a line reaches a dead
+    /// end, joining dashed or dithered strokes into one path (`None` disables it)
+    pub bridge_radius: Option<i32>,
+
+    /// Pen-down moves closer than this many pixels to the last issued position
+    /// are skipped
+    pub min_move: f64,
+
+    /// Input used to put the pen down and lift it
+    pub pen_action: PenAction,
+
+    /// Corner of the primary display the cursor is moved to once a drawing
+    /// finishes or is cancelled, so it doesn't rest over a tool of the target
+    /// app (default: `None`, leaving the cursor where the drawing stopped)
+    pub park_cursor: Option<ParkCorner>,
+
+    /// Lift the pen and put it back down after every this many points of a
+    /// line, splitting long strokes for brush engines that lag or crash on
+    /// them (`None` draws each line as one stroke)
+    pub lift_every: Option<usize>,
+
+    /// Gesture used to put the pen down at the start of each line
+    pub stroke_start: StrokeStart,
+
+    /// Step the brush opacity before each line according to the source
+    /// brightness under it (`None` leaves the opacity alone)
+    pub opacity: Option<OpacityKeys>,
+
+    /// How lines are generated from the image
+    pub fill_style: FillStyle,
+
+    /// Order in which the extracted lines are drawn
+    pub line_order: LineOrder,
+
+    /// Number of `(columns, rows)` the image is split into, each tile being
+    /// placed and drawn separately
+    pub tile_grid: (u32, u32),
+
+    /// Drawings with more points than this ask for confirmation before starting
+    /// (`None` disables the check)
+    pub max_points: Option<usize>,
+
+    /// Coarsen the sampling step (and, as a last resort, shorten the delays)
+    /// until the estimated drawing time fits, see
+    /// [`fit_time_budget`](Self::fit_time_budget) (`None` keeps the settings)
+    pub time_budget: Option<Duration>,
+
+    /// Join lines whose ends are close and point the same way, given as the
+    /// `(angle, gap)` tolerances in degrees and pixels (`None` disables it)
+    pub merge_collinear: Option<(f64, f64)>,
+
+    /// Only draw this many of the longest lines, for a quick sketch of the
+    /// image (`None` draws every line)
+    pub max_lines: Option<usize>,
+
+    /// Lines shorter than this many pixels are skipped (`None` keeps them)
+    pub min_len: Option<f64>,
+
+    /// Lines longer than this many pixels are skipped, e.g. the frame of a
+    /// scanned page (`None` keeps them)
+    pub max_len: Option<f64>,
+
+    /// Average each point with its neighbors over a window of this many points
+    /// before drawing, see
+    /// [`smooth_moving_average`](crate::drawing::smoothing::smooth_moving_average)
+    /// (`None` draws the points as extracted)
+    pub smoothing_window: Option<usize>,
+
+    /// Drop points that barely change the shape of their line before drawing
+    /// (`None` draws every point)
+    pub simplification: Option<Simplification>,
+
+    /// Only draw inside this polygon, given in pixels relative to the region's
+    /// top-left corner (`None` draws the whole region)
+    ///
+    /// Traced black pixels outside it are never sampled, while hatching,
+    /// stippling and contour lines are cut at its border.
+    pub clip_polygon: Option<Polygon>,
+
+    /// Trace the corners of the target region with the pen up before drawing
+    pub preview_region: bool,
+
+    /// Sweep the cursor over every line with the pen up and without delays
+    /// before drawing, to check where the drawing lands on the canvas
+    pub practice: bool,
+
+    /// Draw a rectangular frame around the target region after the image
+    pub draw_frame: bool,
+
+    /// Distance (in pixels) between the frame and the region's border
+    pub frame_inset: u32,
+
+    /// How drawing progress is shown
+    pub progress: ProgressConfig,
+
+    /// Pen-up jumps longer than this many pixels are interpolated instead of
+    /// teleporting the cursor (`None` disables interpolation)
+    pub travel_threshold: Option<u32>,
+
+    /// Number of intermediate moves used for an interpolated pen-up jump
+    pub travel_steps: u32,
+}
+
+/// Smallest connection distance joining diagonal neighbors of a grid sampled
+/// every `step` pixels, with a pixel to spare
+fn diagonal_reach(step: i32) -> i32 {
+    (step as f64 * std::f64::consts::SQRT_2).ceil() as i32 + 1
+}
+
+/// Offset (in screen pixels) of the center of the canvas cell holding image
+/// coordinate `value`, for cells of `cell` screen pixels
+///
+/// Coordinates between two image pixels go to the nearest one.
+pub fn snap_to_cell(value: f64, cell: u32) -> i32 {
+    value.round() as i32 * cell as i32 + cell as i32 / 2
+}
+
+impl Default for DrawConfig {
+    fn default() -> Self {
+        DrawConfig {
+            point_delay: Duration::from_micros(10),
+            line_delay: Duration::from_micros(10),
+            settle_delay: Duration::from_millis(5),
+            countdown: Duration::from_secs(3),
+            step: 2,
+            sampling: SamplingPattern::Grid,
+            interpolation_density: 0.75,
+            density_multiplier: 1.0,
+            rounding: CoordinateRounding::Round,
+            canvas_cell_size: None,
+            brush_size: 1,
+            jitter_amplitude: 0.0,
+            seed: None,
+            tracing: TracingStrategy::Greedy,
+            split_junctions: false,
+            grid_size: None,
+            bridge_radius: None,
+            min_move: 1.0,
+            pen_action: PenAction::MouseButton(Button::Left),
+            park_cursor: None,
+            lift_every: None,
+            stroke_start: StrokeStart::Press,
+            opacity: None,
+            fill_style: FillStyle::Trace,
+            line_order: LineOrder::InOrder,
+            tile_grid: (1, 1),
+            max_points: Some(200_000),
+            time_budget: None,
+            merge_collinear: None,
+            max_lines: None,
+            min_len: None,
+            max_len: None,
+            smoothing_window: None,
+            simplification: None,
+            clip_polygon: None,
+            preview_region: false,
+            practice: false,
+            draw_frame: false,
+            frame_inset: 0,
+            progress: ProgressConfig::default(),
+            travel_threshold: None,
+            travel_steps: 10,
+        }
+    }
+}
+
+impl DrawConfig {
+    /// Sampling step after accounting for the brush size
+    ///
+    /// A brush already covers half its diameter on each side of a stroke, so
+    /// sampling more finely than that radius only produces overlapping strokes.
+    /// The larger of the accuracy step and the brush radius wins: a small brush
+    /// never makes a coarse accuracy finer, while a large brush coarsens even
+    /// the `Accurate` setting.
+    pub fn effective_step(&self) -> i32 {
+        self.step.max((self.brush_size / 2) as i32).max(1)
+    }
+
+    /// End corner of the virtual region the image is scaled to for a screen
+    /// region from `start_pos` to `end_pos`
+    pub fn virtual_end(&self, start_pos: (i32, i32), end_pos: (i32, i32)) -> (i32, i32) {
+        let scale = |from: i32, to: i32| {
+            from + ((to - from) as f64 * self.density_multiplier).round() as i32
+        };
+
+        (scale(start_pos.0, end_pos.0), scale(start_pos.1, end_pos.1))
+    }
+
+    /// Maps a point at the virtual resolution onto the screen region starting
+    /// at `start_pos`, applying the configured [`rounding`](Self::rounding), or
+    /// snapping to the center of its [canvas cell](Self::canvas_cell_size)
+    pub fn to_screen(&self, start_pos: (i32, i32), x: f64, y: f64) -> (i32, i32) {
+        if let Some(cell) = self.canvas_cell_size {
+            return (
+                start_pos.0 + snap_to_cell(x, cell),
+                start_pos.1 + snap_to_cell(y, cell),
+            );
+        }
+
+        let to_pixel = |value: f64| {
+            let value = value / self.density_multiplier;
+            let rounded = match self.rounding {
+                CoordinateRounding::Round => value.round(),
+                CoordinateRounding::Floor => value.floor(),
+                CoordinateRounding::Ceil => value.ceil(),
+            };
+
+            rounded as i32
+        };
+
+        (start_pos.0 + to_pixel(x), start_pos.1 + to_pixel(y))
+    }
+
+    /// Whether drawing needs the grayscale source image in addition to the
+    /// binarized one
+    pub fn needs_tone(&self) -> bool {
+        self.line_order == LineOrder::ByBrightness
+            || self.opacity.is_some()
+            || self.fill_style != FillStyle::Trace
+    }
+
+    /// Finest and coarsest steps used with adaptive sampling
+    ///
+    /// The most detailed areas are sampled at half the effective step and flat
+    /// ones at twice it.
+    pub fn adaptive_step_range(&self) -> (i32, i32) {
+        let step = self.effective_step();
+        (((step + 1) / 2).max(1), step * 2)
+    }
+
+    /// Maximum distance between two sampled points that are joined into one line
+    ///
+    /// Grid samples `step` pixels apart have diagonal neighbors `step * √2`
+    /// pixels away, so the distance is derived from the
+    /// [`effective_step`](Self::effective_step) to always reach them, or from
+    /// the coarsest adaptive step with adaptive sampling. Poisson-disk samples
+    /// can be up to twice the step apart, so that sampling connects over twice
+    /// it. It never drops below the brush radius.
+    pub fn connection_distance(&self) -> i32 {
+        let distance = DEFAULT_CONNECTION_DISTANCE.max((self.brush_size / 2) as i32 + 1);
+
+        match self.sampling {
+            SamplingPattern::Grid => distance.max(diagonal_reach(self.effective_step())),
+            SamplingPattern::Adaptive => distance.max(diagonal_reach(self.adaptive_step_range().1)),
+            SamplingPattern::PoissonDisk => distance.max(self.effective_step() * 2 + 1),
+        }
+    }
+
+    /// Rough lower bound on how long drawing `points` points split across
+    /// `lines` lines takes
+    ///
+    /// Only the configured delays are counted: the point delay of each point,
+    /// plus the settle delay and both line delays of each line. Interpolated
+    /// moves and the time spent issuing input make real runs somewhat longer.
+    pub fn estimate_duration(&self, points: usize, lines: usize) -> Duration {
+        let per_line = self.settle_delay + self.line_delay * 2;
+
+        // Multiplying by a `u32` would truncate huge point counts
+        self.point_delay.mul_f64(points as f64) + per_line.mul_f64(lines as f64)
+    }
+
+    /// Copy of these settings whose estimated drawing time fits `budget`,
+    /// along with what the last estimate was made from
+    ///
+    /// `estimate` returns the expected duration of a drawing with the given
+    /// settings and what it was computed from, e.g. by extracting its paths
+    /// and calling [`estimate_duration`](Self::estimate_duration). The step is
+    /// coarsened by half each round until the estimate fits or the step
+    /// reaches [`MAX_BUDGET_STEP`]; if the drawing is still too slow by then,
+    /// the delays are shortened by the missing factor, but no further than
+    /// [`MIN_BUDGET_DELAY_FACTOR`], so the result may still overrun a budget
+    /// that is too tight.
+    pub fn fit_time_budget<R>(
+        &self,
+        budget: Duration,
+        mut estimate: impl FnMut(&DrawConfig) -> (Duration, R),
+    ) -> (DrawConfig, R) {
+        let mut config = self.clone();
+        config.step = config.effective_step();
+
+        loop {
+            let (expected, estimated) = estimate(&config);
+            if expected <= budget {
+                return (config, estimated);
+            }
+
+            if config.step < MAX_BUDGET_STEP {
+                config.step = (config.step * 3 / 2)
+                    .max(config.step + 1)
+                    .min(MAX_BUDGET_STEP);
+                continue;
+            }
+
+            let factor =
+                (budget.as_secs_f64() / expected.as_secs_f64()).max(MIN_BUDGET_DELAY_FACTOR);
+            config.point_delay = config.point_delay.mul_f64(factor);
+            config.line_delay = config.line_delay.mul_f64(factor);
+            config.settle_delay = config.settle_delay.mul_f64(factor);
+            return (config, estimated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        drawing::{ordering::path_length, simplify::simplify_douglas_peucker},
+        utils::geometry::Point,
+    };
+
+    #[test]
+    fn estimate_counts_every_delay() {
+        let config = DrawConfig {
+            point_delay: Duration::from_millis(1),
+            line_delay: Duration::from_millis(10),
+            settle_delay: Duration::from_millis(5),
+            ..DrawConfig::default()
+        };
+
+        assert_eq!(
+            config.estimate_duration(1000, 4),
+            Duration::from_millis(1000 + 4 * 25)
+        );
+    }
+
+    #[test]
+    fn estimate_does_not_truncate_huge_point_counts() {
+        let config = DrawConfig {
+            point_delay: Duration::from_micros(10),
+            line_delay: Duration::ZERO,
+            settle_delay: Duration::ZERO,
+            ..DrawConfig::default()
+        };
+        let points = u32::MAX as usize + 1_000_000;
+
+        let expected = Duration::from_micros(10).as_secs_f64() * points as f64;
+        let estimate = config.estimate_duration(points, 0).as_secs_f64();
+        assert!((estimate - expected).abs() < 1.0);
+    }
+
+    /// Largest and total vertical error of moving along a long, shallow
+    /// diagonal with the given rounding
+    fn diagonal_error(rounding: CoordinateRounding) -> (f64, f64) {
+        let config = DrawConfig {
+            rounding,
+            ..DrawConfig::default()
+        };
+
+        (0..1000).fold((0.0f64, 0.0), |(max, total), x| {
+            let y = x as f64 * 0.37;
+            let error = (config.to_screen((0, 0), x as f64, y).1 as f64 - y).abs();
+            (max.max(error), total + error)
+        })
+    }
+
+    #[test]
+    fn points_snap_to_the_center_of_their_cell() {
+        assert_eq!(snap_to_cell(0.0, 4), 2);
+        assert_eq!(snap_to_cell(3.0, 4), 14);
+        assert_eq!(snap_to_cell(2.6, 4), 14);
+        assert_eq!(snap_to_cell(2.4, 4), 10);
+        // Odd cells have a center pixel
+        assert_eq!(snap_to_cell(1.0, 5), 7);
+
+        let config = DrawConfig {
+            canvas_cell_size: Some(8),
+            ..DrawConfig::default()
+        };
+        assert_eq!(config.to_screen((100, 50), 3.0, 2.0), (128, 70));
+    }
+
+    #[test]
+    fn rounding_halves_the_error_of_truncation() {
+        let (round_max, round_total) = diagonal_error(CoordinateRounding::Round);
+        let (floor_max, floor_total) = diagonal_error(CoordinateRounding::Floor);
+
+        assert!(round_max <= 0.5);
+        assert!(floor_max > 0.9);
+        assert!(round_total < floor_total * 0.6);
+    }
+
+    #[test]
+    fn longer_lines_get_a_larger_epsilon() {
+        let simplification = Simplification::LengthScaled {
+            ratio: 0.005,
+            max_epsilon: 5.0,
+        };
+        assert!(simplification.epsilon(100.0) > simplification.epsilon(20.0));
+        assert_eq!(simplification.epsilon(10_000.0), 5.0);
+        assert_eq!(Simplification::Fixed(2.0).epsilon(10_000.0), 2.0);
+
+        // The same one pixel wobble is noise on a long line but detail on a
+        // short one
+        let wobble = |len: i32| -> Vec<Point> { (0..len).map(|x| Point::new(x, x % 2)).collect() };
+        let simplify = |line: &[Point]| {
+            simplify_douglas_peucker(line, simplification.epsilon(path_length(line)))
+        };
+        let (long, short) = (wobble(1000), wobble(20));
+
+        assert_eq!(simplify(&long).len(), 2);
+        assert_eq!(simplify(&short), short);
+    }
+
+    /// Estimate of a drawing whose point count falls with the square of the
+    /// step, like a filled shape sampled on a grid
+    fn area_estimate(config: &DrawConfig) -> (Duration, i32) {
+        let points = 100_000 / (config.step * config.step) as usize;
+        (config.estimate_duration(points, 10), config.step)
+    }
+
+    #[test]
+    fn tighter_budget_coarsens_the_step_more() {
+        let config = DrawConfig {
+            step: 1,
+            point_delay: Duration::from_millis(1),
+            ..DrawConfig::default()
+        };
+
+        let (loose, loose_step) = config.fit_time_budget(Duration::from_secs(60), area_estimate);
+        let (tight, tight_step) = config.fit_time_budget(Duration::from_secs(5), area_estimate);
+
+        assert!(tight.step > loose.step);
+        assert_eq!((loose.step, tight.step), (loose_step, tight_step));
+        assert_eq!(loose.point_delay, config.point_delay);
+    }
+
+    #[test]
+    fn delays_are_shortened_only_so_far() {
+        let config = DrawConfig {
+            point_delay: Duration::from_millis(1),
+            ..DrawConfig::default()
+        };
+
+        let (fitted, step) = config.fit_time_budget(Duration::from_millis(1), area_estimate);
+
+        assert_eq!(step, MAX_BUDGET_STEP);
+        assert_eq!(
+            fitted.point_delay,
+            Duration::from_millis(1).mul_f64(MIN_BUDGET_DELAY_FACTOR)
+        );
+    }
+}
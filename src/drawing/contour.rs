@@ -0,0 +1,190 @@
+use crate::utils::geometry::Point;
+use image::{ImageBuffer, Luma};
+use std::collections::HashMap;
+
+/// Cell edge a contour crosses, given by the top-left pixel of the edge and
+/// whether it is horizontal (to the right of that pixel) or vertical (below it)
+type Edge = (u32, u32, bool);
+
+/// `count` brightness levels evenly splitting the full range, excluding pure
+/// black and white
+pub fn contour_levels(count: u32) -> Vec<u16> {
+    (1..=count)
+        .map(|level| (u16::MAX as u64 * level as u64 / (count as u64 + 1)) as u16)
+        .collect()
+}
+
+/// Traces the iso-brightness lines of `img` at each of `levels` with marching
+/// squares
+///
+/// Pixels are treated as the corners of a grid of cells, and every cell whose
+/// corners lie on both sides of a level gets a segment through its edges, placed
+/// by interpolating between the corner values. Segments sharing an edge are
+/// then joined into polylines, so each contour is returned as one line; closed
+/// contours start and end on the same point. Ambiguous saddle cells are
+/// resolved by the average of their corners.
+pub fn extract_contours(img: &ImageBuffer<Luma<u16>, Vec<u16>>, levels: &[u16]) -> Vec<Vec<Point>> {
+    let (width, height) = img.dimensions();
+    if width < 2 || height < 2 {
+        return Vec::new();
+    }
+
+    let mut contours = Vec::new();
+
+    for &level in levels {
+        let value = |x: u32, y: u32| img.get_pixel(x, y)[0] as f64;
+        let level = level as f64;
+        let mut segments: Vec<(Edge, Edge)> = Vec::new();
+
+        for y in 0..height - 1 {
+            for x in 0..width - 1 {
+                let corners = [
+                    value(x, y),
+                    value(x + 1, y),
+                    value(x + 1, y + 1),
+                    value(x, y + 1),
+                ];
+                let case = corners
+                    .iter()
+                    .fold(0, |case, &corner| (case << 1) | (corner < level) as u8);
+
+                let top = (x, y, true);
+                let right = (x + 1, y, false);
+                let bottom = (x, y + 1, true);
+                let left = (x, y, false);
+                let center_inside = corners.iter().sum::<f64>() / 4.0 < level;
+
+                // Corner bits are top-left, top-right, bottom-right, bottom-left
+                // from the highest down
+                match case {
+                    0b0001 | 0b1110 => segments.push((left, bottom)),
+                    0b0010 | 0b1101 => segments.push((bottom, right)),
+                    0b0011 | 0b1100 => segments.push((left, right)),
+                    0b0100 | 0b1011 => segments.push((top, right)),
+                    0b0110 | 0b1001 => segments.push((top, bottom)),
+                    0b0111 | 0b1000 => segments.push((left, top)),
+                    0b0101 if center_inside => {
+                        segments.extend([(left, top), (bottom, right)]);
+                    }
+                    0b0101 => segments.extend([(left, bottom), (top, right)]),
+                    0b1010 if center_inside => {
+                        segments.extend([(top, right), (left, bottom)]);
+                    }
+                    0b1010 => segments.extend([(left, top), (bottom, right)]),
+                    _ => {}
+                }
+            }
+        }
+
+        let position = |&(x, y, horizontal): &Edge| {
+            let (x2, y2) = if horizontal { (x + 1, y) } else { (x, y + 1) };
+            let (from, to) = (value(x, y), value(x2, y2));
+            let t = if from == to {
+                0.5
+            } else {
+                ((level - from) / (to - from)).clamp(0.0, 1.0)
+            };
+
+            if horizontal {
+                Point::new((x as f64 + t).round() as i32, y as i32)
+            } else {
+                Point::new(x as i32, (y as f64 + t).round() as i32)
+            }
+        };
+
+        for chain in chain_segments(&segments) {
+            let mut line: Vec<Point> = chain.iter().map(position).collect();
+            line.dedup();
+            contours.push(line);
+        }
+    }
+
+    contours
+}
+
+/// Joins segments sharing an edge into chains of edges
+fn chain_segments(segments: &[(Edge, Edge)]) -> Vec<Vec<Edge>> {
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (index, &(a, b)) in segments.iter().enumerate() {
+        by_edge.entry(a).or_default().push(index);
+        by_edge.entry(b).or_default().push(index);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut chains = Vec::new();
+
+    for (start, &(first, second)) in segments.iter().enumerate() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+
+        let mut forward = vec![first, second];
+        extend_chain(&mut forward, segments, &by_edge, &mut used);
+
+        let mut backward = vec![first];
+        extend_chain(&mut backward, segments, &by_edge, &mut used);
+
+        backward.reverse();
+        backward.pop();
+        backward.extend(forward);
+        chains.push(backward);
+    }
+
+    chains
+}
+
+/// Follows unused segments from the last edge of `chain` until none are left
+fn extend_chain(
+    chain: &mut Vec<Edge>,
+    segments: &[(Edge, Edge)],
+    by_edge: &HashMap<Edge, Vec<usize>>,
+    used: &mut [bool],
+) {
+    loop {
+        let end = *chain.last().unwrap();
+        let next = by_edge[&end].iter().copied().find(|&index| !used[index]);
+
+        let Some(next) = next else {
+            break;
+        };
+        used[next] = true;
+
+        let (a, b) = segments[next];
+        chain.push(if a == end { b } else { a });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radial_gradient_gives_concentric_rings() {
+        // Black in the middle, brightening outwards until white 32 pixels out
+        let img = ImageBuffer::from_fn(64, 64, |x, y| {
+            let radius = ((x as f64 - 32.0).powi(2) + (y as f64 - 32.0).powi(2)).sqrt();
+            Luma([((radius / 32.0).min(1.0) * u16::MAX as f64) as u16])
+        });
+
+        let contours = extract_contours(&img, &contour_levels(3));
+
+        assert_eq!(contours.len(), 3);
+        for (contour, expected_radius) in contours.iter().zip([8.0, 16.0, 24.0]) {
+            assert_eq!(contour.first(), contour.last(), "contour isn't closed");
+            for point in contour {
+                let radius =
+                    ((point.x as f64 - 32.0).powi(2) + (point.y as f64 - 32.0).powi(2)).sqrt();
+                assert!(
+                    (radius - expected_radius).abs() < 1.5,
+                    "{point:?} is off the ring"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn levels_split_the_range_evenly() {
+        assert_eq!(contour_levels(3), vec![16383, 32767, 49151]);
+    }
+}
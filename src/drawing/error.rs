@@ -0,0 +1,40 @@
+use inquire::InquireError;
+use std::{error::Error, fmt};
+
+/// Reason a drawing stopped before all of its lines were drawn
+#[derive(Debug)]
+pub enum DrawError {
+    /// The input backend kept failing to simulate `action`, even after a retry
+    Input {
+        action: &'static str,
+        source: Box<dyn Error>,
+    },
+
+    /// A question asked along the way couldn't be answered, e.g. because it
+    /// was cancelled
+    Prompt(InquireError),
+}
+
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::Input { action, source } => write!(f, "Failed to {}: {}", action, source),
+            DrawError::Prompt(e) => write!(f, "Failed to get user input: {}", e),
+        }
+    }
+}
+
+impl Error for DrawError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DrawError::Input { source, .. } => Some(source.as_ref()),
+            DrawError::Prompt(e) => Some(e),
+        }
+    }
+}
+
+impl From<InquireError> for DrawError {
+    fn from(e: InquireError) -> Self {
+        DrawError::Prompt(e)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{
+    choices::{LineOrder, SamplingPattern},
+    drawing::{
+        components::{
+            auto_grid_size, find_connected_components, grid_size_for_step, trace_components,
+            SpatialIndex,
+        },
+        config::DrawConfig,
+        sampling::{sample_by_detail, sample_poisson_disk},
+    },
+    utils::{
+        geometry::{Point, Polygon},
+        timing,
+    },
+};
+use image::{ImageBuffer, Luma, Primitive};
+use log::info;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Extracts lines following the black pixels of a binarized image, within
+/// the configured clip polygon if any
+///
+/// Points are sampled as set by [`DrawConfig::sampling`] and joined with the
+/// configured tracing strategy. Lines come out longest first, unless the line
+/// order keeps them as extracted.
+pub fn extract_paths<T: Primitive + Send + Sync>(
+    img: &ImageBuffer<Luma<T>, Vec<T>>,
+    config: &DrawConfig,
+) -> Vec<Vec<Point>> {
+    // The polygon is given in screen pixels, points are at the virtual resolution
+    let clip = config
+        .clip_polygon
+        .as_ref()
+        .map(|polygon| polygon.scaled(config.density_multiplier));
+
+    let (black_pixels, spatial_index) = {
+        let _timer = timing::stage("pixel extraction");
+        let (mut points, spatial_index) = match config.sampling {
+            SamplingPattern::Grid => {
+                let step = config.effective_step();
+                let grid_size = config
+                    .grid_size
+                    .unwrap_or_else(|| grid_size_for_step(step, config.connection_distance()));
+
+                get_black_pixels_adaptive(img, step, clip.as_ref(), Some(grid_size))
+            }
+            SamplingPattern::Adaptive => {
+                let (min_step, max_step) = config.adaptive_step_range();
+                (sample_by_detail(img, min_step, max_step), None)
+            }
+            SamplingPattern::PoissonDisk => (
+                sample_poisson_disk(img, config.effective_step(), config.seed),
+                None,
+            ),
+        };
+
+        if let (Some(clip), SamplingPattern::Adaptive | SamplingPattern::PoissonDisk) =
+            (&clip, config.sampling)
+        {
+            points.retain(|&point| clip.contains(point));
+        }
+
+        // Only grid sampling indexes while scanning, the others are indexed
+        // here once their density is known
+        let spatial_index = spatial_index.unwrap_or_else(|| {
+            let grid_size = config
+                .grid_size
+                .unwrap_or_else(|| auto_grid_size(&points, config.connection_distance()));
+            SpatialIndex::new(&points, grid_size)
+        });
+
+        (points, spatial_index)
+    };
+    info!("Found {} black pixels to draw", black_pixels.len());
+
+    if black_pixels.is_empty() {
+        return Vec::new();
+    }
+
+    let _timer = timing::stage("component finding");
+    if config.line_order == LineOrder::AsExtracted {
+        trace_components(
+            black_pixels,
+            config.connection_distance(),
+            Some(spatial_index),
+            config.bridge_radius,
+            config.tracing,
+            config.split_junctions,
+        )
+    } else {
+        find_connected_components(
+            black_pixels,
+            config.connection_distance(),
+            Some(spatial_index),
+            config.bridge_radius,
+            config.tracing,
+            config.split_junctions,
+        )
+    }
+}
+
+/// Samples the black pixels of `img` every `step` pixels, skipping those
+/// outside `clip` if given
+///
+/// With a `grid_size`, the points are also indexed into cells of that size
+/// during the same scan, so tracing doesn't have to go over them again.
+pub fn get_black_pixels_adaptive<T: Primitive + Send + Sync>(
+    img: &ImageBuffer<Luma<T>, Vec<T>>,
+    step: i32,
+    clip: Option<&Polygon>,
+    grid_size: Option<i32>,
+) -> (HashSet<Point>, Option<SpatialIndex>) {
+    let step = step.max(1) as usize;
+    let new_index = || grid_size.map(SpatialIndex::empty);
+
+    // Each thread scans whole sampled rows into its own set and the sets are
+    // merged at the end, avoiding an intermediate list of every coordinate
+    (0..img.height())
+        .into_par_iter()
+        .step_by(step)
+        .fold(
+            || (HashSet::new(), new_index()),
+            |(mut points, mut index), y| {
+                for x in (0..img.width()).step_by(step) {
+                    let point = Point::new(x as i32, y as i32);
+                    if img.get_pixel(x, y)[0] == T::DEFAULT_MIN_VALUE
+                        && clip.is_none_or(|clip| clip.contains(point))
+                    {
+                        points.insert(point);
+                        if let Some(index) = &mut index {
+                            index.insert(point);
+                        }
+                    }
+                }
+
+                (points, index)
+            },
+        )
+        .reduce(
+            || (HashSet::new(), new_index()),
+            |(mut merged, mut merged_index), (mut points, mut index)| {
+                if merged.len() < points.len() {
+                    std::mem::swap(&mut merged, &mut points);
+                    std::mem::swap(&mut merged_index, &mut index);
+                }
+
+                merged.extend(points);
+                if let (Some(merged_index), Some(index)) = (&mut merged_index, index) {
+                    merged_index.merge(index);
+                }
+
+                (merged, merged_index)
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::choices::{get_step, DrawingAccuracy};
+
+    #[test]
+    fn diagonal_samples_connect_at_every_accuracy() {
+        // Grid samples of a diagonal are `step * √2` apart, the farthest
+        // neighbors a grid can have
+        let img = ImageBuffer::from_fn(61, 61, |x, y| Luma([if x == y { 0u8 } else { 255 }]));
+
+        for accuracy in DrawingAccuracy::ALL {
+            let step = get_step(accuracy);
+            let config = DrawConfig {
+                step,
+                sampling: SamplingPattern::Grid,
+                ..DrawConfig::default()
+            };
+
+            let lines = extract_paths(&img, &config);
+            assert_eq!(lines.len(), 1, "{:?}", accuracy);
+            assert_eq!(lines[0].len(), 60 / step as usize + 1, "{:?}", accuracy);
+        }
+    }
+}
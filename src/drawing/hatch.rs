@@ -0,0 +1,244 @@
+use crate::utils::geometry::Point;
+use image::{ImageBuffer, Luma};
+use serde::{Deserialize, Serialize};
+
+/// Hatch runs shorter than this many pixels are dropped as specks
+const MIN_RUN_LENGTH: f64 = 2.0;
+
+/// One angle of a cross-hatched drawing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HatchPass {
+    /// Direction of the lines in degrees, 0 being horizontal
+    pub angle: f64,
+
+    /// Darkness (`0.0` white to `1.0` black) an area needs to get this pass
+    pub min_darkness: f64,
+}
+
+impl HatchPass {
+    /// Classic ink-sketch passes: diagonal everywhere, the opposite diagonal
+    /// from mid-tones on and horizontal lines in the darkest areas
+    pub fn defaults() -> Vec<HatchPass> {
+        vec![
+            HatchPass {
+                angle: 45.0,
+                min_darkness: 0.0,
+            },
+            HatchPass {
+                angle: -45.0,
+                min_darkness: 0.5,
+            },
+            HatchPass {
+                angle: 0.0,
+                min_darkness: 0.75,
+            },
+        ]
+    }
+}
+
+/// Hatches `tone` once per pass, each pass only covering areas at least as
+/// dark as its threshold, so darker areas get more crossing angles
+///
+/// Lines are returned pass by pass, see [`hatch_lines`] for the spacing.
+pub fn cross_hatch_lines(
+    tone: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    passes: &[HatchPass],
+    min_spacing: f64,
+    max_spacing: f64,
+) -> Vec<Vec<Point>> {
+    passes
+        .iter()
+        .flat_map(|pass| {
+            hatch_lines(
+                tone,
+                pass.angle,
+                min_spacing,
+                max_spacing,
+                pass.min_darkness,
+            )
+        })
+        .collect()
+}
+
+/// Generates parallel hatch segments across the dark areas of `tone`
+///
+/// Candidate lines run at `angle` degrees (0 is horizontal, increasing
+/// clockwise on screen) and are `min_spacing` pixels apart. Each area only
+/// keeps enough of them that their spacing roughly equals
+/// `min_spacing / darkness`, so black areas get every line while mid-tones get
+/// every second, fourth, eighth... line. Spacings are halved and doubled rather
+/// than varied continuously so the lines of neighboring tones join up instead
+/// of ending in ragged stubs. Areas light enough to need more than
+/// `max_spacing` are left blank, as are areas with a darkness below
+/// `threshold`.
+///
+/// Every returned line is a single straight segment given by its two end
+/// points, with consecutive scan lines running in alternating directions to
+/// keep pen-up travel short.
+pub fn hatch_lines(
+    tone: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    angle: f64,
+    min_spacing: f64,
+    max_spacing: f64,
+    threshold: f64,
+) -> Vec<Vec<Point>> {
+    let (width, height) = tone.dimensions();
+    let min_spacing = min_spacing.max(1.0);
+    let levels = (max_spacing / min_spacing).log2().floor().max(0.0) as u32;
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let corners = [
+        (0.0, 0.0),
+        (width as f64, 0.0),
+        (0.0, height as f64),
+        (width as f64, height as f64),
+    ];
+    let (offset_min, offset_max) = projected_range(&corners, (-sin, cos));
+    let (along_min, along_max) = projected_range(&corners, (cos, sin));
+
+    let darkness_at = |x: f64, y: f64| {
+        if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+            return None;
+        }
+
+        let pixel = tone.get_pixel(x as u32, y as u32)[0];
+        Some(1.0 - pixel as f64 / u16::MAX as f64)
+    };
+
+    let line_count = ((offset_max - offset_min) / min_spacing).ceil() as u32;
+    let mut lines = Vec::new();
+
+    for index in 0..=line_count {
+        // Every second line drops out where the target spacing doubles, so a
+        // line's rank is how many doublings it survives
+        let rank = if index == 0 {
+            levels
+        } else {
+            index.trailing_zeros().min(levels)
+        };
+        let min_darkness = 0.5f64.powf(rank as f64 + 0.5).max(threshold);
+
+        let offset = offset_min + index as f64 * min_spacing;
+        let (base_x, base_y) = (-sin * offset, cos * offset);
+
+        let mut segments = Vec::new();
+        let mut run: Option<((f64, f64), (f64, f64))> = None;
+        let mut along = along_min;
+
+        while along <= along_max + 1.0 {
+            let (x, y) = (base_x + cos * along, base_y + sin * along);
+            let dark = darkness_at(x, y).is_some_and(|darkness| darkness > min_darkness);
+
+            run = match (run, dark) {
+                (Some((start, _)), true) => Some((start, (x, y))),
+                (None, true) => Some(((x, y), (x, y))),
+                (Some((start, end)), false) => {
+                    push_run(&mut segments, start, end);
+                    None
+                }
+                (None, false) => None,
+            };
+
+            along += 1.0;
+        }
+
+        if let Some((start, end)) = run {
+            push_run(&mut segments, start, end);
+        }
+
+        if index % 2 == 1 {
+            segments.reverse();
+            segments.iter_mut().for_each(|segment| segment.reverse());
+        }
+
+        lines.extend(segments);
+    }
+
+    lines
+}
+
+/// Smallest and largest projection of `points` onto `axis`
+fn projected_range(points: &[(f64, f64)], axis: (f64, f64)) -> (f64, f64) {
+    points
+        .iter()
+        .map(|&(x, y)| x * axis.0 + y * axis.1)
+        .fold((f64::MAX, f64::MIN), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+/// Adds the run from `start` to `end` as a two-point line unless it is a speck
+fn push_run(segments: &mut Vec<Vec<Point>>, start: (f64, f64), end: (f64, f64)) {
+    let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+    if length < MIN_RUN_LENGTH {
+        return;
+    }
+
+    segments.push(vec![
+        Point::new(start.0 as i32, start.1 as i32),
+        Point::new(end.0 as i32, end.1 as i32),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fully black top half over a light gray (30% dark) bottom half
+    fn dark_over_light() -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(40, 40, |_, y| Luma([if y < 20 { 0 } else { 45875 }]))
+    }
+
+    #[test]
+    fn darker_areas_get_denser_lines() {
+        let lines = hatch_lines(&dark_over_light(), 0.0, 2.0, 16.0, 0.0);
+
+        let dark = lines.iter().filter(|line| line[0].y < 20).count();
+        let light = lines.len() - dark;
+        assert_eq!(dark, 10);
+        assert!(
+            light > 0 && light * 3 <= dark,
+            "{dark} dark vs {light} light"
+        );
+    }
+
+    #[test]
+    fn lines_are_spaced_evenly_in_black_areas() {
+        let black = ImageBuffer::from_pixel(40, 40, Luma([0u16]));
+        let rows: Vec<_> = hatch_lines(&black, 0.0, 4.0, 16.0, 0.0)
+            .iter()
+            .map(|line| line[0].y)
+            .collect();
+
+        assert_eq!(rows, (0..40).step_by(4).collect::<Vec<_>>());
+    }
+
+    /// Directions (in degrees, 0 to 180) of the lines longer than a few pixels
+    fn line_angles(lines: &[Vec<Point>]) -> Vec<i32> {
+        let mut angles: Vec<i32> = lines
+            .iter()
+            .filter(|line| line[0].distance_squared(&line[1]) > 100)
+            .map(|line| {
+                let (dx, dy) = (line[1].x - line[0].x, line[1].y - line[0].y);
+                let angle = (dy as f64).atan2(dx as f64).to_degrees().rem_euclid(180.0);
+                ((angle / 15.0).round() as i32 * 15) % 180
+            })
+            .collect();
+        angles.sort_unstable();
+        angles.dedup();
+        angles
+    }
+
+    #[test]
+    fn darker_areas_get_more_passes() {
+        let black = ImageBuffer::from_pixel(40, 40, Luma([0u16]));
+        let light = ImageBuffer::from_pixel(40, 40, Luma([45875u16]));
+        let passes = HatchPass::defaults();
+
+        let black_angles = line_angles(&cross_hatch_lines(&black, &passes, 2.0, 16.0));
+        let light_angles = line_angles(&cross_hatch_lines(&light, &passes, 2.0, 16.0));
+
+        assert_eq!(black_angles, vec![0, 45, 135]);
+        assert_eq!(light_angles, vec![45]);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{choices::ScalingMode, drawing::config::DrawConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Number of past drawing settings kept in the history
+pub const HISTORY_LEN: usize = 10;
+
+/// Drawing settings used by a past run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// How the image was scaled into the region
+    pub scaling_mode: ScalingMode,
+
+    /// Every other drawing setting
+    pub config: DrawConfig,
+}
+
+impl HistoryEntry {
+    /// One-line description of the entry to pick it by
+    pub fn summary(&self) -> String {
+        format!(
+            "{:?} scaling, step {}, {:?} sampling, {:?} per point, {:?} lines",
+            self.scaling_mode,
+            self.config.step,
+            self.config.sampling,
+            self.config.point_delay,
+            self.config.line_order
+        )
+    }
+}
+
+/// On-disk list of the settings of the last [`HISTORY_LEN`] drawings, newest
+/// first, stored as JSON
+pub struct History {
+    path: PathBuf,
+}
+
+impl Default for History {
+    /// History in the user's data directory, so it survives reboots and isn't
+    /// shared with other users (the temp directory if there is none)
+    fn default() -> Self {
+        let dir = dirs::data_dir().unwrap_or_else(std::env::temp_dir);
+        Self::at(dir.join("drawrs").join("history.json"))
+    }
+}
+
+impl History {
+    /// History stored in the file at `path`
+    pub fn at(path: impl AsRef<Path>) -> Self {
+        History {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Past settings, newest first
+    ///
+    /// # Errors
+    /// Fails if the history exists but can't be read or parsed. A missing
+    /// history is empty.
+    pub fn load(&self) -> Result<Vec<HistoryEntry>, Box<dyn Error>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Adds `entry` as the newest settings, moving it to the front if it's
+    /// already there and dropping the oldest beyond [`HISTORY_LEN`]
+    pub fn record(&self, entry: HistoryEntry) -> Result<(), Box<dyn Error>> {
+        let mut entries = self.load().unwrap_or_default();
+        entries.retain(|existing| *existing != entry);
+        entries.insert(0, entry);
+        entries.truncate(HISTORY_LEN);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(step: i32) -> HistoryEntry {
+        HistoryEntry {
+            scaling_mode: ScalingMode::Fit,
+            config: DrawConfig {
+                step,
+                time_budget: Some(Duration::from_secs(600)),
+                ..DrawConfig::default()
+            },
+        }
+    }
+
+    #[test]
+    fn entries_round_trip_newest_first() {
+        let dir = std::env::temp_dir().join(format!("drawrs-history-test-{}", std::process::id()));
+        let history = History::at(dir.join("history.json"));
+
+        assert!(history.load().unwrap().is_empty());
+        for step in 0..HISTORY_LEN as i32 + 2 {
+            history.record(entry(step)).unwrap();
+        }
+        history.record(entry(5)).unwrap();
+
+        let entries = history.load().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), HISTORY_LEN);
+        assert_eq!(entries[0], entry(5));
+        assert_eq!(entries[1], entry(HISTORY_LEN as i32 + 1));
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|&existing| *existing == entry(5))
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    crate::{drawing::recording::InputEvent, utils::screen::primary_monitor_size},
+    enigo::{Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings},
+    std::error::Error,
+};
+
+/// Sink for the simulated input issued while drawing
+///
+/// Keeping the drawing code behind this trait lets it drive something other
+/// than the real cursor, such as a recorder.
+pub trait InputBackend {
+    /// Moves the cursor to an absolute screen position
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Box<dyn Error>>;
+
+    /// Presses and holds a mouse button
+    fn button_press(&mut self, button: Button) -> Result<(), Box<dyn Error>>;
+
+    /// Releases a held mouse button
+    fn button_release(&mut self, button: Button) -> Result<(), Box<dyn Error>>;
+
+    /// Presses, releases or clicks a keyboard key
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), Box<dyn Error>>;
+
+    /// Size (in pixels) of the primary display
+    fn display_size(&self) -> Result<(i32, i32), Box<dyn Error>>;
+}
+
+/// Backend that moves the real cursor through `enigo`
+pub struct EnigoBackend {
+    enigo: Enigo,
+}
+
+impl EnigoBackend {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(EnigoBackend {
+            enigo: Enigo::new(&Settings::default())?,
+        })
+    }
+}
+
+impl InputBackend for EnigoBackend {
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Box<dyn Error>> {
+        Ok(self.enigo.move_mouse(x, y, Coordinate::Abs)?)
+    }
+
+    fn button_press(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        Ok(self.enigo.button(button, Direction::Press)?)
+    }
+
+    fn button_release(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        Ok(self.enigo.button(button, Direction::Release)?)
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), Box<dyn Error>> {
+        Ok(self.enigo.key(key, direction)?)
+    }
+
+    fn display_size(&self) -> Result<(i32, i32), Box<dyn Error>> {
+        primary_monitor_size(&self.enigo)
+    }
+}
+
+/// Backend that simulates nothing and keeps every event in memory, for
+/// tests and dry runs
+///
+/// It can be told to fail once a number of events went through, and to recover
+/// after a number of failed attempts, to exercise the error handling of the
+/// drawing code.
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    /// Every event that went through, oldest first
+    pub events: Vec<InputEvent>,
+
+    /// Size reported by [`display_size`](InputBackend::display_size)
+    pub display: (i32, i32),
+
+    /// Number of events accepted before every further one fails (`None`
+    /// never fails)
+    pub fail_after: Option<usize>,
+
+    /// Number of failed attempts after which events are accepted again
+    /// (`None` keeps failing)
+    pub recover_after: Option<usize>,
+
+    /// Number of events simulated so far, including the ones that failed
+    pub attempts: usize,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        MockBackend {
+            events: Vec::new(),
+            display: (1920, 1080),
+            fail_after: None,
+            recover_after: None,
+            attempts: 0,
+        }
+    }
+
+    /// Mock backend whose input fails once `events` events went through
+    pub fn failing_after(events: usize) -> Self {
+        MockBackend {
+            fail_after: Some(events),
+            ..Self::new()
+        }
+    }
+
+    /// Accepts events again after `failures` failed attempts
+    pub fn recovering_after(mut self, failures: usize) -> Self {
+        self.recover_after = Some(failures);
+        self
+    }
+
+    fn push(&mut self, event: InputEvent) -> Result<(), Box<dyn Error>> {
+        let failures = self.attempts - self.events.len();
+        self.attempts += 1;
+
+        if self
+            .fail_after
+            .is_some_and(|limit| self.events.len() >= limit)
+            && self
+                .recover_after
+                .is_none_or(|recover_after| failures < recover_after)
+        {
+            return Err(format!("mock input failure on {:?}", event).into());
+        }
+
+        self.events.push(event);
+        Ok(())
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputBackend for MockBackend {
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Box<dyn Error>> {
+        self.push(InputEvent::Move { x, y })
+    }
+
+    fn button_press(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        self.push(InputEvent::ButtonPress(button))
+    }
+
+    fn button_release(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        self.push(InputEvent::ButtonRelease(button))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), Box<dyn Error>> {
+        self.push(InputEvent::Key(key, direction))
+    }
+
+    fn display_size(&self) -> Result<(i32, i32), Box<dyn Error>> {
+        Ok(self.display)
+    }
+}
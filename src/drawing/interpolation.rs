@@ -0,0 +1,134 @@
+/// Number of intermediate moves used to cover a segment of the given squared length
+///
+/// `density` is the number of moves per pixel of distance; the result is clamped
+/// to at least one move so every segment still reaches its end point.
+pub fn interpolation_steps(distance_squared: i32, density: f64) -> i32 {
+    (((distance_squared as f64).sqrt() * density).round() as i32).max(1)
+}
+
+/// Linearly interpolates `steps` evenly spaced positions between two screen positions
+///
+/// The starting position is excluded and the target position is always the last
+/// element, so moving through the result ends exactly on `to`. At least one
+/// position is always returned.
+pub fn interpolate_positions(from: (i32, i32), to: (i32, i32), steps: u32) -> Vec<(i32, i32)> {
+    let steps = steps.max(1);
+
+    (1..=steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            let x = from.0 as f64 + t * (to.0 - from.0) as f64;
+            let y = from.1 as f64 + t * (to.1 - from.1) as f64;
+
+            (x.round() as i32, y.round() as i32)
+        })
+        .collect()
+}
+
+/// Drops mouse moves that land too close to the last issued position
+///
+/// Distances are measured from the last position actually sent, so a run of
+/// tiny skipped moves still adds up to a real move once it reaches
+/// `min_move` and the cursor never drifts from the intended path.
+pub struct MoveFilter {
+    min_move: f64,
+    last_issued: Option<(i32, i32)>,
+    pending: Option<(i32, i32)>,
+}
+
+impl MoveFilter {
+    /// Creates a filter that skips moves shorter than `min_move` pixels
+    pub fn new(min_move: f64) -> Self {
+        MoveFilter {
+            min_move: min_move.max(0.0),
+            last_issued: None,
+            pending: None,
+        }
+    }
+
+    /// Records a position the cursor was moved to outside of the filter
+    pub fn reset(&mut self, pos: (i32, i32)) {
+        self.last_issued = Some(pos);
+        self.pending = None;
+    }
+
+    /// Returns whether a move to `pos` should be issued
+    pub fn accept(&mut self, pos: (i32, i32)) -> bool {
+        if let Some(last) = self.last_issued {
+            let dx = (pos.0 - last.0) as f64;
+            let dy = (pos.1 - last.1) as f64;
+
+            if dx * dx + dy * dy < self.min_move * self.min_move {
+                self.pending = Some(pos);
+                return false;
+            }
+        }
+
+        self.reset(pos);
+        true
+    }
+
+    /// Takes the last skipped position, if any, so it can be issued before the
+    /// pen is lifted
+    pub fn flush(&mut self) -> Option<(i32, i32)> {
+        let pending = self.pending.take();
+        if pending.is_some() {
+            self.last_issued = pending;
+        }
+
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_sets_moves_per_pixel() {
+        // A 3-4-5 segment is 5 pixels long
+        assert_eq!(interpolation_steps(25, 1.0), 5);
+        assert_eq!(interpolation_steps(25, 2.0), 10);
+        assert_eq!(interpolation_steps(25, 0.4), 2);
+    }
+
+    #[test]
+    fn interpolation_takes_at_least_one_step() {
+        assert_eq!(interpolation_steps(25, 0.0), 1);
+        assert_eq!(interpolation_steps(0, 1.0), 1);
+    }
+
+    #[test]
+    fn interpolated_positions_end_on_target() {
+        let positions = interpolate_positions((0, 0), (10, 0), 5);
+
+        assert_eq!(positions, vec![(2, 0), (4, 0), (6, 0), (8, 0), (10, 0)]);
+    }
+
+    #[test]
+    fn near_identical_points_collapse_to_one_move() {
+        let mut filter = MoveFilter::new(1.0);
+        let issued: Vec<_> = [(5, 5), (5, 5), (5, 5), (5, 5)]
+            .into_iter()
+            .filter(|&pos| filter.accept(pos))
+            .collect();
+
+        assert_eq!(issued, vec![(5, 5)]);
+        assert_eq!(filter.flush(), Some((5, 5)));
+    }
+
+    #[test]
+    fn skipped_moves_add_up_without_drifting() {
+        let mut filter = MoveFilter::new(3.0);
+        filter.reset((0, 0));
+
+        let issued: Vec<_> = (1..=7)
+            .map(|x| (x, 0))
+            .filter(|&pos| filter.accept(pos))
+            .collect();
+
+        assert_eq!(issued, vec![(3, 0), (6, 0)]);
+        assert_eq!(filter.flush(), Some((7, 0)));
+        assert_eq!(filter.flush(), None);
+    }
+}
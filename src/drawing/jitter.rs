@@ -0,0 +1,63 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Perturbs drawn coordinates by a small random offset for a hand-drawn look
+///
+/// Uses a seeded RNG so the same seed and path set always produce the same
+/// strokes.
+pub struct Jitter {
+    amplitude: f64,
+    rng: StdRng,
+}
+
+impl Jitter {
+    /// Creates a jitter source with the given maximum offset (in pixels) and seed
+    pub fn new(amplitude: f64, seed: u64) -> Self {
+        Jitter {
+            amplitude: amplitude.max(0.0),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Offsets a coordinate by up to `amplitude` pixels along each axis
+    ///
+    /// Coordinates are returned unchanged when the amplitude is zero.
+    pub fn apply(&mut self, x: f64, y: f64) -> (f64, f64) {
+        if self.amplitude == 0.0 {
+            return (x, y);
+        }
+
+        (
+            x + self.rng.random_range(-self.amplitude..=self.amplitude),
+            y + self.rng.random_range(-self.amplitude..=self.amplitude),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_amplitude_keeps_coordinates() {
+        let mut jitter = Jitter::new(0.0, 7);
+
+        for i in 0..100 {
+            let (x, y) = (i as f64, i as f64 * 0.5);
+            assert_eq!(jitter.apply(x, y), (x, y));
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_offsets() {
+        let mut first = Jitter::new(2.0, 42);
+        let mut second = Jitter::new(2.0, 42);
+
+        for i in 0..100 {
+            let (x, y) = (i as f64, 10.0);
+            let offset = first.apply(x, y);
+
+            assert_eq!(offset, second.apply(x, y));
+            assert!((offset.0 - x).abs() <= 2.0 && (offset.1 - y).abs() <= 2.0);
+        }
+    }
+}
@@ -1,3 +1,28 @@
 pub mod app;
+pub mod checkpoint;
 pub mod components;
+pub mod config;
+pub mod contour;
+pub mod error;
+pub mod extraction;
+pub mod hatch;
+pub mod history;
+pub mod input;
+pub mod interpolation;
+pub mod jitter;
+pub mod opacity;
+pub mod ordering;
+#[cfg(feature = "overlay")]
+pub mod overlay;
 pub mod pathfinding;
+pub mod pattern;
+pub mod progress;
+pub mod recording;
+pub mod sampling;
+pub mod simplify;
+pub mod smoothing;
+pub mod spanning_tree;
+pub mod stipple;
+pub mod strokes;
+pub mod svg;
+pub mod visualize;
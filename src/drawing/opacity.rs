@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Pause after each opacity key press so the target app registers every one
+pub const KEY_DELAY: Duration = Duration::from_millis(15);
+
+/// Keyboard shortcuts used to approximate pen pressure by stepping the brush
+/// opacity before each line
+///
+/// The app's opacity can't be read back, so drawing starts by pressing
+/// `decrease` enough times to reach the lowest level, and from then on only the
+/// difference to each line's target level is pressed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpacityKeys {
+    /// Key that raises the opacity by one step
+    pub increase: char,
+
+    /// Key that lowers the opacity by one step
+    pub decrease: char,
+
+    /// Number of distinct opacity levels reachable with the keys
+    pub steps: u32,
+
+    /// Exponent applied to the darkness before mapping it to a level; values
+    /// above 1 keep midtones lighter, values below 1 make them heavier
+    pub gamma: f64,
+}
+
+impl OpacityKeys {
+    /// Opacity level (`0` lightest to `steps - 1` heaviest) for a line with the
+    /// given average brightness (`0.0` black to `1.0` white)
+    pub fn level_for(&self, brightness: f64) -> u32 {
+        let darkness = (1.0 - brightness).clamp(0.0, 1.0);
+        let max_level = self.steps.max(1) - 1;
+
+        (darkness.powf(self.gamma.max(f64::EPSILON)) * max_level as f64).round() as u32
+    }
+}
+
+impl Default for OpacityKeys {
+    fn default() -> Self {
+        OpacityKeys {
+            increase: 'o',
+            decrease: 'i',
+            steps: 10,
+            gamma: 1.0,
+        }
+    }
+}
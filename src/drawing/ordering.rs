@@ -0,0 +1,339 @@
+use crate::utils::geometry::Point;
+use image::{ImageBuffer, Luma};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::collections::HashMap;
+
+/// Sorts lines from the darkest to the lightest, judged by the average source
+/// brightness under their points
+///
+/// `tone` must be the grayscale source scaled to the same size as the binary
+/// image the lines were extracted from. Lines of equal brightness keep their
+/// relative order.
+pub fn order_by_brightness(lines: &mut [Vec<Point>], tone: &ImageBuffer<Luma<u16>, Vec<u16>>) {
+    lines.sort_by_cached_key(|line| (average_brightness(line, tone) * 65535.0) as u32);
+}
+
+/// Average brightness (`0.0` black to `1.0` white) of `tone` under the points
+/// of a line, ignoring points outside of the image
+pub fn average_brightness(line: &[Point], tone: &ImageBuffer<Luma<u16>, Vec<u16>>) -> f64 {
+    let (sum, count) = line
+        .iter()
+        .filter_map(|point| {
+            let (x, y) = (u32::try_from(point.x).ok()?, u32::try_from(point.y).ok()?);
+            tone.get_pixel_checked(x, y)
+        })
+        .fold((0u64, 0u64), |(sum, count), pixel| {
+            (sum + pixel[0] as u64, count + 1)
+        });
+
+    if count == 0 {
+        return 1.0;
+    }
+
+    sum as f64 / count as f64 / u16::MAX as f64
+}
+
+/// Shuffles lines into a random order that only depends on `seed`, so a
+/// shuffled drawing can be reproduced or resumed
+pub fn shuffle_seeded(lines: &mut [Vec<Point>], seed: u64) {
+    lines.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// Total length (in pixels) of the segments of a line
+pub fn path_length(line: &[Point]) -> f64 {
+    line.windows(2)
+        .map(|pair| (pair[0].distance_squared(&pair[1]) as f64).sqrt())
+        .sum()
+}
+
+/// Drops all but the `count` longest lines, keeping the survivors in their
+/// current order
+///
+/// Lines are compared by [`path_length`], and lines of equal length by their
+/// position in the list, so the result is deterministic.
+pub fn keep_longest(lines: &mut Vec<Vec<Point>>, count: usize) {
+    if lines.len() <= count {
+        return;
+    }
+
+    let mut by_length: Vec<(usize, f64)> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| (index, path_length(line)))
+        .collect();
+    by_length.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut keep = vec![false; lines.len()];
+    for &(index, _) in &by_length[..count] {
+        keep[index] = true;
+    }
+
+    let mut index = 0;
+    lines.retain(|_| {
+        index += 1;
+        keep[index - 1]
+    });
+}
+
+/// Drops lines whose [`path_length`] is below `min_len` or above `max_len`,
+/// keeping the rest in their current order
+///
+/// Either bound can be `None` to leave that side open.
+pub fn filter_by_length(lines: &mut Vec<Vec<Point>>, min_len: Option<f64>, max_len: Option<f64>) {
+    let range = min_len.unwrap_or(0.0)..=max_len.unwrap_or(f64::INFINITY);
+    lines.retain(|line| range.contains(&path_length(line)));
+}
+
+/// Number of points at each end of a line used to measure its direction there
+const DIRECTION_SPAN: usize = 4;
+
+/// Joins lines whose ends nearly touch and continue in the same direction, so
+/// a stroke broken into pieces is drawn without lifting the pen
+///
+/// A line is extended from either end by the nearest line that starts or ends
+/// within `gap_tol` pixels, as long as both lines and the gap between them
+/// point within `angle_tol` degrees of each other. Lines are reversed as needed
+/// to join them end to start, and the merged lines keep the position of the
+/// first line they contain.
+pub fn merge_collinear(lines: Vec<Vec<Point>>, angle_tol: f64, gap_tol: f64) -> Vec<Vec<Point>> {
+    let min_cos = angle_tol.clamp(0.0, 180.0).to_radians().cos();
+    let cell = gap_tol.ceil().max(1.0) as i32;
+    let cell_of = |point: Point| (point.x.div_euclid(cell), point.y.div_euclid(cell));
+
+    let mut endpoints: HashMap<(i32, i32), Vec<(usize, bool)>> = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.len() >= 2 {
+            endpoints
+                .entry(cell_of(line[0]))
+                .or_default()
+                .push((index, true));
+            endpoints
+                .entry(cell_of(line[line.len() - 1]))
+                .or_default()
+                .push((index, false));
+        }
+    }
+
+    let mut lines: Vec<Option<Vec<Point>>> = lines.into_iter().map(Some).collect();
+    let mut merged = Vec::new();
+
+    for index in 0..lines.len() {
+        let Some(mut line) = lines[index].take() else {
+            continue;
+        };
+
+        if line.len() >= 2 {
+            // Extend forwards, then flip to extend backwards and flip back
+            for _ in 0..2 {
+                while let Some((other, at_start)) =
+                    find_continuation(&line, &lines, &endpoints, cell, min_cos, gap_tol)
+                {
+                    let mut other = lines[other]
+                        .take()
+                        .expect("Continuation was already merged");
+                    if !at_start {
+                        other.reverse();
+                    }
+
+                    if other.first() == line.last() {
+                        other.remove(0);
+                    }
+                    line.extend(other);
+                }
+
+                line.reverse();
+            }
+        }
+
+        merged.push(line);
+    }
+
+    merged
+}
+
+/// Finds the closest remaining line that continues `line` past its last point,
+/// returning its index and whether it is joined at its start
+fn find_continuation(
+    line: &[Point],
+    lines: &[Option<Vec<Point>>],
+    endpoints: &HashMap<(i32, i32), Vec<(usize, bool)>>,
+    cell: i32,
+    min_cos: f64,
+    gap_tol: f64,
+) -> Option<(usize, bool)> {
+    let end = line[line.len() - 1];
+    let heading = direction(line[line.len().saturating_sub(DIRECTION_SPAN + 1)], end)?;
+    let (cell_x, cell_y) = (end.x.div_euclid(cell), end.y.div_euclid(cell));
+
+    let mut best: Option<(i32, usize, bool)> = None;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for &(index, at_start) in endpoints
+                .get(&(cell_x + dx, cell_y + dy))
+                .into_iter()
+                .flatten()
+            {
+                let Some(other) = &lines[index] else {
+                    continue;
+                };
+
+                // Walk the candidate in the direction it would be drawn after `line`
+                let ordered: Vec<Point> = if at_start {
+                    other.iter().take(DIRECTION_SPAN + 1).copied().collect()
+                } else {
+                    other
+                        .iter()
+                        .rev()
+                        .take(DIRECTION_SPAN + 1)
+                        .copied()
+                        .collect()
+                };
+
+                let gap = end.distance_squared(&ordered[0]);
+                if gap as f64 > gap_tol * gap_tol {
+                    continue;
+                }
+
+                let aligned = |from: Point, to: Point| {
+                    direction(from, to).is_none_or(|dir| dot(heading, dir) >= min_cos)
+                };
+                if !aligned(end, ordered[0]) || !aligned(ordered[0], ordered[ordered.len() - 1]) {
+                    continue;
+                }
+
+                if best
+                    .is_none_or(|(best_gap, best_index, _)| (gap, index) < (best_gap, best_index))
+                {
+                    best = Some((gap, index, at_start));
+                }
+            }
+        }
+    }
+
+    best.map(|(_, index, at_start)| (index, at_start))
+}
+
+/// Unit vector from `from` to `to`, or `None` if they are the same point
+fn direction(from: Point, to: Point) -> Option<(f64, f64)> {
+    let (dx, dy) = ((to.x - from.x) as f64, (to.y - from.y) as f64);
+    let length = (dx * dx + dy * dy).sqrt();
+
+    (length > 0.0).then(|| (dx / length, dy / length))
+}
+
+/// Dot product of two vectors
+fn dot(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(count: i32) -> Vec<Vec<Point>> {
+        (0..count)
+            .map(|i| vec![Point::new(i, 0), Point::new(i, 10)])
+            .collect()
+    }
+
+    #[test]
+    fn darker_lines_come_first() {
+        // Light gray on the left, near black on the right
+        let tone =
+            ImageBuffer::from_fn(20, 10, |x, _| Luma([if x < 10 { 50000u16 } else { 5000 }]));
+        let light = vec![Point::new(2, 2), Point::new(2, 8)];
+        let dark = vec![Point::new(15, 2), Point::new(15, 8)];
+        let mut lines = vec![light.clone(), dark.clone()];
+
+        order_by_brightness(&mut lines, &tone);
+
+        assert_eq!(lines, vec![dark, light]);
+    }
+
+    fn horizontal(xs: std::ops::RangeInclusive<i32>) -> Vec<Point> {
+        xs.map(|x| Point::new(x, 0)).collect()
+    }
+
+    #[test]
+    fn collinear_segments_across_a_small_gap_merge() {
+        let mut reversed = horizontal(13..=23);
+        reversed.reverse();
+
+        let merged = merge_collinear(vec![horizontal(0..=10), reversed], 10.0, 5.0);
+
+        let mut expected = horizontal(0..=10);
+        expected.extend(horizontal(13..=23));
+        assert_eq!(merged, vec![expected]);
+    }
+
+    #[test]
+    fn perpendicular_segments_stay_apart() {
+        let vertical: Vec<Point> = (0..=10).map(|y| Point::new(13, y)).collect();
+        let lines = vec![horizontal(0..=10), vertical];
+
+        assert_eq!(merge_collinear(lines.clone(), 10.0, 5.0), lines);
+    }
+
+    #[test]
+    fn cap_keeps_only_the_longest_lines() {
+        let lines: Vec<Vec<Point>> = [3, 9, 1, 7, 5]
+            .iter()
+            .enumerate()
+            .map(|(i, &length)| vec![Point::new(i as i32, 0), Point::new(i as i32, length)])
+            .collect();
+        let mut capped = lines.clone();
+
+        keep_longest(&mut capped, 2);
+
+        assert_eq!(capped, vec![lines[1].clone(), lines[3].clone()]);
+    }
+
+    #[test]
+    fn cap_above_the_line_count_keeps_everything() {
+        let mut lines = numbered_lines(5);
+        keep_longest(&mut lines, 10);
+
+        assert_eq!(lines, numbered_lines(5));
+    }
+
+    #[test]
+    fn lines_beyond_max_len_are_dropped() {
+        let frame = vec![Point::new(0, 0), Point::new(500, 0)];
+        let stroke = vec![Point::new(0, 10), Point::new(40, 10)];
+        let speck = vec![Point::new(0, 20), Point::new(1, 20)];
+        let mut lines = vec![frame, stroke.clone(), speck];
+
+        filter_by_length(&mut lines, Some(5.0), Some(100.0));
+
+        assert_eq!(lines, vec![stroke]);
+    }
+
+    #[test]
+    fn open_bounds_keep_everything() {
+        let mut lines = numbered_lines(5);
+        filter_by_length(&mut lines, None, None);
+
+        assert_eq!(lines, numbered_lines(5));
+    }
+
+    #[test]
+    fn same_seed_gives_same_shuffle() {
+        let mut first = numbered_lines(50);
+        let mut second = numbered_lines(50);
+        shuffle_seeded(&mut first, 1323);
+        shuffle_seeded(&mut second, 1323);
+
+        assert_eq!(first, second);
+        assert_ne!(first, numbered_lines(50));
+    }
+
+    #[test]
+    fn different_seeds_give_different_shuffles() {
+        let mut first = numbered_lines(50);
+        let mut second = numbered_lines(50);
+        shuffle_seeded(&mut first, 1);
+        shuffle_seeded(&mut second, 2);
+
+        assert_ne!(first, second);
+    }
+}
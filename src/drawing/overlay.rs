@@ -0,0 +1,97 @@
+use minifb::{Window, WindowOptions};
+
+/// Color of the selection outline (`0RGB`)
+const OUTLINE_COLOR: u32 = 0x00ff_3030;
+
+/// Thickness (in pixels) of the selection outline
+const OUTLINE_WIDTH: usize = 2;
+
+/// Borderless, always-on-top window that outlines the region being selected
+///
+/// minifb windows can't be resized programmatically, so the window is created
+/// once at the size of the screen and moved to the selection's top-left
+/// corner, with the outline redrawn into its top-left part whenever the
+/// selection changes size. Whether the rest of the window is see-through
+/// depends on the platform's support for transparent windows.
+pub struct SelectionOverlay {
+    window: Option<Window>,
+    screen: (usize, usize),
+    size: (usize, usize),
+    buffer: Vec<u32>,
+}
+
+impl SelectionOverlay {
+    /// Opens the overlay window for a screen of the given size
+    ///
+    /// If the window can't be opened, updates are silently ignored.
+    pub fn new(screen: (usize, usize)) -> Self {
+        let screen = (screen.0.max(1), screen.1.max(1));
+        let window = Window::new(
+            "drawrs selection",
+            screen.0,
+            screen.1,
+            WindowOptions {
+                borderless: true,
+                title: false,
+                topmost: true,
+                transparency: true,
+                none: true,
+                ..WindowOptions::default()
+            },
+        )
+        .ok();
+
+        SelectionOverlay {
+            window,
+            screen,
+            size: (0, 0),
+            buffer: vec![0; screen.0 * screen.1],
+        }
+    }
+
+    /// Outlines the region between the two given screen positions
+    pub fn update(&mut self, start: (i32, i32), current: (i32, i32)) {
+        let Some(window) = self.window.as_mut() else {
+            return;
+        };
+
+        let left = start.0.min(current.0);
+        let top = start.1.min(current.1);
+        let size = (
+            ((start.0 - current.0).unsigned_abs() as usize + 1).min(self.screen.0),
+            ((start.1 - current.1).unsigned_abs() as usize + 1).min(self.screen.1),
+        );
+
+        if size != self.size {
+            self.size = size;
+            Self::draw_outline(&mut self.buffer, self.screen.0, size);
+        }
+
+        window.set_position(left as isize, top as isize);
+        if window
+            .update_with_buffer(&self.buffer, self.screen.0, self.screen.1)
+            .is_err()
+        {
+            self.window = None;
+        }
+    }
+
+    /// Clears `buffer` (`stride` pixels wide) and outlines a `width` x `height`
+    /// rectangle in its top-left corner
+    fn draw_outline(buffer: &mut [u32], stride: usize, (width, height): (usize, usize)) {
+        buffer.fill(0);
+
+        for y in 0..height {
+            for x in 0..width {
+                let on_edge = x < OUTLINE_WIDTH
+                    || y < OUTLINE_WIDTH
+                    || x + OUTLINE_WIDTH >= width
+                    || y + OUTLINE_WIDTH >= height;
+
+                if on_edge {
+                    buffer[y * stride + x] = OUTLINE_COLOR;
+                }
+            }
+        }
+    }
+}
@@ -1,28 +1,66 @@
 use crate::utils::geometry::Point;
 use std::collections::{HashMap, HashSet};
 
+/// How strongly candidates that turn away from the current direction are penalized
+///
+/// A candidate's squared distance is scaled by up to `1 + DIRECTION_BIAS` as it
+/// turns from straight ahead to straight back, so a diagonal continuation beats
+/// a slightly closer sideways neighbor.
+const DIRECTION_BIAS: f64 = 10.0;
+
+/// Number of separate arms unvisited neighbors must form for a point to count
+/// as a junction
+const JUNCTION_ARMS: usize = 3;
+
+/// Cosine of the sharpest turn a line may take through a junction (45 degrees)
+const JUNCTION_MIN_COS: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/// Finds the closest unvisited point within `max_distance` of `current`
+///
+/// When `previous` is given, candidates are ranked by distance weighted by how
+/// far they turn away from the `previous -> current` direction, so lines prefer
+/// to keep going the way they were heading when distances are comparable.
+///
+/// `grid_size` must be the cell size `spatial_index` was built with; enough
+/// neighboring cells are scanned to cover `max_distance`.
 pub fn find_next_point(
     current: Point,
+    previous: Option<Point>,
     spatial_index: &HashMap<(i32, i32), Vec<Point>>,
+    grid_size: i32,
     visited: &HashSet<Point>,
     max_distance: i32,
 ) -> Option<Point> {
-    let grid_size = max_distance.max(1);
+    let grid_size = grid_size.max(1);
+    let cell_radius = (max_distance + grid_size - 1) / grid_size;
     let current_grid_x = current.x / grid_size;
     let current_grid_y = current.y / grid_size;
 
+    let direction = previous.map(|prev| (current.x - prev.x, current.y - prev.y));
+
     let mut best_point = None;
-    let mut best_distance = i32::MAX;
+    let mut best_cost = f64::MAX;
 
-    for dx in -1..=1 {
-        for dy in -1..=1 {
+    for dx in -cell_radius..=cell_radius {
+        for dy in -cell_radius..=cell_radius {
             let grid_key = (current_grid_x + dx, current_grid_y + dy);
             if let Some(points) = spatial_index.get(&grid_key) {
                 for &point in points {
                     if !visited.contains(&point) {
                         let dist_sq = current.distance_squared(&point);
-                        if dist_sq <= max_distance * max_distance && dist_sq < best_distance {
-                            best_distance = dist_sq;
+                        if dist_sq > max_distance * max_distance {
+                            continue;
+                        }
+
+                        // Ties are broken by position so tracing doesn't depend on the
+                        // (randomized) order points were inserted into the index
+                        let cost = dist_sq as f64 * turn_penalty(direction, current, point);
+                        let tie_winner = cost == best_cost
+                            && best_point
+                                .is_some_and(|best: Point| (point.y, point.x) < (best.y, best.x));
+
+                        if cost < best_cost || tie_winner {
+                            best_cost = cost;
                             best_point = Some(point);
                         }
                     }
@@ -34,21 +72,226 @@ pub fn find_next_point(
     best_point
 }
 
+/// Multiplier in `1..=1 + DIRECTION_BIAS` for moving from `current` to `candidate`
+/// given the current heading
+fn turn_penalty(direction: Option<(i32, i32)>, current: Point, candidate: Point) -> f64 {
+    match direction.and_then(|direction| turn_cosine(direction, current, candidate)) {
+        Some(cos) => 1.0 + DIRECTION_BIAS * (1.0 - cos) / 2.0,
+        None => 1.0,
+    }
+}
+
+/// Cosine of the angle between `direction` and the move from `current` to
+/// `candidate`, or `None` if either has no length
+fn turn_cosine((dx, dy): (i32, i32), current: Point, candidate: Point) -> Option<f64> {
+    let (vx, vy) = (candidate.x - current.x, candidate.y - current.y);
+    let norms = (((dx * dx + dy * dy) * (vx * vx + vy * vy)) as f64).sqrt();
+
+    (norms != 0.0).then(|| (dx * vx + dy * vy) as f64 / norms)
+}
+
+/// Whether `current` is a junction: its unvisited neighbors within
+/// `max_distance` form at least `JUNCTION_ARMS` arms, runs of adjacent compass
+/// directions separated by directions without any
+///
+/// Along a plain stroke the unvisited points form a single arm ahead, however
+/// thick the stroke is, since the points beside and ahead of `current` fill
+/// the directions in between. Only the crossing of two strokes (or a fork)
+/// leaves empty directions between the arms, as long as `max_distance`
+/// reaches further than the strokes are wide.
+fn is_junction(
+    current: Point,
+    spatial_index: &HashMap<(i32, i32), Vec<Point>>,
+    grid_size: i32,
+    visited: &HashSet<Point>,
+    max_distance: i32,
+) -> bool {
+    let grid_size = grid_size.max(1);
+    let cell_radius = (max_distance + grid_size - 1) / grid_size;
+    let mut directions = [false; 8];
+
+    for dx in -cell_radius..=cell_radius {
+        for dy in -cell_radius..=cell_radius {
+            let cell = (current.x / grid_size + dx, current.y / grid_size + dy);
+            for point in spatial_index.get(&cell).into_iter().flatten() {
+                if *point == current
+                    || visited.contains(point)
+                    || current.distance_squared(point) > max_distance * max_distance
+                {
+                    continue;
+                }
+
+                let angle = ((point.y - current.y) as f64).atan2((point.x - current.x) as f64);
+                let octant = ((angle + std::f64::consts::PI) / std::f64::consts::FRAC_PI_4).round();
+                directions[octant as usize % 8] = true;
+            }
+        }
+    }
+
+    let arms = (0..8)
+        .filter(|&octant| directions[octant] && !directions[(octant + 7) % 8])
+        .count();
+    arms >= JUNCTION_ARMS
+}
+
+/// Greedily follows unvisited points from `start` into a single line
+///
+/// Points within `max_distance` are always preferred. When the line reaches a
+/// dead end and a `bridge_radius` is given, the search widens to that radius
+/// once before giving up, so dashed or dithered strokes become a single path.
+///
+/// With `split_junctions`, a line reaching a junction (see [`is_junction`])
+/// only carries on if it can keep going roughly straight and ends otherwise,
+/// so crossing strokes are traced as separate straight lines instead of one
+/// path turning onto the other.
 pub fn trace_line(
     start: Point,
     spatial_index: &HashMap<(i32, i32), Vec<Point>>,
+    grid_size: i32,
     visited: &mut HashSet<Point>,
     max_distance: i32,
+    bridge_radius: Option<i32>,
+    split_junctions: bool,
 ) -> Vec<Point> {
     let mut line = vec![start];
     visited.insert(start);
 
-    while let Some(next) =
-        find_next_point(*line.last().unwrap(), spatial_index, visited, max_distance)
-    {
-        line.push(next);
-        visited.insert(next);
+    loop {
+        let current = *line.last().unwrap();
+        let previous = line.len().checked_sub(2).map(|idx| line[idx]);
+        let search =
+            |radius| find_next_point(current, previous, spatial_index, grid_size, visited, radius);
+
+        let next = search(max_distance).or_else(|| {
+            bridge_radius
+                .filter(|&radius| radius > max_distance)
+                .and_then(search)
+        });
+
+        let heading = previous.map(|prev| (current.x - prev.x, current.y - prev.y));
+        let next = match heading {
+            Some(heading)
+                if split_junctions
+                    && is_junction(current, spatial_index, grid_size, visited, max_distance) =>
+            {
+                next.filter(|&next| {
+                    turn_cosine(heading, current, next).is_some_and(|cos| cos >= JUNCTION_MIN_COS)
+                })
+            }
+            _ => next,
+        };
+
+        match next {
+            Some(next) => {
+                line.push(next);
+                visited.insert(next);
+            }
+            None => break,
+        }
     }
 
     line
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::components::build_spatial_index;
+
+    #[test]
+    fn keeps_heading_over_a_closer_sideways_point() {
+        let points = HashSet::from([Point::new(3, 3), Point::new(3, 2)]);
+        let index = build_spatial_index(&points, 3);
+        let next = find_next_point(
+            Point::new(2, 2),
+            Some(Point::new(1, 1)),
+            &index,
+            3,
+            &HashSet::new(),
+            3,
+        );
+
+        assert_eq!(next, Some(Point::new(3, 3)));
+    }
+
+    #[test]
+    fn diagonal_traces_as_one_line() {
+        let points: HashSet<_> = (0..50).map(|i| Point::new(i, i)).collect();
+        let index = build_spatial_index(&points, 3);
+        let mut visited = HashSet::new();
+
+        let line = trace_line(Point::new(0, 0), &index, 3, &mut visited, 3, None, false);
+
+        assert_eq!(line, (0..50).map(|i| Point::new(i, i)).collect::<Vec<_>>());
+    }
+
+    /// Two horizontal runs with a 5 pixel gap between them
+    fn dashed_line() -> HashSet<Point> {
+        (0..=10).chain(16..=26).map(|x| Point::new(x, 0)).collect()
+    }
+
+    #[test]
+    fn bridge_joins_runs_across_a_gap() {
+        let points = dashed_line();
+        let index = build_spatial_index(&points, 3);
+        let mut visited = HashSet::new();
+
+        let line = trace_line(Point::new(0, 0), &index, 3, &mut visited, 3, Some(6), false);
+
+        assert_eq!(line.len(), points.len());
+    }
+
+    #[test]
+    fn gap_ends_the_line_without_a_bridge() {
+        let points = dashed_line();
+        let index = build_spatial_index(&points, 3);
+        let mut visited = HashSet::new();
+
+        let line = trace_line(Point::new(0, 0), &index, 3, &mut visited, 3, None, false);
+
+        assert_eq!(line.last(), Some(&Point::new(10, 0)));
+    }
+
+    /// Two diagonal strokes crossing at (10, 10)
+    fn cross() -> HashSet<Point> {
+        (0..=20)
+            .flat_map(|i| [Point::new(i, i), Point::new(i, 20 - i)])
+            .collect()
+    }
+
+    #[test]
+    fn crossing_strokes_trace_as_two_straight_lines() {
+        let points = cross();
+        let index = build_spatial_index(&points, 3);
+        let mut visited = HashSet::new();
+
+        let first = trace_line(Point::new(0, 0), &index, 3, &mut visited, 3, None, true);
+        let second = trace_line(Point::new(20, 0), &index, 3, &mut visited, 3, None, true);
+
+        assert_eq!(first.len(), 21);
+        assert!(first.iter().all(|p| p.x == p.y));
+        assert_eq!(second.len(), 20);
+        assert!(second.iter().all(|p| p.x + p.y == 20));
+    }
+
+    #[test]
+    fn crossing_is_a_junction() {
+        let points = cross();
+        let index = build_spatial_index(&points, 3);
+        let visited: HashSet<_> = (0..10).map(|i| Point::new(i, i)).collect();
+
+        assert!(is_junction(Point::new(10, 10), &index, 3, &visited, 3));
+    }
+
+    #[test]
+    fn thick_stroke_is_not_a_junction() {
+        // Three pixels thick, traced up to x = 10
+        let points: HashSet<_> = (0..3)
+            .flat_map(|y| (0..30).map(move |x| Point::new(x, y)))
+            .collect();
+        let index = build_spatial_index(&points, 3);
+        let visited: HashSet<_> = points.iter().copied().filter(|p| p.x < 10).collect();
+
+        assert!(!is_junction(Point::new(10, 1), &index, 3, &visited, 3));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::utils::geometry::Point;
+
+/// Number of cells per side of the calibration grid
+const GRID_CELLS: i32 = 4;
+
+/// Builds a calibration pattern filling a `width` by `height` canvas
+///
+/// The pattern is a border with an evenly spaced grid, both diagonals and an
+/// inscribed circle. Every line is a dense polyline, so a correctly working
+/// setup draws it without gaps; misaligned or missing strokes point at the
+/// target app or the region rather than at image processing.
+pub fn calibration_pattern(width: u32, height: u32) -> Vec<Vec<Point>> {
+    let right = width.max(2) as i32 - 1;
+    let bottom = height.max(2) as i32 - 1;
+    let mut lines = Vec::new();
+
+    for cell in 0..=GRID_CELLS {
+        let x = right * cell / GRID_CELLS;
+        let y = bottom * cell / GRID_CELLS;
+
+        lines.push(straight_line(Point::new(x, 0), Point::new(x, bottom)));
+        lines.push(straight_line(Point::new(0, y), Point::new(right, y)));
+    }
+
+    lines.push(straight_line(Point::new(0, 0), Point::new(right, bottom)));
+    lines.push(straight_line(Point::new(right, 0), Point::new(0, bottom)));
+    lines.push(circle(
+        Point::new(right / 2, bottom / 2),
+        right.min(bottom) as f64 / 2.0,
+    ));
+
+    lines
+}
+
+/// Builds the four edges of a `width` by `height` canvas, `inset` pixels in
+/// from its border, as one stroke each
+///
+/// An inset leaving no room for a frame yields no lines.
+pub fn frame_lines(width: u32, height: u32, inset: u32) -> Vec<Vec<Point>> {
+    let (left, top) = (inset as i32, inset as i32);
+    let right = width as i32 - 1 - inset as i32;
+    let bottom = height as i32 - 1 - inset as i32;
+    if right <= left || bottom <= top {
+        return Vec::new();
+    }
+
+    vec![
+        straight_line(Point::new(left, top), Point::new(right, top)),
+        straight_line(Point::new(right, top), Point::new(right, bottom)),
+        straight_line(Point::new(right, bottom), Point::new(left, bottom)),
+        straight_line(Point::new(left, bottom), Point::new(left, top)),
+    ]
+}
+
+/// Every pixel on the segment from `from` to `to`, both included
+fn straight_line(from: Point, to: Point) -> Vec<Point> {
+    let steps = (to.x - from.x).abs().max((to.y - from.y).abs()).max(1);
+
+    (0..=steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            Point::new(
+                (from.x as f64 + t * (to.x - from.x) as f64).round() as i32,
+                (from.y as f64 + t * (to.y - from.y) as f64).round() as i32,
+            )
+        })
+        .collect()
+}
+
+/// Closed polyline around `center`, with points about a pixel apart
+fn circle(center: Point, radius: f64) -> Vec<Point> {
+    let steps = ((std::f64::consts::TAU * radius).ceil() as i32).max(8);
+
+    (0..=steps)
+        .map(|step| {
+            let angle = step as f64 / steps as f64 * std::f64::consts::TAU;
+            Point::new(
+                (center.x as f64 + radius * angle.cos()).round() as i32,
+                (center.y as f64 + radius * angle.sin()).round() as i32,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_is_four_straight_edges() {
+        let lines = frame_lines(100, 50, 5);
+        let ends: Vec<_> = lines
+            .iter()
+            .map(|line| (line[0], line[line.len() - 1]))
+            .collect();
+
+        assert_eq!(
+            ends,
+            [
+                (Point::new(5, 5), Point::new(94, 5)),
+                (Point::new(94, 5), Point::new(94, 44)),
+                (Point::new(94, 44), Point::new(5, 44)),
+                (Point::new(5, 44), Point::new(5, 5)),
+            ]
+        );
+        for line in &lines {
+            let (first, last) = (line[0], line[line.len() - 1]);
+            assert!(line.iter().all(|p| p.x == first.x) || line.iter().all(|p| p.y == first.y));
+            assert_eq!(
+                line.len() as i32,
+                (last.x - first.x).abs().max((last.y - first.y).abs()) + 1
+            );
+        }
+    }
+
+    #[test]
+    fn inset_past_the_center_leaves_no_frame() {
+        assert!(frame_lines(100, 50, 25).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::utils::status::{self, StatusEvent};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Template of the default drawing progress bar
+pub const DEFAULT_TEMPLATE: &str = "{wide_bar} line {pos}/{len} ({eta}) {msg}";
+
+/// Filled, current and empty characters of the default progress bar
+pub const DEFAULT_CHARS: &str = "=>-";
+
+/// Share of the lines between two textual progress reports
+const REPORT_FRACTION: f64 = 0.1;
+
+/// How drawing progress is shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProgressConfig {
+    /// Show an indicatif progress bar
+    Bar {
+        /// Indicatif template string, see [`ProgressStyle::template`]
+        template: String,
+
+        /// Characters used for the filled, current and empty parts of the bar
+        chars: String,
+    },
+
+    /// Print a plain line every tenth of the drawing instead of a bar, for logs
+    /// and terminals that garble redrawn output (or a `progress` event per line
+    /// in JSON mode)
+    Disabled,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        ProgressConfig::Bar {
+            template: DEFAULT_TEMPLATE.to_string(),
+            chars: DEFAULT_CHARS.to_string(),
+        }
+    }
+}
+
+/// Progress of a drawing, shown as configured by a [`ProgressConfig`]
+///
+/// A drawing made of several parts, like the tiles of a mural, is tracked as a
+/// whole, see [`for_parts`](Self::for_parts).
+pub struct DrawProgress {
+    bar: Option<ProgressBar>,
+    total: u64,
+    position: u64,
+    last_report: u64,
+
+    /// Number of parts the drawing is made of
+    parts: u64,
+
+    /// Number of parts started so far
+    started_parts: u64,
+
+    /// Lines of the parts started so far
+    started_lines: u64,
+
+    /// Lines of the parts before the current one
+    part_start: u64,
+}
+
+impl DrawProgress {
+    /// Starts showing progress for a drawing of `total` lines
+    ///
+    /// An invalid bar template is reported and replaced by the default one.
+    pub fn new(config: &ProgressConfig, total: u64) -> Self {
+        let bar = match config {
+            ProgressConfig::Bar { template, chars } => {
+                let style = ProgressStyle::default_bar()
+                    .template(template)
+                    .unwrap_or_else(|e| {
+                        warn!("Invalid progress template '{}': {}", template, e);
+                        ProgressStyle::default_bar()
+                            .template(DEFAULT_TEMPLATE)
+                            .expect("Invalid progress style template")
+                    })
+                    .progress_chars(if chars.chars().count() >= 2 {
+                        chars.as_str()
+                    } else {
+                        DEFAULT_CHARS
+                    });
+
+                let bar = ProgressBar::new(total);
+                bar.set_style(style);
+                Some(bar)
+            }
+            ProgressConfig::Disabled => None,
+        };
+
+        DrawProgress {
+            bar,
+            total,
+            position: 0,
+            last_report: 0,
+            parts: 1,
+            started_parts: 1,
+            started_lines: total,
+            part_start: 0,
+        }
+    }
+
+    /// Starts showing progress for a drawing made of `parts` parts, whose lines
+    /// are only known as each one is [started](Self::start_part)
+    pub fn for_parts(config: &ProgressConfig, parts: u64) -> Self {
+        DrawProgress {
+            parts: parts.max(1),
+            started_parts: 0,
+            ..Self::new(config, 0)
+        }
+    }
+
+    /// Starts counting the next part of the drawing, which has `lines` lines
+    ///
+    /// Lines are counted on from the parts before it. The parts still to come
+    /// are expected to have as many lines as the started ones on average, so
+    /// the total and the ETA cover the whole drawing.
+    pub fn start_part(&mut self, lines: u64) {
+        self.part_start = self.started_lines;
+        self.started_parts += 1;
+        self.started_lines += lines;
+
+        let remaining = self.parts.saturating_sub(self.started_parts);
+        let per_part = self.started_lines as f64 / self.started_parts as f64;
+        self.total = self.started_lines + (per_part * remaining as f64).round() as u64;
+        if let Some(bar) = &self.bar {
+            bar.set_length(self.total);
+        }
+        self.set_position(0);
+    }
+
+    /// Jumps to `position` lines into the current part, e.g. when resuming a
+    /// drawing
+    pub fn set_position(&mut self, position: u64) {
+        self.position = self.part_start + position;
+        self.last_report = self.position;
+
+        if let Some(bar) = &self.bar {
+            bar.set_position(self.position);
+        }
+    }
+
+    /// Marks one more line as drawn
+    pub fn inc(&mut self) {
+        self.position += 1;
+
+        match &self.bar {
+            Some(bar) => bar.inc(1),
+            None if status::is_json() => status::emit(StatusEvent::Progress {
+                line: self.position,
+                total: self.total,
+            }),
+            None => {
+                let interval = ((self.total as f64 * REPORT_FRACTION).ceil() as u64).max(1);
+                if self.position - self.last_report >= interval || self.position == self.total {
+                    println!("Drew line {}/{}", self.position, self.total);
+                    self.last_report = self.position;
+                }
+            }
+        }
+    }
+
+    /// Shows a short status next to the bar (`{msg}` in the template), or
+    /// prints it without a bar
+    pub fn set_message(&self, message: impl Into<String>) {
+        match &self.bar {
+            Some(bar) => bar.set_message(message.into()),
+            None => status::say(message.into()),
+        }
+    }
+
+    /// Prints a message without garbling the bar
+    pub fn println(&self, message: impl AsRef<str>) {
+        match &self.bar {
+            Some(bar) => bar.println(message),
+            None => status::say(message.as_ref()),
+        }
+    }
+
+    /// Stops showing progress with a final message, or only prints it while
+    /// more parts of the drawing are to come
+    pub fn finish_with_message(&self, message: impl Into<String>) {
+        match &self.bar {
+            Some(bar) if self.started_parts >= self.parts => {
+                bar.finish_with_message(message.into())
+            }
+            Some(bar) => bar.println(message.into()),
+            None => status::say(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_progress_has_no_bar() {
+        let mut progress = DrawProgress::new(&ProgressConfig::Disabled, 10);
+        progress.inc();
+
+        assert!(progress.bar.is_none());
+        assert_eq!(progress.position, 1);
+    }
+
+    #[test]
+    fn bar_progress_shows_a_bar() {
+        let progress = DrawProgress::new(&ProgressConfig::default(), 10);
+
+        assert!(progress.bar.is_some());
+    }
+
+    #[test]
+    fn parts_count_on_from_each_other() {
+        let mut progress = DrawProgress::for_parts(&ProgressConfig::Disabled, 3);
+
+        progress.start_part(10);
+        assert_eq!(progress.total, 30);
+        for _ in 0..10 {
+            progress.inc();
+        }
+
+        progress.start_part(20);
+        assert_eq!(progress.position, 10);
+        assert_eq!(progress.total, 45);
+        progress.set_position(5);
+        assert_eq!(progress.position, 15);
+
+        progress.start_part(6);
+        assert_eq!(progress.total, 36);
+    }
+
+    #[test]
+    fn invalid_template_falls_back_to_the_default() {
+        let config = ProgressConfig::Bar {
+            template: "{unclosed".to_string(),
+            chars: "#".to_string(),
+        };
+        let progress = DrawProgress::new(&config, 10);
+
+        assert!(progress.bar.is_some());
+    }
+}
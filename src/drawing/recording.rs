@@ -0,0 +1,182 @@
+use {
+    crate::drawing::input::InputBackend,
+    enigo::{Button, Direction, Key},
+    std::{
+        error::Error,
+        fmt,
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+/// A single simulated input
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Move { x: i32, y: i32 },
+    ButtonPress(Button),
+    ButtonRelease(Button),
+    Key(Key, Direction),
+}
+
+/// An input event along with when it happened and the pen state after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// Time since the recording started
+    pub elapsed: Duration,
+    pub event: InputEvent,
+    pub pen_down: bool,
+}
+
+impl fmt::Display for RecordedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>12.3}ms ", self.elapsed.as_secs_f64() * 1000.0)?;
+
+        match &self.event {
+            InputEvent::Move { x, y } => write!(f, "move {} {}", x, y)?,
+            InputEvent::ButtonPress(button) => write!(f, "press {:?}", button)?,
+            InputEvent::ButtonRelease(button) => write!(f, "release {:?}", button)?,
+            InputEvent::Key(key, direction) => write!(f, "key {:?} {:?}", key, direction)?,
+        }
+
+        write!(f, " pen={}", if self.pen_down { "down" } else { "up" })
+    }
+}
+
+/// Backend that forwards input to another backend while recording every event
+///
+/// Events are kept in memory, or, when created with [`to_file`](Self::to_file),
+/// written to a log file one line per event as they happen instead, so long
+/// drawings don't pile up every event until they finish.
+pub struct RecordingBackend<B: InputBackend> {
+    inner: B,
+    started: Instant,
+    pen_down: bool,
+    events: Vec<RecordedEvent>,
+    log: Option<BufWriter<File>>,
+}
+
+impl<B: InputBackend> RecordingBackend<B> {
+    pub fn new(inner: B) -> Self {
+        RecordingBackend {
+            inner,
+            started: Instant::now(),
+            pen_down: false,
+            events: Vec::new(),
+            log: None,
+        }
+    }
+
+    /// Records into a newly created log file at `path`
+    pub fn to_file(inner: B, path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let mut backend = Self::new(inner);
+        backend.log = Some(BufWriter::new(File::create(path)?));
+
+        Ok(backend)
+    }
+
+    /// Every event recorded so far, oldest first (always empty when recording
+    /// to a file)
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    fn record(&mut self, event: InputEvent) -> Result<(), Box<dyn Error>> {
+        match &event {
+            InputEvent::ButtonPress(_) | InputEvent::Key(_, Direction::Press) => {
+                self.pen_down = true
+            }
+            InputEvent::ButtonRelease(_) | InputEvent::Key(_, Direction::Release) => {
+                self.pen_down = false
+            }
+            _ => {}
+        }
+
+        let recorded = RecordedEvent {
+            elapsed: self.started.elapsed(),
+            event,
+            pen_down: self.pen_down,
+        };
+
+        match self.log.as_mut() {
+            Some(log) => {
+                writeln!(log, "{}", recorded)?;
+                if !recorded.pen_down {
+                    log.flush()?;
+                }
+            }
+            None => self.events.push(recorded),
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: InputBackend> InputBackend for RecordingBackend<B> {
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Box<dyn Error>> {
+        self.inner.move_to(x, y)?;
+        self.record(InputEvent::Move { x, y })
+    }
+
+    fn button_press(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        self.inner.button_press(button)?;
+        self.record(InputEvent::ButtonPress(button))
+    }
+
+    fn button_release(&mut self, button: Button) -> Result<(), Box<dyn Error>> {
+        self.inner.button_release(button)?;
+        self.record(InputEvent::ButtonRelease(button))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), Box<dyn Error>> {
+        self.inner.key(key, direction)?;
+        self.record(InputEvent::Key(key, direction))
+    }
+
+    fn display_size(&self) -> Result<(i32, i32), Box<dyn Error>> {
+        self.inner.display_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::input::MockBackend;
+
+    #[test]
+    fn records_events_with_the_pen_state() {
+        let mut backend = RecordingBackend::new(MockBackend::new());
+        backend.move_to(1, 2).unwrap();
+        backend.button_press(Button::Left).unwrap();
+        backend.button_release(Button::Left).unwrap();
+
+        let recorded: Vec<_> = backend
+            .events()
+            .iter()
+            .map(|recorded| (recorded.event.clone(), recorded.pen_down))
+            .collect();
+        assert_eq!(
+            recorded,
+            vec![
+                (InputEvent::Move { x: 1, y: 2 }, false),
+                (InputEvent::ButtonPress(Button::Left), true),
+                (InputEvent::ButtonRelease(Button::Left), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_recording_keeps_nothing_in_memory() {
+        let path = std::env::temp_dir().join(format!("drawrs-record-{}.log", std::process::id()));
+        let mut backend = RecordingBackend::to_file(MockBackend::new(), &path).unwrap();
+        backend.move_to(3, 4).unwrap();
+        backend.button_release(Button::Left).unwrap();
+
+        assert!(backend.events().is_empty());
+        let log = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(log.lines().count(), 2);
+        assert!(log.contains("move 3 4 pen=up"));
+    }
+}
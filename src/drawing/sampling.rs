@@ -0,0 +1,257 @@
+use crate::utils::geometry::Point;
+use image::{ImageBuffer, Luma, Primitive};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::HashSet;
+
+/// Side (in pixels) of the square blocks that each get their own sampling step
+const BLOCK_SIZE: u32 = 16;
+
+/// Share of a block's pixels lying on an edge at which it gets the finest step
+const DETAIL_SATURATION: f64 = 0.2;
+
+/// Fallback seed for Poisson-disk sampling of drawings without a [seed]
+///
+/// A checkpoint records how many lines were drawn, so the samples (and the
+/// lines traced from them) must not change between two runs of a drawing.
+///
+/// [seed]: crate::drawing::config::DrawConfig::seed
+const POISSON_SEED: u64 = 0xB10E_5EED;
+
+/// Candidates tried around each active sample before it is retired
+const POISSON_ATTEMPTS: u32 = 30;
+
+/// Samples black pixels with a step that adapts to the local amount of detail
+///
+/// The image is split into `BLOCK_SIZE` blocks and each block's edge density is
+/// measured as the share of its pixels that differ from their right or bottom
+/// neighbor, which is the gradient of a binary image. Flat blocks (solid fills
+/// or empty space) are sampled every `max_step` pixels, and the step shrinks
+/// linearly towards `min_step` as the density approaches `DETAIL_SATURATION`.
+///
+/// Samples are aligned to multiples of their step in image coordinates, so
+/// neighboring blocks using the same step line up.
+pub fn sample_by_detail<T: Primitive + Send + Sync>(
+    img: &ImageBuffer<Luma<T>, Vec<T>>,
+    min_step: i32,
+    max_step: i32,
+) -> HashSet<Point> {
+    let min_step = min_step.max(1) as u32;
+    let max_step = (max_step.max(1) as u32).max(min_step);
+    let blocks_x = img.width().div_ceil(BLOCK_SIZE);
+    let blocks_y = img.height().div_ceil(BLOCK_SIZE);
+
+    (0..blocks_y)
+        .into_par_iter()
+        .fold(HashSet::new, |mut points, block_y| {
+            for block_x in 0..blocks_x {
+                let x0 = block_x * BLOCK_SIZE;
+                let y0 = block_y * BLOCK_SIZE;
+                let x1 = (x0 + BLOCK_SIZE).min(img.width());
+                let y1 = (y0 + BLOCK_SIZE).min(img.height());
+
+                let detail = (edge_density(img, x0, y0, x1, y1) / DETAIL_SATURATION).min(1.0);
+                let step = max_step - ((max_step - min_step) as f64 * detail).round() as u32;
+
+                for y in (y0.next_multiple_of(step)..y1).step_by(step as usize) {
+                    for x in (x0.next_multiple_of(step)..x1).step_by(step as usize) {
+                        if img.get_pixel(x, y)[0] == T::DEFAULT_MIN_VALUE {
+                            points.insert(Point::new(x as i32, y as i32));
+                        }
+                    }
+                }
+            }
+
+            points
+        })
+        .reduce(HashSet::new, |mut merged, mut points| {
+            if merged.len() < points.len() {
+                std::mem::swap(&mut merged, &mut points);
+            }
+
+            merged.extend(points);
+            merged
+        })
+}
+
+/// Share of the pixels in `[x0, x1) x [y0, y1)` that differ from their right or
+/// bottom neighbor
+fn edge_density<T: Primitive>(
+    img: &ImageBuffer<Luma<T>, Vec<T>>,
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+) -> f64 {
+    let area = (x1 - x0) * (y1 - y0);
+    if area == 0 {
+        return 0.0;
+    }
+
+    let mut edges = 0u32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let value = img.get_pixel(x, y)[0];
+            let right_differs = x + 1 < img.width() && img.get_pixel(x + 1, y)[0] != value;
+            let below_differs = y + 1 < img.height() && img.get_pixel(x, y + 1)[0] != value;
+
+            if right_differs || below_differs {
+                edges += 1;
+            }
+        }
+    }
+
+    edges as f64 / area as f64
+}
+
+/// Samples black pixels with blue-noise spacing using Bridson's algorithm
+///
+/// No two returned points are closer than `min_distance` pixels, and every
+/// black pixel lies within about twice that of a point, avoiding the aliasing
+/// of a regular grid. Disconnected black areas are each seeded from their first
+/// pixel in scan order, so every area gets covered. Candidates are drawn from
+/// `seed`, or [`POISSON_SEED`] without one.
+pub fn sample_poisson_disk<T: Primitive>(
+    img: &ImageBuffer<Luma<T>, Vec<T>>,
+    min_distance: i32,
+    seed: Option<u64>,
+) -> HashSet<Point> {
+    let (width, height) = img.dimensions();
+    let min_distance = min_distance.max(1);
+    let min_distance_sq = min_distance * min_distance;
+
+    // Cells are small enough that each holds at most one sample, so only the
+    // two surrounding rings need checking
+    let cell = (min_distance as f64 / std::f64::consts::SQRT_2).max(1.0);
+    let cols = (width as f64 / cell).ceil() as usize + 1;
+    let rows = (height as f64 / cell).ceil() as usize + 1;
+    let mut grid: Vec<Option<Point>> = vec![None; cols * rows];
+    let cell_of = |point: Point| {
+        (
+            (point.x as f64 / cell) as usize,
+            (point.y as f64 / cell) as usize,
+        )
+    };
+
+    let is_black = |point: Point| {
+        point.x >= 0
+            && point.y >= 0
+            && (point.x as u32) < width
+            && (point.y as u32) < height
+            && img.get_pixel(point.x as u32, point.y as u32)[0] == T::DEFAULT_MIN_VALUE
+    };
+
+    let fits = |grid: &[Option<Point>], point: Point| {
+        let (cx, cy) = cell_of(point);
+        !(cy.saturating_sub(2)..(cy + 3).min(rows)).any(|y| {
+            (cx.saturating_sub(2)..(cx + 3).min(cols)).any(|x| {
+                grid[y * cols + x]
+                    .is_some_and(|other| other.distance_squared(&point) < min_distance_sq)
+            })
+        })
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed.unwrap_or(POISSON_SEED));
+    let mut points = HashSet::new();
+    let mut active = Vec::new();
+
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let seed = Point::new(x, y);
+            if !is_black(seed) || !fits(&grid, seed) {
+                continue;
+            }
+
+            let (cx, cy) = cell_of(seed);
+            grid[cy * cols + cx] = Some(seed);
+            points.insert(seed);
+            active.push(seed);
+
+            while !active.is_empty() {
+                let index = rng.random_range(0..active.len());
+                let origin = active[index];
+
+                let found = (0..POISSON_ATTEMPTS).find_map(|_| {
+                    let angle = rng.random_range(0.0..std::f64::consts::TAU);
+                    let distance = rng.random_range(min_distance as f64..2.0 * min_distance as f64);
+                    let candidate = Point::new(
+                        (origin.x as f64 + angle.cos() * distance).round() as i32,
+                        (origin.y as f64 + angle.sin() * distance).round() as i32,
+                    );
+
+                    (is_black(candidate) && fits(&grid, candidate)).then_some(candidate)
+                });
+
+                match found {
+                    Some(candidate) => {
+                        let (cx, cy) = cell_of(candidate);
+                        grid[cy * cols + cx] = Some(candidate);
+                        points.insert(candidate);
+                        active.push(candidate);
+                    }
+                    None => {
+                        active.swap_remove(index);
+                    }
+                }
+            }
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GrayImage;
+
+    #[test]
+    fn flat_areas_get_fewer_samples_than_detailed_ones() {
+        // Solid black on the left, one pixel wide stripes on the right
+        let img = GrayImage::from_fn(64, 32, |x, _| {
+            Luma([if x < 32 || x % 2 == 0 { 0 } else { 255 }])
+        });
+        let points = sample_by_detail(&img, 1, 4);
+
+        let flat = points.iter().filter(|point| point.x < 32).count();
+        let detailed = points.len() - flat;
+        assert_eq!(flat, 8 * 8);
+        assert!(detailed > flat * 4, "{detailed} detailed vs {flat} flat");
+    }
+
+    #[test]
+    fn poisson_samples_keep_their_distance() {
+        let img = GrayImage::from_fn(60, 60, |x, y| {
+            Luma([if (x as i32 - 30).pow(2) + (y as i32 - 30).pow(2) < 600 {
+                0
+            } else {
+                255
+            }])
+        });
+
+        for seed in [None, Some(7)] {
+            let points: Vec<_> = sample_poisson_disk(&img, 4, seed).into_iter().collect();
+            assert!(points.len() > 20);
+
+            for (index, a) in points.iter().enumerate() {
+                for b in &points[index + 1..] {
+                    assert!(a.distance_squared(b) >= 16, "{a:?} and {b:?} are too close");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn poisson_samples_follow_the_seed() {
+        let img = GrayImage::from_pixel(40, 40, Luma([0]));
+
+        assert_eq!(
+            sample_poisson_disk(&img, 3, None),
+            sample_poisson_disk(&img, 3, Some(POISSON_SEED))
+        );
+        assert_ne!(
+            sample_poisson_disk(&img, 3, Some(1)),
+            sample_poisson_disk(&img, 3, Some(2))
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use crate::utils::geometry::Point;
+
+/// Removes the points of a line that lie within `epsilon` pixels of the
+/// simplified line, by the Douglas–Peucker algorithm
+///
+/// The endpoints are always kept, so closed lines stay closed. An `epsilon` of
+/// zero or less leaves the line unchanged.
+pub fn simplify_douglas_peucker(line: &[Point], epsilon: f64) -> Vec<Point> {
+    if epsilon <= 0.0 || line.len() < 3 {
+        return line.to_vec();
+    }
+
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+
+    let mut spans = vec![(0, line.len() - 1)];
+    while let Some((start, end)) = spans.pop() {
+        let farthest = (start + 1..end)
+            .map(|index| (index, segment_distance(line[index], line[start], line[end])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((index, distance)) = farthest {
+            if distance > epsilon {
+                keep[index] = true;
+                spans.push((start, index));
+                spans.push((index, end));
+            }
+        }
+    }
+
+    line.iter()
+        .zip(keep)
+        .filter_map(|(&point, keep)| keep.then_some(point))
+        .collect()
+}
+
+/// Distance from `point` to the segment from `a` to `b`
+fn segment_distance(point: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let (px, py) = ((point.x - a.x) as f64, (point.y - a.y) as f64);
+
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+    };
+
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
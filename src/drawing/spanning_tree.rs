@@ -0,0 +1,206 @@
+use crate::utils::geometry::Point;
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet},
+};
+
+/// Traces points into strokes following a minimum spanning tree
+///
+/// Points within `max_distance` of each other (or `bridge_radius`, if larger)
+/// are joined by a minimum spanning tree per connected group, which never
+/// doubles back and prefers the shortest connections. Each tree is then walked
+/// depth-first from one end of its longest branch: the walk always continues
+/// into the deepest subtree, so the first stroke runs the full length of the
+/// group, and every side branch becomes its own stroke starting at the point
+/// it branches off from. Unlike greedy tracing, strokes never jump backwards
+/// over already drawn points.
+///
+/// Groups of two points or fewer are dropped, but every stroke of a larger
+/// group is kept, even a side branch of a single point, so each point of the
+/// group is drawn. Groups come out top to bottom by their top-most, left-most
+/// point.
+///
+/// `grid_size` must be the cell size `spatial_index` was built with.
+pub fn trace_spanning_tree(
+    points: HashSet<Point>,
+    max_distance: i32,
+    spatial_index: &HashMap<(i32, i32), Vec<Point>>,
+    grid_size: i32,
+    bridge_radius: Option<i32>,
+) -> Vec<Vec<Point>> {
+    let grid_size = grid_size.max(1);
+    let range = bridge_radius.unwrap_or(0).max(max_distance).max(1);
+
+    let mut sorted: Vec<Point> = points.into_iter().collect();
+    sorted.sort_by_key(|p| (p.y, p.x));
+    let index_of: HashMap<Point, usize> = sorted
+        .iter()
+        .enumerate()
+        .map(|(index, &point)| (point, index))
+        .collect();
+
+    let mut in_tree = vec![false; sorted.len()];
+    let mut lines = Vec::new();
+
+    for root in 0..sorted.len() {
+        if in_tree[root] {
+            continue;
+        }
+
+        let adjacency = grow_tree(
+            root,
+            &sorted,
+            &index_of,
+            spatial_index,
+            grid_size,
+            range,
+            &mut in_tree,
+        );
+        if adjacency.len() <= 2 {
+            continue;
+        }
+
+        let start = farthest_node(root, &adjacency);
+        lines.extend(
+            walk_tree(start, &adjacency)
+                .into_iter()
+                .map(|line| line.into_iter().map(|index| sorted[index]).collect()),
+        );
+    }
+
+    lines
+}
+
+/// Grows a minimum spanning tree from `root` with Prim's algorithm, returning
+/// the tree's edges as an adjacency list
+///
+/// Ties between equally long edges are broken by point index so the tree does
+/// not depend on hash map iteration order.
+fn grow_tree(
+    root: usize,
+    points: &[Point],
+    index_of: &HashMap<Point, usize>,
+    spatial_index: &HashMap<(i32, i32), Vec<Point>>,
+    grid_size: i32,
+    range: i32,
+    in_tree: &mut [bool],
+) -> HashMap<usize, Vec<usize>> {
+    let cell_radius = (range + grid_size - 1) / grid_size;
+    let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    let add = |node: usize, in_tree: &mut [bool], frontier: &mut BinaryHeap<_>| {
+        in_tree[node] = true;
+        let point = points[node];
+
+        for dx in -cell_radius..=cell_radius {
+            for dy in -cell_radius..=cell_radius {
+                let cell = (point.x / grid_size + dx, point.y / grid_size + dy);
+                for neighbor in spatial_index.get(&cell).into_iter().flatten() {
+                    let neighbor_index = index_of[neighbor];
+                    let distance = point.distance_squared(neighbor);
+
+                    if !in_tree[neighbor_index] && distance <= range * range {
+                        frontier.push(Reverse((distance, neighbor_index, node)));
+                    }
+                }
+            }
+        }
+    };
+
+    adjacency.insert(root, Vec::new());
+    add(root, in_tree, &mut frontier);
+
+    while let Some(Reverse((_, node, parent))) = frontier.pop() {
+        if in_tree[node] {
+            continue;
+        }
+
+        adjacency.entry(parent).or_default().push(node);
+        adjacency.entry(node).or_default().push(parent);
+        add(node, in_tree, &mut frontier);
+    }
+
+    adjacency
+}
+
+/// Node of the tree the most edges away from `from`, found by a breadth-first
+/// search
+fn farthest_node(from: usize, adjacency: &HashMap<usize, Vec<usize>>) -> usize {
+    let mut seen = HashSet::from([from]);
+    let mut queue = vec![from];
+    let mut last = from;
+
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for node in queue {
+            last = node;
+            for &neighbor in &adjacency[&node] {
+                if seen.insert(neighbor) {
+                    next.push(neighbor);
+                }
+            }
+        }
+        queue = next;
+    }
+
+    last
+}
+
+/// Splits the tree rooted at `root` into strokes, continuing each stroke into
+/// the deepest subtree and starting a new stroke for every other branch
+fn walk_tree(root: usize, adjacency: &HashMap<usize, Vec<usize>>) -> Vec<Vec<usize>> {
+    // Order nodes parent-first, then fill in subtree depths children-first
+    let mut parent_of = HashMap::from([(root, root)]);
+    let mut order = vec![root];
+    let mut cursor = 0;
+    while cursor < order.len() {
+        let node = order[cursor];
+        for &neighbor in &adjacency[&node] {
+            if let Entry::Vacant(entry) = parent_of.entry(neighbor) {
+                entry.insert(node);
+                order.push(neighbor);
+            }
+        }
+        cursor += 1;
+    }
+
+    let mut depth: HashMap<usize, usize> = HashMap::new();
+    for &node in order.iter().rev() {
+        let deepest_child = adjacency[&node]
+            .iter()
+            .filter(|&&child| parent_of[&child] == node && child != root)
+            .map(|child| depth[child] + 1)
+            .max()
+            .unwrap_or(0);
+        depth.insert(node, deepest_child);
+    }
+
+    let mut strokes = Vec::new();
+    let mut pending = vec![vec![root]];
+
+    while let Some(mut stroke) = pending.pop() {
+        loop {
+            let node = *stroke.last().unwrap();
+            let mut children: Vec<usize> = adjacency[&node]
+                .iter()
+                .copied()
+                .filter(|&child| parent_of[&child] == node && child != root)
+                .collect();
+
+            if children.is_empty() {
+                break;
+            }
+
+            children.sort_by_key(|child| (Reverse(depth[child]), *child));
+            for &branch in &children[1..] {
+                pending.push(vec![node, branch]);
+            }
+            stroke.push(children[0]);
+        }
+
+        strokes.push(stroke);
+    }
+
+    strokes
+}
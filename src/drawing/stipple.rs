@@ -0,0 +1,92 @@
+use crate::utils::geometry::Point;
+use image::{ImageBuffer, Luma};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Seed dots are placed with when the drawing has no [seed] of its own
+///
+/// Resuming a drawing skips the lines already drawn, which only lines up if the
+/// dots come out the same as in the interrupted run.
+///
+/// [seed]: crate::drawing::config::DrawConfig::seed
+const STIPPLE_SEED: u64 = 0x5717_91E5;
+
+/// Height (in pixels) of the bands dots are ordered in
+const BAND_HEIGHT: i32 = 16;
+
+/// Places dots over `img` with a density proportional to its darkness
+///
+/// Candidates are scattered uniformly at `density` per pixel and each one is
+/// kept with a probability equal to the darkness under it (rejection sampling),
+/// so black areas get `density` dots per pixel, mid-grays half of that and
+/// white areas none.
+///
+/// The dots only depend on `seed` (or [`STIPPLE_SEED`] without one). They are
+/// returned band by band, alternating direction in each band, so drawing them
+/// in order doesn't jump back and forth across the image.
+pub fn generate_stipple(
+    img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    density: f64,
+    seed: Option<u64>,
+) -> Vec<Point> {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed.unwrap_or(STIPPLE_SEED));
+    let candidates = (width as f64 * height as f64 * density.max(0.0)).round() as usize;
+
+    let mut dots: Vec<Point> = (0..candidates)
+        .filter_map(|_| {
+            let x = rng.random_range(0..width);
+            let y = rng.random_range(0..height);
+            let darkness = 1.0 - img.get_pixel(x, y)[0] as f64 / u16::MAX as f64;
+
+            (rng.random::<f64>() < darkness).then(|| Point::new(x as i32, y as i32))
+        })
+        .collect();
+
+    dots.sort_by_key(|dot| {
+        let band = dot.y / BAND_HEIGHT;
+        (band, if band % 2 == 0 { dot.x } else { -dot.x }, dot.y)
+    });
+    dots.dedup();
+
+    dots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Black left half, light gray (25% dark) right half
+    fn dark_and_light() -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(100, 100, |x, _| Luma([if x < 50 { 0 } else { 49151 }]))
+    }
+
+    #[test]
+    fn darker_areas_get_more_dots() {
+        let dots = generate_stipple(&dark_and_light(), 0.2, None);
+
+        let dark = dots.iter().filter(|dot| dot.x < 50).count();
+        let light = dots.len() - dark;
+        assert!(
+            light > 0 && dark > light * 2,
+            "{dark} dark vs {light} light"
+        );
+    }
+
+    #[test]
+    fn dots_follow_the_seed() {
+        let img = dark_and_light();
+
+        assert_eq!(
+            generate_stipple(&img, 0.1, None),
+            generate_stipple(&img, 0.1, Some(STIPPLE_SEED))
+        );
+        assert_ne!(
+            generate_stipple(&img, 0.1, Some(1)),
+            generate_stipple(&img, 0.1, Some(2))
+        );
+    }
+}
@@ -0,0 +1,498 @@
+use crate::{
+    choices::StrokeStart,
+    drawing::{
+        config::{DrawConfig, PenAction},
+        error::DrawError,
+        input::InputBackend,
+        interpolation::{interpolate_positions, interpolation_steps, MoveFilter},
+        jitter::Jitter,
+        opacity::KEY_DELAY,
+    },
+    utils::geometry::Point,
+};
+use enigo::{Direction, Key};
+use log::warn;
+use std::{thread, time::Duration};
+
+/// Pause before retrying a simulated input that failed
+const INPUT_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Draws a single line of a drawing whose top-left corner is at
+/// `start_pos`, coming from `from` with the pen up
+///
+/// `delays` are the point and line delays, which may differ from the
+/// configured ones after the speed was adjusted live.
+///
+/// # Errors
+/// Fails if simulating input still fails after a retry, see [`try_input`].
+pub fn draw_stroke<B: InputBackend>(
+    input: &mut B,
+    line: &[Point],
+    start_pos: (i32, i32),
+    from: Option<(i32, i32)>,
+    jitter: &mut Jitter,
+    (point_delay, line_delay): (Duration, Duration),
+    config: &DrawConfig,
+) -> Result<(), DrawError> {
+    let (abs_start_x, abs_start_y) =
+        config.to_screen(start_pos, line[0].x as f64, line[0].y as f64);
+
+    if let (Some(threshold), Some(from)) = (config.travel_threshold, from) {
+        let gap =
+            Point::new(from.0, from.1).distance_squared(&Point::new(abs_start_x, abs_start_y));
+
+        if gap as i64 > threshold as i64 * threshold as i64 {
+            for (x, y) in
+                interpolate_positions(from, (abs_start_x, abs_start_y), config.travel_steps)
+            {
+                move_mouse(input, x, y)?;
+                thread::sleep(point_delay);
+            }
+        }
+    }
+
+    move_mouse(input, abs_start_x, abs_start_y)?;
+    thread::sleep(line_delay);
+    thread::sleep(config.settle_delay);
+
+    start_stroke(input, (abs_start_x, abs_start_y), point_delay, config)?;
+    let mut move_filter = MoveFilter::new(config.min_move);
+    move_filter.reset((abs_start_x, abs_start_y));
+
+    for (index, points_chunk) in line.windows(2).enumerate() {
+        let current = points_chunk[0];
+        let next = points_chunk[1];
+
+        // `current` is point `index + 1` of the line, counting from one
+        let drawn = index + 1;
+        if config.lift_every.is_some_and(|every| drawn % every == 0) {
+            lift_and_press(input, &mut move_filter, line_delay, config)?;
+        }
+
+        let distance = current.distance_squared(&next);
+        if distance > 1 {
+            let steps = interpolation_steps(distance, config.interpolation_density);
+            for step in 1..=steps {
+                let t = step as f64 / steps as f64;
+                let interp_x = current.x as f64 + t * (next.x - current.x) as f64;
+                let interp_y = current.y as f64 + t * (next.y - current.y) as f64;
+                let (interp_x, interp_y) = jitter.apply(interp_x, interp_y);
+
+                let pos = config.to_screen(start_pos, interp_x, interp_y);
+                pen_move(input, pos, &mut move_filter, point_delay)?;
+            }
+        } else {
+            let (next_x, next_y) = jitter.apply(next.x as f64, next.y as f64);
+            let pos = config.to_screen(start_pos, next_x, next_y);
+            pen_move(input, pos, &mut move_filter, point_delay)?;
+        }
+    }
+
+    if let Some((x, y)) = move_filter.flush() {
+        move_mouse(input, x, y)?;
+        thread::sleep(point_delay);
+    }
+
+    pen(input, config.pen_action, Direction::Release)?;
+    thread::sleep(line_delay);
+
+    Ok(())
+}
+
+/// Lifts the pen where it is and puts it straight back down, splitting a
+/// long stroke, see [`DrawConfig::lift_every`]
+pub fn lift_and_press<B: InputBackend>(
+    input: &mut B,
+    move_filter: &mut MoveFilter,
+    line_delay: Duration,
+    config: &DrawConfig,
+) -> Result<(), DrawError> {
+    if let Some((x, y)) = move_filter.flush() {
+        move_mouse(input, x, y)?;
+    }
+
+    pen(input, config.pen_action, Direction::Release)?;
+    thread::sleep(line_delay);
+    pen(input, config.pen_action, Direction::Press)?;
+    thread::sleep(config.settle_delay);
+
+    Ok(())
+}
+
+/// Puts the pen down at `pos` using the configured stroke-start gesture,
+/// leaving the pen down on `pos`
+///
+/// The circle gesture pauses `point_delay` after each of its points, like the
+/// rest of the stroke.
+pub fn start_stroke<B: InputBackend>(
+    input: &mut B,
+    pos: (i32, i32),
+    point_delay: Duration,
+    config: &DrawConfig,
+) -> Result<(), DrawError> {
+    const TAP_DELAY: Duration = Duration::from_millis(15);
+    const CIRCLE_RADIUS: f64 = 2.0;
+    const CIRCLE_POINTS: u32 = 8;
+
+    pen(input, config.pen_action, Direction::Press)?;
+
+    match config.stroke_start {
+        StrokeStart::Press => {}
+        StrokeStart::DoubleTap => {
+            thread::sleep(TAP_DELAY);
+            pen(input, config.pen_action, Direction::Release)?;
+            thread::sleep(TAP_DELAY);
+            pen(input, config.pen_action, Direction::Press)?;
+        }
+        StrokeStart::Circle => {
+            for point in 1..=CIRCLE_POINTS {
+                let angle = point as f64 / CIRCLE_POINTS as f64 * std::f64::consts::TAU;
+                let x = pos.0 as f64 + CIRCLE_RADIUS * (angle.cos() - 1.0);
+                let y = pos.1 as f64 + CIRCLE_RADIUS * angle.sin();
+
+                move_mouse(input, x.round() as i32, y.round() as i32)?;
+                thread::sleep(point_delay);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Taps a key `times` times, pausing after each press
+pub fn press_key_times<B: InputBackend>(
+    input: &mut B,
+    key: char,
+    times: u32,
+) -> Result<(), DrawError> {
+    for _ in 0..times {
+        try_input(input, "press a key", |input| {
+            input.key(Key::Unicode(key), Direction::Click)
+        })?;
+        thread::sleep(KEY_DELAY);
+    }
+
+    Ok(())
+}
+
+/// Puts the pen down or lifts it using the configured input
+pub fn pen<B: InputBackend>(
+    input: &mut B,
+    action: PenAction,
+    direction: Direction,
+) -> Result<(), DrawError> {
+    match action {
+        PenAction::MouseButton(button) if direction == Direction::Release => {
+            try_input(input, "release the mouse button", |input| {
+                input.button_release(button)
+            })
+        }
+        PenAction::MouseButton(button) => try_input(input, "press the mouse button", |input| {
+            input.button_press(button)
+        }),
+        PenAction::Key(key) if direction == Direction::Release => {
+            try_input(input, "release the pen key", |input| {
+                input.key(key, Direction::Release)
+            })
+        }
+        PenAction::Key(key) => try_input(input, "press the pen key", |input| {
+            input.key(key, Direction::Press)
+        }),
+    }
+}
+
+/// Moves the cursor with the pen down, unless the move is too small to matter
+pub fn pen_move<B: InputBackend>(
+    input: &mut B,
+    pos: (i32, i32),
+    move_filter: &mut MoveFilter,
+    delay: Duration,
+) -> Result<(), DrawError> {
+    if !move_filter.accept(pos) {
+        return Ok(());
+    }
+
+    move_mouse(input, pos.0, pos.1)?;
+
+    if !delay.is_zero() {
+        thread::sleep(delay);
+    }
+
+    Ok(())
+}
+
+/// Moves the cursor through `points` with the pen up, pausing `pause` after
+/// each
+///
+/// # Errors
+/// Fails if moving the cursor still fails after a retry, see [`try_input`].
+pub fn sweep<B: InputBackend>(
+    input: &mut B,
+    points: impl IntoIterator<Item = (i32, i32)>,
+    pause: Duration,
+) -> Result<(), DrawError> {
+    for (x, y) in points {
+        move_mouse(input, x, y)?;
+        thread::sleep(pause);
+    }
+
+    Ok(())
+}
+
+pub fn move_mouse<B: InputBackend>(input: &mut B, x: i32, y: i32) -> Result<(), DrawError> {
+    try_input(input, "move the mouse", |input| input.move_to(x, y))
+}
+
+/// Simulates input through the backend, retrying once after
+/// [`INPUT_RETRY_DELAY`] since most failures are transient
+pub fn try_input<B: InputBackend>(
+    input: &mut B,
+    action: &'static str,
+    mut simulate: impl FnMut(&mut B) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), DrawError> {
+    if let Err(e) = simulate(input) {
+        warn!("Failed to {}, retrying: {}", action, e);
+        thread::sleep(INPUT_RETRY_DELAY);
+        simulate(input).map_err(|source| DrawError::Input { action, source })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::{input::MockBackend, recording::InputEvent};
+    use enigo::Button;
+
+    /// Default settings without any delays
+    fn instant_config() -> DrawConfig {
+        DrawConfig {
+            point_delay: Duration::ZERO,
+            line_delay: Duration::ZERO,
+            settle_delay: Duration::ZERO,
+            ..DrawConfig::default()
+        }
+    }
+
+    fn draw(input: &mut MockBackend, line: &[Point], from: Option<(i32, i32)>) {
+        let config = instant_config();
+        let delays = (Duration::ZERO, Duration::ZERO);
+        draw_stroke(
+            input,
+            line,
+            (100, 50),
+            from,
+            &mut Jitter::new(0.0, 0),
+            delays,
+            &config,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn stroke_moves_between_press_and_release() {
+        let mut input = MockBackend::new();
+        let line = [Point::new(0, 0), Point::new(1, 0), Point::new(2, 0)];
+        draw(&mut input, &line, None);
+
+        assert_eq!(
+            input.events,
+            vec![
+                InputEvent::Move { x: 100, y: 50 },
+                InputEvent::ButtonPress(Button::Left),
+                InputEvent::Move { x: 101, y: 50 },
+                InputEvent::Move { x: 102, y: 50 },
+                InputEvent::ButtonRelease(Button::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_line_is_its_own_stroke() {
+        let mut input = MockBackend::new();
+        draw(&mut input, &[Point::new(0, 0), Point::new(0, 1)], None);
+        draw(
+            &mut input,
+            &[Point::new(5, 5), Point::new(6, 6)],
+            Some((100, 51)),
+        );
+
+        assert_eq!(
+            input.events,
+            vec![
+                InputEvent::Move { x: 100, y: 50 },
+                InputEvent::ButtonPress(Button::Left),
+                InputEvent::Move { x: 100, y: 51 },
+                InputEvent::ButtonRelease(Button::Left),
+                InputEvent::Move { x: 105, y: 55 },
+                InputEvent::ButtonPress(Button::Left),
+                InputEvent::Move { x: 106, y: 56 },
+                InputEvent::ButtonRelease(Button::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn mouse_button_pen_presses_and_releases_the_button() {
+        let mut input = MockBackend::new();
+        pen(
+            &mut input,
+            PenAction::MouseButton(Button::Right),
+            Direction::Press,
+        )
+        .unwrap();
+        pen(
+            &mut input,
+            PenAction::MouseButton(Button::Right),
+            Direction::Release,
+        )
+        .unwrap();
+
+        assert_eq!(
+            input.events,
+            vec![
+                InputEvent::ButtonPress(Button::Right),
+                InputEvent::ButtonRelease(Button::Right),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_pen_holds_the_key_through_the_stroke() {
+        let mut input = MockBackend::new();
+        let config = DrawConfig {
+            pen_action: PenAction::Key(Key::Unicode('b')),
+            ..instant_config()
+        };
+        let line = [Point::new(0, 0), Point::new(1, 0)];
+        let delays = (Duration::ZERO, Duration::ZERO);
+        draw_stroke(
+            &mut input,
+            &line,
+            (0, 0),
+            None,
+            &mut Jitter::new(0.0, 0),
+            delays,
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            input.events,
+            vec![
+                InputEvent::Move { x: 0, y: 0 },
+                InputEvent::Key(Key::Unicode('b'), Direction::Press),
+                InputEvent::Move { x: 1, y: 0 },
+                InputEvent::Key(Key::Unicode('b'), Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn failing_key_release_is_reported_as_a_release() {
+        let mut input = MockBackend::failing_after(1);
+        let action = PenAction::Key(Key::Unicode('b'));
+        pen(&mut input, action, Direction::Press).unwrap();
+        let result = pen(&mut input, action, Direction::Release);
+
+        assert!(matches!(
+            result,
+            Err(DrawError::Input {
+                action: "release the pen key",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn long_line_is_split_into_sub_strokes() {
+        let mut input = MockBackend::new();
+        let config = DrawConfig {
+            lift_every: Some(100),
+            ..instant_config()
+        };
+        let line: Vec<Point> = (0..1000).map(|x| Point::new(x, 0)).collect();
+        let delays = (Duration::ZERO, Duration::ZERO);
+        draw_stroke(
+            &mut input,
+            &line,
+            (0, 0),
+            None,
+            &mut Jitter::new(0.0, 0),
+            delays,
+            &config,
+        )
+        .unwrap();
+
+        // Moves made with the pen down, one entry per sub-stroke
+        let mut sub_strokes = Vec::new();
+        let mut pen_down = false;
+        for event in &input.events {
+            match event {
+                InputEvent::ButtonPress(_) => {
+                    pen_down = true;
+                    sub_strokes.push(0);
+                }
+                InputEvent::ButtonRelease(_) => pen_down = false,
+                InputEvent::Move { .. } if pen_down => *sub_strokes.last_mut().unwrap() += 1,
+                _ => {}
+            }
+        }
+
+        assert_eq!(sub_strokes.len(), 10);
+        assert!(sub_strokes.iter().all(|&moves| moves <= 100));
+        assert_eq!(sub_strokes.iter().sum::<usize>(), 999);
+        assert!(!pen_down);
+    }
+
+    #[test]
+    fn input_is_retried_before_failing() {
+        let mut input = MockBackend::failing_after(0);
+        let result = move_mouse(&mut input, 10, 10);
+
+        assert!(matches!(
+            result,
+            Err(DrawError::Input {
+                action: "move the mouse",
+                ..
+            })
+        ));
+        assert!(input.events.is_empty());
+        assert_eq!(input.attempts, 2);
+    }
+
+    #[test]
+    fn one_failed_attempt_is_recovered_by_the_retry() {
+        let mut input = MockBackend::failing_after(0).recovering_after(1);
+        move_mouse(&mut input, 10, 10).unwrap();
+
+        assert_eq!(input.events, vec![InputEvent::Move { x: 10, y: 10 }]);
+        assert_eq!(input.attempts, 2);
+    }
+
+    #[test]
+    fn sweep_stops_at_the_first_failing_move() {
+        let mut input = MockBackend::failing_after(2);
+        let result = sweep(
+            &mut input,
+            [(0, 0), (10, 0), (10, 10), (0, 10)],
+            Duration::ZERO,
+        );
+
+        assert!(matches!(
+            result,
+            Err(DrawError::Input {
+                action: "move the mouse",
+                ..
+            })
+        ));
+        assert_eq!(
+            input.events,
+            vec![
+                InputEvent::Move { x: 0, y: 0 },
+                InputEvent::Move { x: 10, y: 0 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::utils::geometry::Point;
+use resvg::{
+    tiny_skia::{self, PathSegment},
+    usvg,
+};
+
+/// Flattens the outline of every path of an SVG into polylines, without
+/// rasterizing it
+///
+/// Coordinates are in SVG user units multiplied by `scale`, with the path's
+/// own transforms applied. Bézier curves are split into straight segments that
+/// stay within `tolerance` units (after scaling) of the curve, so curves come
+/// out smooth at any size. Text is drawn as the outlines of its glyphs.
+pub fn svg_to_polylines(tree: &usvg::Tree, scale: f32, tolerance: f32) -> Vec<Vec<Point>> {
+    let mut lines = Vec::new();
+    collect_group(tree.root(), scale, tolerance.max(0.01), &mut lines);
+
+    lines
+}
+
+fn collect_group(group: &usvg::Group, scale: f32, tolerance: f32, lines: &mut Vec<Vec<Point>>) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(group) => collect_group(group, scale, tolerance, lines),
+            usvg::Node::Text(text) => collect_group(text.flattened(), scale, tolerance, lines),
+            usvg::Node::Path(path) => {
+                let transform = path.abs_transform().post_scale(scale, scale);
+                if let Some(data) = path.data().clone().transform(transform) {
+                    flatten_path(&data, tolerance, lines);
+                }
+            }
+            usvg::Node::Image(_) => {}
+        }
+    }
+}
+
+/// Appends one polyline per subpath of `path`
+fn flatten_path(path: &tiny_skia::Path, tolerance: f32, lines: &mut Vec<Vec<Point>>) {
+    let mut line: Vec<Point> = Vec::new();
+    let mut start = tiny_skia::Point::zero();
+    let mut current = start;
+
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(point) => {
+                if line.len() > 1 {
+                    lines.push(std::mem::take(&mut line));
+                } else {
+                    line.clear();
+                }
+                push(&mut line, point);
+                start = point;
+                current = point;
+            }
+            PathSegment::LineTo(point) => {
+                push(&mut line, point);
+                current = point;
+            }
+            PathSegment::QuadTo(control, end) => {
+                let steps =
+                    flattening_steps(second_difference(current, control, end), 0.25, tolerance);
+                for step in 1..=steps {
+                    let t = step as f32 / steps as f32;
+                    push(&mut line, quadratic_at(current, control, end, t));
+                }
+                current = end;
+            }
+            PathSegment::CubicTo(control1, control2, end) => {
+                let curvature = second_difference(current, control1, control2)
+                    .max(second_difference(control1, control2, end));
+                let steps = flattening_steps(curvature, 0.75, tolerance);
+                for step in 1..=steps {
+                    let t = step as f32 / steps as f32;
+                    push(&mut line, cubic_at(current, control1, control2, end, t));
+                }
+                current = end;
+            }
+            PathSegment::Close => {
+                push(&mut line, start);
+                current = start;
+            }
+        }
+    }
+
+    if line.len() > 1 {
+        lines.push(line);
+    }
+}
+
+/// Appends `point` rounded to whole pixels, unless that repeats the last point
+fn push(line: &mut Vec<Point>, point: tiny_skia::Point) {
+    let point = Point::new(point.x.round() as i32, point.y.round() as i32);
+    if line.last() != Some(&point) {
+        line.push(point);
+    }
+}
+
+/// Number of straight segments keeping a Bézier curve within `tolerance` of
+/// its flattening, by Wang's formula
+///
+/// `curvature` is the largest second difference of the control points and
+/// `factor` is `n * (n - 1) / 8` for a curve of degree `n`.
+fn flattening_steps(curvature: f32, factor: f32, tolerance: f32) -> u32 {
+    ((factor * curvature / tolerance).sqrt().ceil() as u32).max(1)
+}
+
+/// Length of `a - 2b + c`
+fn second_difference(a: tiny_skia::Point, b: tiny_skia::Point, c: tiny_skia::Point) -> f32 {
+    let x = a.x - 2.0 * b.x + c.x;
+    let y = a.y - 2.0 * b.y + c.y;
+
+    (x * x + y * y).sqrt()
+}
+
+fn quadratic_at(
+    start: tiny_skia::Point,
+    control: tiny_skia::Point,
+    end: tiny_skia::Point,
+    t: f32,
+) -> tiny_skia::Point {
+    let u = 1.0 - t;
+
+    tiny_skia::Point::from_xy(
+        u * u * start.x + 2.0 * u * t * control.x + t * t * end.x,
+        u * u * start.y + 2.0 * u * t * control.y + t * t * end.y,
+    )
+}
+
+fn cubic_at(
+    start: tiny_skia::Point,
+    control1: tiny_skia::Point,
+    control2: tiny_skia::Point,
+    end: tiny_skia::Point,
+    t: f32,
+) -> tiny_skia::Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+
+    tiny_skia::Point::from_xy(
+        a * start.x + b * control1.x + c * control2.x + d * end.x,
+        a * start.y + b * control1.y + c * control2.y + d * end.y,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cubic_flattens_monotonically_within_tolerance() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <path d="M 0 0 C 0 100 100 100 100 0" fill="none" stroke="black"/>
+        </svg>"#;
+        let tree = usvg::Tree::from_data(svg.as_bytes(), &usvg::Options::default()).unwrap();
+        let tolerance = 0.5;
+
+        let lines = svg_to_polylines(&tree, 1.0, tolerance);
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert_eq!(line.first(), Some(&Point::new(0, 0)));
+        assert_eq!(line.last(), Some(&Point::new(100, 0)));
+        assert!(line.windows(2).all(|pair| pair[0].x <= pair[1].x));
+
+        // Densely sampled (0, 0) (0, 100) (100, 100) (100, 0) curve
+        let curve: Vec<(f64, f64)> = (0..=10_000)
+            .map(|step| {
+                let t = step as f64 / 10_000.0;
+                let (b, c, d) = (
+                    3.0 * (1.0 - t).powi(2) * t,
+                    3.0 * (1.0 - t) * t * t,
+                    t.powi(3),
+                );
+                ((c + d) * 100.0, (b + c) * 100.0)
+            })
+            .collect();
+        let distance = |x: f64, y: f64| {
+            curve
+                .iter()
+                .map(|&(cx, cy)| (cx - x).hypot(cy - y))
+                .fold(f64::INFINITY, f64::min)
+        };
+
+        // Points are rounded to whole pixels on top of the flattening error
+        let allowed = tolerance as f64 + 0.5f64.hypot(0.5);
+        for pair in line.windows(2) {
+            let (x0, y0) = (pair[0].x as f64, pair[0].y as f64);
+            let (x1, y1) = (pair[1].x as f64, pair[1].y as f64);
+
+            assert!(distance(x0, y0) <= allowed, "{:?}", pair[0]);
+            assert!(
+                distance((x0 + x1) / 2.0, (y0 + y1) / 2.0) <= allowed,
+                "{:?}",
+                pair
+            );
+        }
+    }
+}
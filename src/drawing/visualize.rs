@@ -0,0 +1,64 @@
+use crate::utils::geometry::Point;
+use image::{Rgb, RgbImage};
+use imageproc::drawing::draw_line_segment_mut;
+
+/// Renders lines onto a white canvas, colored by draw order
+///
+/// The first line is drawn in blue and the last in red, with the lines in
+/// between fading from one to the other. Long jumps between consecutive colors
+/// reveal poor ordering, and many short specks reveal fragmented extraction.
+pub fn render_paths_ordered(lines: &[Vec<Point>], width: u32, height: u32) -> RgbImage {
+    let mut canvas = RgbImage::from_pixel(width.max(1), height.max(1), Rgb([255, 255, 255]));
+    let last = lines.len().saturating_sub(1).max(1) as f32;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let t = idx as f32 / last;
+        let color = Rgb([(255.0 * t) as u8, 0, (255.0 * (1.0 - t)) as u8]);
+
+        if let [point] = line.as_slice() {
+            let (x, y) = (point.x, point.y);
+            if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                canvas.put_pixel(x as u32, y as u32, color);
+            }
+            continue;
+        }
+
+        for segment in line.windows(2) {
+            draw_line_segment_mut(
+                &mut canvas,
+                (segment[0].x as f32, segment[0].y as f32),
+                (segment[1].x as f32, segment[1].y as f32),
+                color,
+            );
+        }
+    }
+
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_lines_at_the_requested_size() {
+        let lines = vec![
+            vec![Point::new(0, 0), Point::new(9, 0)],
+            vec![Point::new(0, 5), Point::new(9, 5)],
+        ];
+        let canvas = render_paths_ordered(&lines, 10, 8);
+
+        assert_eq!(canvas.dimensions(), (10, 8));
+        assert_eq!(*canvas.get_pixel(4, 0), Rgb([0, 0, 255]));
+        assert_eq!(*canvas.get_pixel(4, 5), Rgb([255, 0, 0]));
+        assert_eq!(*canvas.get_pixel(4, 7), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn empty_line_set_gives_a_blank_canvas() {
+        let canvas = render_paths_ordered(&[], 4, 3);
+
+        assert_eq!(canvas.dimensions(), (4, 3));
+        assert!(canvas.pixels().all(|&pixel| pixel == Rgb([255, 255, 255])));
+    }
+}
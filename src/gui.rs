@@ -0,0 +1,272 @@
+use crate::{
+    choices::{ImageProcessingMethod, ScalingMode},
+    drawing::{config::DrawConfig, extraction::extract_paths},
+    image_processing::{
+        ImageLoader, ImageProcessor, ImageScaler, ProcessingOptions, ThresholdSource,
+        SUPPORTED_EXTENSIONS,
+    },
+    utils::screen::RegionSpec,
+};
+use eframe::egui::{self, ColorImage, TextureHandle, TextureOptions};
+use image::{DynamicImage, Rgb};
+use native_dialog::DialogBuilder;
+use std::{cell::RefCell, rc::Rc};
+
+/// Size (in pixels) of the box the binarized preview is fitted into
+const PREVIEW_SIZE: (i32, i32) = (640, 480);
+
+/// Image and processing settings picked in the GUI
+#[derive(Debug, Clone)]
+pub struct GuiSelection {
+    /// Path of the image to draw
+    pub image_path: String,
+
+    /// Processing method to binarize the image with
+    pub method: ImageProcessingMethod,
+
+    /// Options the image is binarized with
+    pub options: ProcessingOptions,
+
+    /// Region to draw into, or `None` to mark it with the hotkeys
+    pub region: Option<RegionSpec>,
+}
+
+/// Opens a window to pick an image, its processing settings and the region to
+/// draw into while watching a live preview of the binarized result
+///
+/// Processing goes through [`ImageProcessor`] exactly as in the terminal flow,
+/// so the preview matches what gets drawn, only at a lower resolution.
+/// Transparent pixels are shown composited onto `background`.
+///
+/// # Returns
+/// - `Ok(Some(GuiSelection))`: The settings to draw with, once "Draw" is clicked
+/// - `Ok(None)`: The window was closed without picking anything
+/// - `Err`: The window couldn't be opened
+pub fn run_gui(background: Rgb<u8>) -> Result<Option<GuiSelection>, Box<dyn std::error::Error>> {
+    let selection = Rc::new(RefCell::new(None));
+    let app = GuiApp::new(background, Rc::clone(&selection));
+
+    eframe::run_native(
+        "drawrs",
+        eframe::NativeOptions::default(),
+        Box::new(|_| Ok(Box::new(app))),
+    )?;
+
+    Ok(selection.take())
+}
+
+/// State of the GUI window
+struct GuiApp {
+    background: Rgb<u8>,
+    selection: Rc<RefCell<Option<GuiSelection>>>,
+    image_path: Option<String>,
+
+    /// Source image fitted into [`PREVIEW_SIZE`]
+    thumbnail: Option<DynamicImage>,
+    method: ImageProcessingMethod,
+    manual_threshold: bool,
+    threshold: u16,
+
+    /// Whether the region is set with [`Self::region`] instead of the hotkeys
+    fixed_region: bool,
+
+    /// Left, top, right and bottom edges of the region, as percentages of the
+    /// screen size
+    region: [f64; 4],
+    preview: Option<TextureHandle>,
+    path_count: usize,
+    error: Option<String>,
+}
+
+impl GuiApp {
+    fn new(background: Rgb<u8>, selection: Rc<RefCell<Option<GuiSelection>>>) -> Self {
+        GuiApp {
+            background,
+            selection,
+            image_path: None,
+            thumbnail: None,
+            method: ImageProcessingMethod::Otsu,
+            manual_threshold: false,
+            threshold: u16::MAX / 2,
+            fixed_region: false,
+            region: [25.0, 25.0, 75.0, 75.0],
+            preview: None,
+            path_count: 0,
+            error: None,
+        }
+    }
+
+    fn options(&self) -> ProcessingOptions {
+        ProcessingOptions {
+            threshold_source: if self.manual_threshold {
+                ThresholdSource::Manual(self.threshold)
+            } else {
+                ThresholdSource::Auto
+            },
+            ..ProcessingOptions::default()
+        }
+    }
+
+    fn region_spec(&self) -> Option<RegionSpec> {
+        let [left, top, right, bottom] = self.region;
+
+        self.fixed_region.then_some(RegionSpec::Percent {
+            left,
+            top,
+            right,
+            bottom,
+        })
+    }
+
+    /// Asks for an image file and loads a thumbnail of it
+    fn open_image(&mut self) {
+        let picked = DialogBuilder::file()
+            .add_filter("Image Files", SUPPORTED_EXTENSIONS)
+            .open_single_file()
+            .show();
+
+        let path = match picked {
+            Ok(Some(file)) => file.display().to_string(),
+            Ok(None) => return,
+            Err(e) => {
+                self.error = Some(format!("Failed to open the file dialog: {}", e));
+                return;
+            }
+        };
+
+        let thumbnail = ImageLoader::load(&path).and_then(|img| {
+            let img = ImageLoader::flatten_alpha(img, self.background);
+            let fitted = ImageScaler::scale_image_to_region(
+                &img.to_luma16(),
+                None,
+                (0, 0),
+                PREVIEW_SIZE,
+                ScalingMode::Fit,
+            )?;
+
+            Ok(DynamicImage::ImageLuma16(fitted))
+        });
+
+        match thumbnail {
+            Ok(thumbnail) => {
+                self.image_path = Some(path);
+                self.thumbnail = Some(thumbnail);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Error loading image: {}", e)),
+        }
+    }
+
+    /// Binarizes the thumbnail with the current settings and uploads the result
+    fn update_preview(&mut self, ctx: &egui::Context) {
+        let Some(thumbnail) = &self.thumbnail else {
+            return;
+        };
+
+        let binary_img = match ImageProcessor::process_dynamic_image_8bit(
+            thumbnail,
+            self.method,
+            &self.options(),
+        ) {
+            Ok(binary_img) => binary_img,
+            Err(e) => {
+                self.error = Some(format!("Error processing image: {}", e));
+                return;
+            }
+        };
+
+        self.path_count = extract_paths(&binary_img, &DrawConfig::default()).len();
+        let size = [binary_img.width() as usize, binary_img.height() as usize];
+        self.preview = Some(ctx.load_texture(
+            "preview",
+            ColorImage::from_gray(size, binary_img.as_raw()),
+            TextureOptions::default(),
+        ));
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let mut changed = false;
+
+            ui.horizontal(|ui| {
+                if ui.button("Open image...").clicked() {
+                    self.open_image();
+                    changed = true;
+                }
+
+                if let Some(path) = &self.image_path {
+                    ui.label(path.as_str());
+                }
+            });
+
+            egui::ComboBox::from_label("Processing method")
+                .selected_text(format!("{:?}", self.method))
+                .show_ui(ui, |ui| {
+                    for method in ImageProcessingMethod::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.method, method, format!("{:?}", method))
+                            .changed();
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .checkbox(&mut self.manual_threshold, "Manual threshold")
+                    .changed();
+                changed |= ui
+                    .add_enabled(
+                        self.manual_threshold,
+                        egui::Slider::new(&mut self.threshold, 0..=u16::MAX),
+                    )
+                    .changed();
+            });
+
+            if changed {
+                self.update_preview(ctx);
+            }
+
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::RED, error.as_str());
+            }
+
+            if let Some(preview) = &self.preview {
+                ui.label(format!("{} paths at preview size", self.path_count));
+                ui.add(egui::Image::new(preview).shrink_to_fit());
+            }
+
+            ui.checkbox(
+                &mut self.fixed_region,
+                "Set the region as a part of the screen",
+            );
+            ui.add_enabled_ui(self.fixed_region, |ui| {
+                let [left, top, right, bottom] = &mut self.region;
+                for (edge, label) in [
+                    (left, "Left edge"),
+                    (top, "Top edge"),
+                    (right, "Right edge"),
+                    (bottom, "Bottom edge"),
+                ] {
+                    ui.add(egui::Slider::new(edge, 0.0..=100.0).suffix("%").text(label));
+                }
+            });
+
+            if !self.fixed_region {
+                ui.label("After clicking Draw, mark the region with the S and E hotkeys");
+            }
+            let draw = ui.add_enabled(self.image_path.is_some(), egui::Button::new("Draw"));
+            if draw.clicked() {
+                if let Some(image_path) = self.image_path.clone() {
+                    *self.selection.borrow_mut() = Some(GuiSelection {
+                        image_path,
+                        method: self.method,
+                        options: self.options(),
+                        region: self.region_spec(),
+                    });
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    choices::ImageProcessingMethod, image_processing::ProcessingOptions, utils::hash::StableHasher,
+};
+use image::{DynamicImage, Rgb};
+use std::{path::PathBuf, time::UNIX_EPOCH};
+
+/// On-disk cache of binarized images
+///
+/// Entries live in the system temp directory and are keyed by the source
+/// file's canonical path and modification time, the frame of an animation, the
+/// background transparent pixels were composited onto, the processing method
+/// and its parameters. Changing any of these produces a different key, so stale
+/// entries are never reused, while a rebuilt binary keeps finding the entries
+/// of an older one.
+pub struct ProcessingCache;
+
+impl ProcessingCache {
+    /// Loads the cached binary image for the given inputs, if one exists
+    ///
+    /// `frame` is the index of the frame of an animation, 0 for still images.
+    pub fn load(
+        image_path: &str,
+        frame: usize,
+        background: Rgb<u8>,
+        method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Option<DynamicImage> {
+        let entry = Self::entry_path(image_path, frame, background, method, options)?;
+
+        image::open(entry).ok()
+    }
+
+    /// Stores a binary image under the key for the given inputs
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the entry was written
+    /// - `Err`: If the source file can't be inspected or the entry can't be saved
+    pub fn store(
+        image_path: &str,
+        frame: usize,
+        background: Rgb<u8>,
+        method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+        binary_img: &DynamicImage,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let entry = Self::entry_path(image_path, frame, background, method, options)
+            .ok_or("Failed to read the source image's metadata")?;
+
+        if let Some(dir) = entry.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        binary_img.save(&entry)?;
+
+        Ok(())
+    }
+
+    fn entry_path(
+        image_path: &str,
+        frame: usize,
+        background: Rgb<u8>,
+        method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Option<PathBuf> {
+        let source = std::fs::canonicalize(image_path).ok()?;
+        let modified = std::fs::metadata(&source)
+            .ok()?
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?;
+
+        let source = source.to_string_lossy();
+        let settings = format!("{:?} {:?}", method, options);
+        let mut hasher = StableHasher::new();
+        hasher
+            .write(&(source.len() as u64).to_le_bytes())
+            .write(source.as_bytes())
+            .write(&modified.as_nanos().to_le_bytes())
+            .write(&(frame as u64).to_le_bytes())
+            .write(&background.0)
+            .write(settings.as_bytes());
+
+        Some(
+            std::env::temp_dir()
+                .join("drawrs-cache")
+                .join(format!("{:016x}.png", hasher.finish())),
+        )
+    }
+}
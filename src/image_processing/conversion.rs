@@ -1,27 +1,389 @@
-use image::{ImageBuffer, Luma};
+use image::{imageops::FilterType, DynamicImage, GrayImage, ImageBuffer, Luma, Primitive, Rgb};
 use rayon::prelude::*;
-use std::cmp::min;
+use std::{
+    cmp::min,
+    time::{Duration, Instant},
+};
 
-use crate::choices::ImageProcessingMethod;
+use crate::{
+    choices::{BitDepth, CombineOp, ImageProcessingMethod},
+    image_processing::{
+        clahe, compute_histogram, equalize_histogram, remove_small_components, unsharp, ImageLoader,
+    },
+    utils::timing,
+};
 
 fn calculate_window_size(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u32 {
     ((min(img.dimensions().0, img.dimensions().1) as f32 * 0.05).round() as u32).clamp(5, 50)
 }
 
+/// Shrinks a window radius so at least one full `2 * radius + 1` window fits
+/// inside the image
+///
+/// Returns 0 when the image is too thin for any window with a radius.
+fn fit_window(img: &ImageBuffer<Luma<u16>, Vec<u16>>, window_size: u32) -> u32 {
+    let (width, height) = img.dimensions();
+    window_size.min(width.min(height).saturating_sub(1) / 2)
+}
+
+/// Builds inclusive summed-area tables of pixel values and squared pixel values
+///
+/// Entry `y * width + x` holds the sum over the rectangle from `(0, 0)` to `(x, y)`.
+fn compute_integral_images(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> (Vec<u64>, Vec<u64>) {
+    let (width, height) = img.dimensions();
+    let mut integral = vec![0u64; (width * height) as usize];
+    let mut integral_sq = vec![0u64; (width * height) as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
+            let pixel = img.get_pixel(x, y)[0] as u64;
+            integral[idx] = pixel
+                + if x > 0 { integral[idx - 1] } else { 0 }
+                + if y > 0 {
+                    integral[idx - width as usize]
+                } else {
+                    0
+                }
+                - if x > 0 && y > 0 {
+                    integral[idx - width as usize - 1]
+                } else {
+                    0
+                };
+            integral_sq[idx] = pixel * pixel
+                + if x > 0 { integral_sq[idx - 1] } else { 0 }
+                + if y > 0 {
+                    integral_sq[idx - width as usize]
+                } else {
+                    0
+                }
+                - if x > 0 && y > 0 {
+                    integral_sq[idx - width as usize - 1]
+                } else {
+                    0
+                };
+        }
+    }
+
+    (integral, integral_sq)
+}
+
+/// Darkest and brightest pixel of the window of radius `radius` around each
+/// pixel, clipped to the image, in row-major order
+///
+/// Runs a sliding minimum and maximum along the rows and then along the
+/// columns, so the cost per pixel doesn't grow with the radius.
+fn window_extremes(img: &ImageBuffer<Luma<u16>, Vec<u16>>, radius: u32) -> (Vec<u16>, Vec<u16>) {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    if width == 0 || height == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let radius = radius as usize;
+    let by_rows = |values: &[u16], pick: fn(u16, u16) -> u16, identity: u16| {
+        values
+            .par_chunks(width)
+            .flat_map_iter(|row| sliding_extreme(row, radius, pick, identity))
+            .collect::<Vec<u16>>()
+    };
+    let by_columns = |values: &[u16], pick: fn(u16, u16) -> u16, identity: u16| {
+        let columns: Vec<Vec<u16>> = (0..width)
+            .into_par_iter()
+            .map(|x| {
+                let column: Vec<u16> = (0..height).map(|y| values[y * width + x]).collect();
+                sliding_extreme(&column, radius, pick, identity)
+            })
+            .collect();
+
+        (0..width * height)
+            .map(|idx| columns[idx % width][idx / width])
+            .collect::<Vec<u16>>()
+    };
+
+    let minima = by_columns(
+        &by_rows(img.as_raw(), u16::min, u16::MAX),
+        u16::min,
+        u16::MAX,
+    );
+    let maxima = by_columns(
+        &by_rows(img.as_raw(), u16::max, u16::MIN),
+        u16::max,
+        u16::MIN,
+    );
+
+    (minima, maxima)
+}
+
+/// Running `pick` (min or max) over the window `i - radius..=i + radius` of
+/// each value, treating positions outside the slice as `identity`
+///
+/// Uses the van Herk/Gil-Werman algorithm: the padded values are cut into
+/// blocks as wide as the window, so every window spans the tail of one block
+/// and the head of the next, both of which are precomputed.
+fn sliding_extreme(
+    values: &[u16],
+    radius: usize,
+    pick: fn(u16, u16) -> u16,
+    identity: u16,
+) -> Vec<u16> {
+    let window = 2 * radius + 1;
+    let padding = vec![identity; radius];
+    let padded = [padding.as_slice(), values, padding.as_slice()].concat();
+    let running = |acc: &mut u16, &value: &u16| {
+        *acc = pick(*acc, value);
+        Some(*acc)
+    };
+
+    let head: Vec<u16> = padded
+        .chunks(window)
+        .flat_map(|block| block.iter().scan(identity, running))
+        .collect();
+    let tail: Vec<u16> = padded
+        .chunks(window)
+        .flat_map(|block| {
+            let mut tail: Vec<u16> = block.iter().rev().scan(identity, running).collect();
+            tail.reverse();
+            tail
+        })
+        .collect();
+
+    (0..values.len())
+        .map(|i| pick(tail[i], head[i + 2 * radius]))
+        .collect()
+}
+
+/// Converts a 16-bit grayscale image to 8 bits
+fn narrow_to_8bit(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        Luma([(img.get_pixel(x, y)[0] / 257) as u8])
+    })
+}
+
+/// Shrinks an image so its longest side is at most `max_side` pixels
+///
+/// Returns `None` when the image is already small enough.
+fn downscale_for_analysis(
+    img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    max_side: u32,
+) -> Option<ImageBuffer<Luma<u16>, Vec<u16>>> {
+    let (width, height) = img.dimensions();
+    let longest = width.max(height);
+    if max_side == 0 || longest <= max_side {
+        return None;
+    }
+
+    let scale = max_side as f64 / longest as f64;
+    let new_width = ((width as f64 * scale).round() as u32).max(1);
+    let new_height = ((height as f64 * scale).round() as u32).max(1);
+
+    Some(image::imageops::resize(
+        img,
+        new_width,
+        new_height,
+        FilterType::Triangle,
+    ))
+}
+
+/// Mean and standard deviation of the inclusive rectangle `(x0, y0)..=(x1, y1)`,
+/// from summed-area tables of pixel values and squared pixel values
+fn window_mean_std(
+    integral: &[u64],
+    integral_sq: &[u64],
+    width: u32,
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+) -> (f64, f64) {
+    let area = ((x1 - x0 + 1) * (y1 - y0 + 1)) as f64;
+    let mean = integral_sum(integral, width, x0, y0, x1, y1) as f64 / area;
+    let mean_sq = integral_sum(integral_sq, width, x0, y0, x1, y1) as f64 / area;
+
+    (mean, (mean_sq - mean * mean).max(0.0).sqrt())
+}
+
+/// Sums the inclusive rectangle `(x0, y0)..=(x1, y1)` of a summed-area table
+fn integral_sum(integral: &[u64], width: u32, x0: u32, y0: u32, x1: u32, y1: u32) -> u64 {
+    let at = |x: u32, y: u32| integral[(y * width + x) as usize];
+
+    let total = at(x1, y1);
+    let above = if y0 > 0 { at(x1, y0 - 1) } else { 0 };
+    let left = if x0 > 0 { at(x0 - 1, y1) } else { 0 };
+    let corner = if x0 > 0 && y0 > 0 {
+        at(x0 - 1, y0 - 1)
+    } else {
+        0
+    };
+
+    total + corner - above - left
+}
+
+/// Where the binarization threshold comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdSource {
+    /// Computed by the selected processing method
+    Auto,
+
+    /// Fixed global threshold supplied by the user, bypassing the processing method
+    Manual(u16),
+
+    /// Offset added to the threshold(s) computed by the processing method
+    Offset(i32),
+}
+
+/// Tunable parameters for the thresholding methods
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingOptions {
+    /// Bit depth of the grayscale image the threshold is computed on and of
+    /// the resulting binary image
+    pub bit_depth: BitDepth,
+
+    /// Whether to compute, override or nudge the threshold
+    pub threshold_source: ThresholdSource,
+
+    /// Equalize the grayscale histogram before thresholding, see
+    /// [`equalize_histogram`]
+    pub equalize: bool,
+
+    /// Apply [`clahe`] before thresholding with the given `(columns, rows)`
+    /// tile grid and clip limit (`None` disables it)
+    pub clahe: Option<((u32, u32), f64)>,
+
+    /// Sharpen edges with [`unsharp`] masking before thresholding, given as
+    /// `(sigma, amount)` (`None` disables it)
+    pub unsharp: Option<(f64, f64)>,
+
+    /// Compute global thresholds (Otsu, Kapur) on a copy downscaled so its longest
+    /// side is at most this many pixels, then apply them at full resolution
+    ///
+    /// This is an approximation: the histogram of a downscaled image closely
+    /// follows the original, so global thresholds barely move. Local methods
+    /// always run at full resolution.
+    pub threshold_downscale: Option<u32>,
+
+    /// Neighborhood radius for the adaptive mean method (`None` derives it from the image size)
+    pub adaptive_window: Option<u32>,
+
+    /// Constant subtracted from the neighborhood mean by the adaptive mean method,
+    /// in 16-bit intensity units
+    pub adaptive_c: u16,
+
+    /// Neighborhood radius for Bernsen's method
+    pub bernsen_window: u32,
+
+    /// Smallest local contrast (darkest to brightest pixel of the window, in
+    /// 16-bit intensity units) Bernsen's method thresholds locally; flatter
+    /// windows are treated as a uniform region
+    pub bernsen_contrast: u16,
+
+    /// Phansalkar's `p` parameter, controlling the boost applied to dark regions
+    pub phansalkar_p: f64,
+
+    /// Phansalkar's `q` parameter, controlling how fast that boost decays with brightness
+    pub phansalkar_q: f64,
+
+    /// Phansalkar's `k` parameter, weighting the local standard deviation
+    pub phansalkar_k: f64,
+
+    /// Phansalkar's dynamic range of the standard deviation (`R`), on a 0-1 intensity scale
+    pub phansalkar_r: f64,
+
+    /// Second method the image is also binarized with, how the two results are
+    /// merged (see [`ImageProcessor::combine_binary`]) and the second method's
+    /// own options, whose `bit_depth`, `min_component_area` and `combine` are
+    /// ignored (`None` uses only the selected method)
+    pub combine: Option<(ImageProcessingMethod, CombineOp, Box<ProcessingOptions>)>,
+
+    /// Black blobs smaller than this many pixels are removed from the binary
+    /// image, see [`remove_small_components`] (`None` keeps every blob)
+    pub min_component_area: Option<u32>,
+}
+
+impl ProcessingOptions {
+    /// Whether any filter runs on the grayscale image before thresholding
+    pub fn preprocesses(&self) -> bool {
+        self.equalize || self.clahe.is_some() || self.unsharp.is_some()
+    }
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        ProcessingOptions {
+            bit_depth: BitDepth::Sixteen,
+            threshold_source: ThresholdSource::Auto,
+            equalize: false,
+            clahe: None,
+            unsharp: None,
+            threshold_downscale: None,
+            adaptive_window: None,
+            adaptive_c: 2560,
+            bernsen_window: 15,
+            bernsen_contrast: 15 * 257,
+            phansalkar_p: 2.0,
+            phansalkar_q: 10.0,
+            phansalkar_k: 0.25,
+            phansalkar_r: 0.5,
+            combine: None,
+            min_component_area: None,
+        }
+    }
+}
+
+/// Threshold(s) a grayscale image is binarized against
+enum Threshold<T = u16> {
+    /// A single threshold shared by every pixel
+    Global(T),
+
+    /// One threshold per pixel, in row-major order
+    PerPixel(Vec<T>),
+}
+
+impl Threshold {
+    /// Converts 16-bit thresholds to the equivalent 8-bit ones
+    fn into_8bit(self) -> Threshold<u8> {
+        let narrow = |t: u16| (t / 257) as u8;
+
+        match self {
+            Threshold::Global(t) => Threshold::Global(narrow(t)),
+            Threshold::PerPixel(map) => Threshold::PerPixel(map.into_iter().map(narrow).collect()),
+        }
+    }
+
+    /// Shifts every threshold by `offset`, saturating at the 16-bit range
+    fn offset(self, offset: i32) -> Self {
+        let shift = |t: u16| (t as i32 + offset).clamp(0, u16::MAX as i32) as u16;
+
+        match self {
+            Threshold::Global(t) => Threshold::Global(shift(t)),
+            Threshold::PerPixel(map) => Threshold::PerPixel(map.into_iter().map(shift).collect()),
+        }
+    }
+}
+
 /// Processor for image binarization operations
 ///
 /// Provides functionaility to convert images to binary format using
 /// Otsu's thresholding method with parallel processing.
 pub struct ImageProcessor;
 
+/// A method with the binary image it produced and how long that took, see
+/// [`ImageProcessor::process_all_methods`]
+pub type MethodResult = (
+    ImageProcessingMethod,
+    ImageBuffer<Luma<u16>, Vec<u16>>,
+    Duration,
+);
+
 impl ImageProcessor {
     /// Loads an image, converts it to grayscale, and binarizes it using Otsu's method
     ///
     /// # Arguments
     /// * `image_path` - Path to the input image file
+    /// * `processing_method` - Thresholding method used for binarization
+    /// * `options` - Tunable parameters for the thresholding method
     ///
     /// # Returns
-    /// - `Ok(ImageBuffer)`: Binary image buffer with white pixels (255) where original > threshold, black(0) otherwise
+    /// - `Ok(ImageBuffer)`: Binary image buffer with white pixels (65535) where original > threshold, black(0) otherwise
     /// - `Err`: If image loading fails or buffer creation fails
     ///
     /// # Process
@@ -39,24 +401,287 @@ impl ImageProcessor {
     pub fn process_image(
         image_path: &str,
         processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
     ) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>, Box<dyn std::error::Error>> {
-        let img = image::open(image_path)?;
-        let gray_img = img.to_luma16();
+        let img = ImageLoader::load(image_path)?;
+        Self::process_dynamic_image(&img, processing_method, options)
+    }
+
+    /// Binarizes an image with every available method for side-by-side comparison
+    ///
+    /// # Returns
+    /// - `Ok(Vec)`: Each method with its binary image and the time it took
+    /// - `Err`: If image loading or any binarization fails
+    pub fn process_all_methods(
+        image_path: &str,
+        options: &ProcessingOptions,
+        background: Rgb<u8>,
+    ) -> Result<Vec<MethodResult>, Box<dyn std::error::Error>> {
+        let img = ImageLoader::flatten_alpha(ImageLoader::load(image_path)?, background);
+
+        ImageProcessingMethod::ALL
+            .iter()
+            .map(|&method| {
+                let start = Instant::now();
+                let binary_img = Self::process_dynamic_image(&img, method, options)?;
+                Ok((method, binary_img, start.elapsed()))
+            })
+            .collect()
+    }
+
+    /// Converts an already loaded image to grayscale and binarizes it
+    ///
+    /// Same as [`process_image`](Self::process_image), for images that don't come
+    /// from a file (e.g. the clipboard).
+    pub fn process_dynamic_image(
+        img: &DynamicImage,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>, Box<dyn std::error::Error>> {
+        let binary_img = Self::binarize_dynamic(img, processing_method, options)?;
+
+        let binary_img = match &options.combine {
+            Some((second_method, op, second_options)) => {
+                let second = Self::binarize_dynamic(img, *second_method, second_options)?;
+                Self::combine_binary(&binary_img, &second, *op)?
+            }
+            None => binary_img,
+        };
+
+        Ok(Self::despeckle(binary_img, options))
+    }
+
+    /// Binarizes an image with a single method, ignoring `options.combine`
+    fn binarize_dynamic(
+        img: &DynamicImage,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>, Box<dyn std::error::Error>> {
+        let gray_img = Self::preprocess(img, options);
+
+        let _timer = timing::stage("threshold");
+        let threshold = Self::compute_threshold(&gray_img, processing_method, options);
+
+        Self::binarize(&gray_img, &threshold)
+    }
+
+    /// Converts an already loaded image to 8-bit grayscale and binarizes it
+    ///
+    /// Otsu's and Kapur's methods run on a 256-bin histogram, which is orders of
+    /// magnitude faster than the 65536-bin one and is lossless for 8-bit
+    /// sources. The local methods still compute their thresholds at 16 bits
+    /// and have them narrowed to 8 bits. Threshold values in `options` are
+    /// always given in 16-bit units.
+    pub fn process_dynamic_image_8bit(
+        img: &DynamicImage,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Result<ImageBuffer<Luma<u8>, Vec<u8>>, Box<dyn std::error::Error>> {
+        let binary_img = Self::binarize_dynamic_8bit(img, processing_method, options)?;
+
+        let binary_img = match &options.combine {
+            Some((second_method, op, second_options)) => {
+                let second = Self::binarize_dynamic_8bit(img, *second_method, second_options)?;
+                Self::combine_binary(&binary_img, &second, *op)?
+            }
+            None => binary_img,
+        };
+
+        Ok(Self::despeckle(binary_img, options))
+    }
+
+    /// Removes the black blobs smaller than `options.min_component_area`
+    fn despeckle<T: Primitive>(
+        mut binary_img: ImageBuffer<Luma<T>, Vec<T>>,
+        options: &ProcessingOptions,
+    ) -> ImageBuffer<Luma<T>, Vec<T>> {
+        if let Some(min_area) = options.min_component_area {
+            let _timer = timing::stage("despeckle");
+            remove_small_components(&mut binary_img, min_area);
+        }
+
+        binary_img
+    }
+
+    /// Binarizes an image at 8 bits with a single method, ignoring
+    /// `options.combine`
+    fn binarize_dynamic_8bit(
+        img: &DynamicImage,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Result<ImageBuffer<Luma<u8>, Vec<u8>>, Box<dyn std::error::Error>> {
+        // Filters run at 16 bits, the result is narrowed for the 8-bit histogram
+        let preprocessed = options
+            .preprocesses()
+            .then(|| Self::preprocess(img, options));
+        let gray_img = match &preprocessed {
+            Some(gray_img) => narrow_to_8bit(gray_img),
+            None => {
+                let _timer = timing::stage("grayscale");
+                img.to_luma8()
+            }
+        };
+
+        let _timer = timing::stage("threshold");
+        let histogram_method = matches!(
+            processing_method,
+            ImageProcessingMethod::Otsu | ImageProcessingMethod::Kapur
+        );
+
+        let threshold = match options.threshold_source {
+            ThresholdSource::Auto | ThresholdSource::Offset(_) if histogram_method => {
+                let histogram = compute_histogram(&gray_img);
+                let total_pixels = gray_img.width() * gray_img.height();
+
+                let threshold = match processing_method {
+                    ImageProcessingMethod::Otsu => {
+                        Self::otsu_from_histogram(&histogram, total_pixels)
+                    }
+                    _ => Self::kapur_from_histogram(&histogram, total_pixels),
+                };
+                let threshold = Threshold::Global(threshold as u16 * 257);
+
+                match options.threshold_source {
+                    ThresholdSource::Offset(offset) => threshold.offset(offset),
+                    _ => threshold,
+                }
+            }
+            _ => Self::compute_threshold(
+                &preprocessed.unwrap_or_else(|| img.to_luma16()),
+                processing_method,
+                options,
+            ),
+        };
+
+        Self::binarize(&gray_img, &threshold.into_8bit())
+    }
+
+    /// Converts an image to 16-bit grayscale and runs the filters enabled in
+    /// `options` on it, giving the image thresholds are computed on
+    pub fn preprocess(
+        img: &DynamicImage,
+        options: &ProcessingOptions,
+    ) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        let mut gray_img = {
+            let _timer = timing::stage("grayscale");
+            img.to_luma16()
+        };
+
+        if options.equalize {
+            let _timer = timing::stage("equalize");
+            equalize_histogram(&mut gray_img);
+        }
+
+        if let Some((tiles, clip_limit)) = options.clahe {
+            let _timer = timing::stage("clahe");
+            gray_img = clahe(&gray_img, tiles, clip_limit);
+        }
+
+        if let Some((sigma, amount)) = options.unsharp {
+            let _timer = timing::stage("unsharp mask");
+            gray_img = unsharp(&gray_img, sigma, amount);
+        }
+
+        gray_img
+    }
+
+    /// Global threshold (in 16-bit units) the selected method picks for an
+    /// image, or `None` for methods that threshold each pixel separately
+    ///
+    /// Always computed at 16 bits, so it may differ from the 8-bit pipeline's
+    /// threshold by a bin.
+    pub fn global_threshold(
+        img: &DynamicImage,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Option<u16> {
+        match Self::compute_threshold(&Self::preprocess(img, options), processing_method, options) {
+            Threshold::Global(threshold) => Some(threshold),
+            Threshold::PerPixel(_) => None,
+        }
+    }
+
+    /// Computes the 16-bit threshold(s) for a grayscale image according to the
+    /// selected method and threshold source
+    fn compute_threshold(
+        gray_img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        processing_method: ImageProcessingMethod,
+        options: &ProcessingOptions,
+    ) -> Threshold {
+        if let ThresholdSource::Manual(value) = options.threshold_source {
+            return Threshold::Global(value);
+        }
+
+        let downscaled = options
+            .threshold_downscale
+            .and_then(|max_side| downscale_for_analysis(gray_img, max_side));
+        let global_sample = downscaled.as_ref().unwrap_or(gray_img);
 
         let threshold = match processing_method {
-            ImageProcessingMethod::Otsu => Self::calculate_otsu_threshold(&gray_img),
-            ImageProcessingMethod::Kapur => Self::calculate_kapur_threshold(&gray_img),
-            ImageProcessingMethod::Wolfs => Self::calculate_wolf_threshold(&gray_img),
-            ImageProcessingMethod::Bernsens => Self::calculate_bernsen_threshold(&gray_img),
-            ImageProcessingMethod::Sauvola => Self::calculate_sauvola_threshold(&gray_img),
+            ImageProcessingMethod::Otsu => {
+                Threshold::Global(Self::calculate_otsu_threshold(global_sample))
+            }
+            ImageProcessingMethod::Kapur => {
+                Threshold::Global(Self::calculate_kapur_threshold(global_sample))
+            }
+            ImageProcessingMethod::Wolfs => {
+                Threshold::PerPixel(Self::calculate_wolf_threshold(gray_img))
+            }
+            ImageProcessingMethod::Bernsens => {
+                Threshold::PerPixel(Self::calculate_bernsen_threshold(gray_img, options))
+            }
+            ImageProcessingMethod::Sauvola => {
+                Threshold::PerPixel(Self::calculate_sauvola_threshold(gray_img))
+            }
+            ImageProcessingMethod::AdaptiveMean => {
+                let window = options
+                    .adaptive_window
+                    .unwrap_or_else(|| calculate_window_size(gray_img));
+                Threshold::PerPixel(Self::calculate_adaptive_mean_threshold(
+                    gray_img,
+                    window,
+                    options.adaptive_c,
+                ))
+            }
+            ImageProcessingMethod::Phansalkar => {
+                Threshold::PerPixel(Self::calculate_phansalkar_threshold(gray_img, options))
+            }
         };
+
+        match options.threshold_source {
+            ThresholdSource::Offset(offset) => threshold.offset(offset),
+            _ => threshold,
+        }
+    }
+
+    /// Applies a threshold in parallel, producing white (the subpixel maximum)
+    /// where a pixel is above its threshold and black (0) otherwise
+    fn binarize<T>(
+        gray_img: &ImageBuffer<Luma<T>, Vec<T>>,
+        threshold: &Threshold<T>,
+    ) -> Result<ImageBuffer<Luma<T>, Vec<T>>, Box<dyn std::error::Error>>
+    where
+        T: Primitive + Send + Sync,
+    {
         let width = gray_img.width();
         let height = gray_img.height();
-        let pixels: Vec<_> = gray_img.pixels().collect();
 
-        let binary_pixels: Vec<u16> = pixels
+        let binary_pixels: Vec<T> = gray_img
+            .as_raw()
             .par_iter()
-            .map(|pixel| if pixel[0] > threshold { 255 } else { 0 })
+            .enumerate()
+            .map(|(idx, &value)| {
+                let pixel_threshold = match threshold {
+                    Threshold::Global(t) => *t,
+                    Threshold::PerPixel(map) => map[idx],
+                };
+
+                if value > pixel_threshold {
+                    T::DEFAULT_MAX_VALUE
+                } else {
+                    T::DEFAULT_MIN_VALUE
+                }
+            })
             .collect();
 
         let binary_img = ImageBuffer::from_vec(width, height, binary_pixels)
@@ -65,6 +690,125 @@ impl ImageProcessor {
         Ok(binary_img)
     }
 
+    /// Share (`0.0` to `1.0`) of the pixels of a binarized image that are black
+    pub fn black_fraction<T>(binary_img: &ImageBuffer<Luma<T>, Vec<T>>) -> f64
+    where
+        T: Primitive + Send + Sync,
+    {
+        let pixels = binary_img.as_raw();
+        if pixels.is_empty() {
+            return 0.0;
+        }
+
+        let black = pixels
+            .par_iter()
+            .filter(|&&value| value == T::DEFAULT_MIN_VALUE)
+            .count();
+
+        black as f64 / pixels.len() as f64
+    }
+
+    /// Combines two binarizations of the same image pixel by pixel
+    ///
+    /// With [`CombineOp::Or`] a pixel is black if it is black in either image,
+    /// with [`CombineOp::And`] only if it is black in both.
+    ///
+    /// # Returns
+    /// - `Ok(ImageBuffer)`: The combined binary image
+    /// - `Err`: If the two images differ in size
+    pub fn combine_binary<T>(
+        a: &ImageBuffer<Luma<T>, Vec<T>>,
+        b: &ImageBuffer<Luma<T>, Vec<T>>,
+        op: CombineOp,
+    ) -> Result<ImageBuffer<Luma<T>, Vec<T>>, Box<dyn std::error::Error>>
+    where
+        T: Primitive,
+    {
+        if a.dimensions() != b.dimensions() {
+            return Err(format!(
+                "Cannot combine a {}x{} image with a {}x{} one",
+                a.width(),
+                a.height(),
+                b.width(),
+                b.height()
+            )
+            .into());
+        }
+
+        let black = T::DEFAULT_MIN_VALUE;
+        Ok(ImageBuffer::from_fn(a.width(), a.height(), |x, y| {
+            let (a_black, b_black) = (a.get_pixel(x, y)[0] == black, b.get_pixel(x, y)[0] == black);
+            let combined = match op {
+                CombineOp::Or => a_black || b_black,
+                CombineOp::And => a_black && b_black,
+            };
+
+            Luma([if combined {
+                black
+            } else {
+                T::DEFAULT_MAX_VALUE
+            }])
+        }))
+    }
+
+    /// Keeps only the black pixels of a binarized image that fall in the white
+    /// (masked-in) part of `mask`, turning every other pixel white
+    ///
+    /// `mask` must have the image's dimensions, see
+    /// [`ImageScaler::fit_mask`](crate::image_processing::ImageScaler::fit_mask).
+    /// Mask pixels of at least half brightness count as masked in.
+    pub fn apply_mask<T>(
+        binary_img: &ImageBuffer<Luma<T>, Vec<T>>,
+        mask: &GrayImage,
+    ) -> ImageBuffer<Luma<T>, Vec<T>>
+    where
+        T: Primitive,
+    {
+        ImageBuffer::from_fn(binary_img.width(), binary_img.height(), |x, y| {
+            if mask.get_pixel(x, y)[0] >= 128 {
+                *binary_img.get_pixel(x, y)
+            } else {
+                Luma([T::DEFAULT_MAX_VALUE])
+            }
+        })
+    }
+
+    /// How closely (`0.0` to `1.0`) a binarized image mirrors itself left to right
+    ///
+    /// The score is the share of black pixels whose mirror image across the
+    /// vertical center line is black too, so `1.0` means perfectly symmetric.
+    /// Images without black pixels score `0.0`.
+    pub fn detect_horizontal_symmetry<T>(binary_img: &ImageBuffer<Luma<T>, Vec<T>>) -> f64
+    where
+        T: Primitive + Send + Sync,
+    {
+        let (width, height) = binary_img.dimensions();
+
+        let (black, mirrored) = (0..height)
+            .into_par_iter()
+            .map(|y| {
+                let mut counts = (0usize, 0usize);
+                for x in 0..width {
+                    if binary_img.get_pixel(x, y)[0] != T::DEFAULT_MIN_VALUE {
+                        continue;
+                    }
+
+                    counts.0 += 1;
+                    if binary_img.get_pixel(width - 1 - x, y)[0] == T::DEFAULT_MIN_VALUE {
+                        counts.1 += 1;
+                    }
+                }
+                counts
+            })
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        if black == 0 {
+            return 0.0;
+        }
+
+        mirrored as f64 / black as f64
+    }
+
     /// Calculates optimal threshold for binarization using Otsu's method
     ///
     /// # Arguments
@@ -81,24 +825,43 @@ impl ImageProcessor {
     ///     - Compute between-class variance
     /// 3. Return threshold with maximum variance
     pub fn calculate_otsu_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u16 {
-        let mut histogram = [0u32; 65536];
+        let histogram = compute_histogram(img);
         let total_pixels = img.width() * img.height();
 
-        for pixel in img.pixels() {
-            histogram[pixel[0] as usize] += 1;
-        }
+        Self::otsu_from_histogram(&histogram, total_pixels) as u16
+    }
+
+    /// Finds the histogram bin that maximizes Otsu's between-class variance
+    ///
+    /// The class sums are accumulated bin by bin, so all 65536 candidate
+    /// thresholds of a 16-bit histogram take a single pass.
+    fn otsu_from_histogram(histogram: &[u32], total_pixels: u32) -> usize {
+        let total = total_pixels as f64;
+        let (all_count, all_sum) = histogram
+            .iter()
+            .enumerate()
+            .fold((0u64, 0u64), |(count, sum), (i, &bin)| {
+                (count + bin as u64, sum + i as u64 * bin as u64)
+            });
 
-        let mut best_threshold = 0u16;
+        let mut best_threshold = 0;
         let mut max_variance = 0.0;
+        let (mut count0, mut sum0) = (0u64, 0u64);
 
-        for t in 0..65536 {
-            let (w0, w1, mu0, mu1) = Self::calculate_class_statistics(&histogram, t, total_pixels);
+        for (t, &bin) in histogram.iter().enumerate() {
+            count0 += bin as u64;
+            sum0 += t as u64 * bin as u64;
+            let (count1, sum1) = (all_count - count0, all_sum - sum0);
 
+            let w0 = count0 as f64 / total;
+            let w1 = count1 as f64 / total;
             if w0 > 0.0 && w1 > 0.0 {
+                let mu0 = sum0 as f64 / count0 as f64;
+                let mu1 = sum1 as f64 / count1 as f64;
                 let between_class_variance = w0 * w1 * (mu0 - mu1).powi(2);
                 if between_class_variance > max_variance {
                     max_variance = between_class_variance;
-                    best_threshold = t as u16;
+                    best_threshold = t;
                 }
             }
         }
@@ -108,241 +871,532 @@ impl ImageProcessor {
 
     pub fn calculate_kapur_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u16 {
         let (width, height) = img.dimensions();
-        let mut histogram = [0u32; 65536];
+        let histogram = compute_histogram(img);
 
-        for pixel in img.pixels() {
-            histogram[pixel.0[0] as usize] += 1;
-        }
+        Self::kapur_from_histogram(&histogram, width * height) as u16
+    }
 
-        let total_pixels = (width * height) as f64;
+    /// Finds the histogram bin that maximizes Kapur's total class entropy
+    fn kapur_from_histogram(histogram: &[u32], total_pixels: u32) -> usize {
+        let total_pixels = total_pixels as f64;
         let mut max_entropy = f64::MIN;
-        let mut best_threshold = 0u16;
+        let mut best_threshold = 0;
+
+        // Empty bins add to neither class, and most of a 16-bit histogram is empty
+        let occupied: Vec<(usize, f64)> = histogram
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(i, &count)| (i, count as f64 / total_pixels))
+            .collect();
 
-        for threshold in 0..65536 {
+        for threshold in 0..histogram.len() {
             let mut w0 = 0.0;
             let mut w1 = 0.0;
             let mut sum0 = 0.0;
             let mut sum1 = 0.0;
 
-            for (i, &count) in histogram.iter().enumerate() {
-                let p = count as f64 / total_pixels;
-
-                if i <= threshold as usize {
+            for &(i, p) in &occupied {
+                if i <= threshold {
                     w0 += p;
-                    if p > 0.0 {
-                        sum0 += p * (p / w0).ln();
-                    }
+                    sum0 += p * (p / w0).ln();
                 } else {
                     w1 += p;
-                    if p > 0.0 {
-                        sum1 += p * (p / w1).ln();
-                    }
+                    sum1 += p * (p / w1).ln();
                 }
             }
 
             let entropy = -sum0 - sum1;
             if entropy > max_entropy {
                 max_entropy = entropy;
-                best_threshold = threshold as u16;
+                best_threshold = threshold;
             }
         }
 
         best_threshold
     }
 
-    pub fn calculate_sauvola_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u16 {
+    /// Calculates a per-pixel threshold from the mean of each pixel's neighborhood
+    ///
+    /// # Arguments
+    /// * `img` - 16-bit grayscale image reference
+    /// * `window` - Neighborhood radius; the window is clamped at the image borders
+    /// * `c` - Constant subtracted from the neighborhood mean
+    ///
+    /// # Returns
+    /// Row-major threshold map with one entry per pixel
+    pub fn calculate_adaptive_mean_threshold(
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        window: u32,
+        c: u16,
+    ) -> Vec<u16> {
         let (width, height) = img.dimensions();
-        let mut integral = vec![0u64; (width * height) as usize];
-        let mut integral_sq = vec![0u64; (width * height) as usize];
-
-        for y in 0..height {
-            for x in 0..width {
-                let idx = (y * width + x) as usize;
-                let pixel = img.get_pixel(x, y)[0] as u64;
-                integral[idx] = pixel
-                    + if x > 0 { integral[idx - 1] } else { 0 }
-                    + if y > 0 {
-                        integral[idx - width as usize]
-                    } else {
-                        0
-                    }
-                    - if x > 0 && y > 0 {
-                        integral[idx - width as usize - 1]
-                    } else {
-                        0
-                    };
-                integral_sq[idx] = pixel * pixel
-                    + if x > 0 { integral_sq[idx - 1] } else { 0 }
-                    + if y > 0 {
-                        integral_sq[idx - width as usize]
-                    } else {
-                        0
-                    }
-                    - if x > 0 && y > 0 {
-                        integral_sq[idx - width as usize - 1]
-                    } else {
-                        0
-                    };
-            }
-        }
+        let (integral, _) = compute_integral_images(img);
+
+        (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let x = idx % width;
+                let y = idx / width;
+                let x0 = x.saturating_sub(window);
+                let y0 = y.saturating_sub(window);
+                let x1 = (x + window).min(width - 1);
+                let y1 = (y + window).min(height - 1);
+
+                let area = ((x1 - x0 + 1) * (y1 - y0 + 1)) as u64;
+                let mean = integral_sum(&integral, width, x0, y0, x1, y1) / area;
+
+                (mean as u16).saturating_sub(c)
+            })
+            .collect()
+    }
 
-        let mut threshold_values = Vec::new();
-        let window_size = calculate_window_size(img);
+    /// Calculates a per-pixel threshold using Phansalkar's variant of Sauvola's method
+    ///
+    /// # Arguments
+    /// * `img` - 16-bit grayscale image reference
+    /// * `options` - Supplies the `p`, `q`, `k` and `R` parameters
+    ///
+    /// # Returns
+    /// Row-major threshold map with one entry per pixel
+    ///
+    /// # Process
+    /// With the local mean `m` and standard deviation `s` normalized to 0-1, each
+    /// pixel's threshold is `m * (1 + p * exp(-q * m) + k * (s / R - 1))`. The
+    /// exponential term raises the threshold in dark regions, which keeps faded
+    /// strokes on stained or low-contrast documents. The recommended defaults are
+    /// `p = 2`, `q = 10`, `k = 0.25` and `R = 0.5`.
+    pub fn calculate_phansalkar_threshold(
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        options: &ProcessingOptions,
+    ) -> Vec<u16> {
+        let (width, height) = img.dimensions();
+        let (integral, integral_sq) = compute_integral_images(img);
+        let window = calculate_window_size(img);
+        let max_value = u16::MAX as f64;
+
+        (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let x = idx % width;
+                let y = idx / width;
+                let x0 = x.saturating_sub(window);
+                let y0 = y.saturating_sub(window);
+                let x1 = (x + window).min(width - 1);
+                let y1 = (y + window).min(height - 1);
+
+                let area = ((x1 - x0 + 1) * (y1 - y0 + 1)) as f64;
+                let sum = integral_sum(&integral, width, x0, y0, x1, y1) as f64;
+                let sum_sq = integral_sum(&integral_sq, width, x0, y0, x1, y1) as f64;
+
+                let mean = sum / area;
+                let std_dev = ((sum_sq / area) - mean * mean).max(0.0).sqrt();
+                let mean = mean / max_value;
+                let std_dev = std_dev / max_value;
+
+                let threshold = mean
+                    * (1.0
+                        + options.phansalkar_p * (-options.phansalkar_q * mean).exp()
+                        + options.phansalkar_k * (std_dev / options.phansalkar_r - 1.0));
+
+                (threshold * max_value).clamp(0.0, max_value) as u16
+            })
+            .collect()
+    }
+
+    /// Computes a per-pixel Sauvola threshold map
+    ///
+    /// Each pixel is compared against `m * (1 + k * (s / R - 1))`, where `m`
+    /// and `s` are the mean and standard deviation of the window around it,
+    /// `k = 0.5` and `R` is the dynamic range of the standard deviation (128 in
+    /// 8-bit units, scaled to 16 bits).
+    pub fn calculate_sauvola_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> Vec<u16> {
+        let width = img.width();
+        let (integral, integral_sq) = compute_integral_images(img);
         let k = 0.5;
-        let r = 128.0;
-
-        for y in window_size..height - window_size {
-            for x in window_size..width - window_size {
-                let x0 = x - window_size;
-                let y0 = y - window_size;
-                let x1 = x + window_size;
-                let y1 = y + window_size;
-
-                let area = ((x1 - x0) * (y1 - y0)) as f64;
-                let sum = integral[(y1 * width + x1) as usize]
-                    - integral[(y0 * width + x1) as usize]
-                    - integral[(y1 * width + x0) as usize]
-                    + integral[(y0 * width + x0) as usize];
-                let sum_sq = integral_sq[(y1 * width + x1) as usize]
-                    - integral_sq[(y0 * width + x1) as usize]
-                    - integral_sq[(y1 * width + x0) as usize]
-                    + integral_sq[(y0 * width + x0) as usize];
-
-                let mean = sum as f64 / area;
-                let variance = (sum_sq as f64 / area) - (mean * mean);
-                let std_dev = variance.sqrt();
-
-                let threshold = mean * (1.0 + k * (std_dev / r - 1.0));
-                threshold_values.push(threshold as u16);
+        let r = 128.0 * 257.0;
+
+        Self::local_threshold_map(img, calculate_window_size(img), |x0, y0, x1, y1| {
+            let (mean, std_dev) = window_mean_std(&integral, &integral_sq, width, x0, y0, x1, y1);
+
+            (mean * (1.0 + k * (std_dev / r - 1.0))).clamp(0.0, u16::MAX as f64) as u16
+        })
+    }
+
+    /// Computes a per-pixel Bernsen threshold map: the midpoint between the
+    /// darkest and brightest pixel of the window around each pixel
+    ///
+    /// Windows whose contrast is below `options.bernsen_contrast` hold no edge
+    /// to split, so Bernsen's rule classifies the pixel as a whole: white if
+    /// the midpoint is in the bright half of the range, black otherwise.
+    pub fn calculate_bernsen_threshold(
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        options: &ProcessingOptions,
+    ) -> Vec<u16> {
+        let width = img.width();
+        let half = u16::MAX / 2;
+        let window_size = fit_window(img, options.bernsen_window);
+        let (minima, maxima) = window_extremes(img, window_size);
+
+        Self::local_threshold_map(img, window_size, |x0, y0, x1, y1| {
+            let idx = ((y0 + y1) / 2 * width + (x0 + x1) / 2) as usize;
+            let (min_val, max_val) = (minima[idx], maxima[idx]);
+
+            let midpoint = ((min_val as u32 + max_val as u32) / 2) as u16;
+            if max_val - min_val >= options.bernsen_contrast {
+                midpoint
+            } else if midpoint > half {
+                // Pixels are white above the threshold, so this whitens the window
+                0
+            } else {
+                u16::MAX
             }
-        }
+        })
+    }
+
+    /// Computes a per-pixel Wolf-Jolion threshold map
+    ///
+    /// Each pixel is compared against `m - k * (1 - s / R) * (m - M)`, where
+    /// `m` and `s` are the mean and standard deviation of the window around
+    /// it, `M` is the darkest pixel of the image, `R` the largest standard
+    /// deviation of any window and `k = 0.5`. Unlike Sauvola's method, the
+    /// threshold adapts to the image's own contrast.
+    pub fn calculate_wolf_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> Vec<u16> {
+        let (width, height) = img.dimensions();
+        let (integral, integral_sq) = compute_integral_images(img);
+        let window_size = fit_window(img, calculate_window_size(img));
+        let k = 0.5;
+
+        let min_gray = img.pixels().map(|pixel| pixel[0]).min().unwrap_or(0) as f64;
+        let max_std_dev = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                let x0 = x.saturating_sub(window_size);
+                let y0 = y.saturating_sub(window_size);
+                let x1 = (x + window_size).min(width - 1);
+                let y1 = (y + window_size).min(height - 1);
 
-        threshold_values.sort();
-        threshold_values[threshold_values.len() / 2]
+                window_mean_std(&integral, &integral_sq, width, x0, y0, x1, y1).1
+            })
+            .reduce(|| 0.0, f64::max)
+            .max(1.0);
+
+        Self::local_threshold_map(img, window_size, |x0, y0, x1, y1| {
+            let (mean, std_dev) = window_mean_std(&integral, &integral_sq, width, x0, y0, x1, y1);
+
+            (mean - k * (1.0 - std_dev / max_std_dev) * (mean - min_gray))
+                .clamp(0.0, u16::MAX as f64) as u16
+        })
     }
 
-    pub fn calculate_bernsen_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u16 {
+    /// Builds a per-pixel threshold map from the window of radius `window_size`
+    /// around each pixel
+    ///
+    /// `threshold_at` gets the inclusive `(x0, y0, x1, y1)` bounds of a window.
+    /// The radius is shrunk to fit small images (see [`fit_window`]), and
+    /// pixels too close to the border for a full window get the median of the
+    /// other thresholds as a global fallback.
+    fn local_threshold_map<F>(
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        window_size: u32,
+        threshold_at: F,
+    ) -> Vec<u16>
+    where
+        F: Fn(u32, u32, u32, u32) -> u16 + Sync,
+    {
         let (width, height) = img.dimensions();
-        let window_size = 15;
-        let mut threshold_values = Vec::new();
-
-        for y in window_size..height - window_size {
-            for x in window_size..width - window_size {
-                let mut min_val = u16::MAX;
-                let mut max_val = u16::MIN;
-
-                for dy in -(window_size as i32)..=(window_size as i32) {
-                    for dx in -(window_size as i32)..=(window_size as i32) {
-                        let nx = x as i32 + dx;
-                        let ny = y as i32 + dy;
-                        if nx >= 0 && ny >= 0 && nx < width as i32 && ny < height as i32 {
-                            let pixel = img.get_pixel(nx as u32, ny as u32)[0];
-                            min_val = min_val.min(pixel);
-                            max_val = max_val.max(pixel);
-                        }
-                    }
-                }
+        let window_size = fit_window(img, window_size);
 
-                let threshold = ((min_val as f64 + max_val as f64) / 2.0) as u16;
-                threshold_values.push(threshold);
-            }
+        let thresholds: Vec<Option<u16>> = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                let interior = window_size > 0
+                    && x >= window_size
+                    && y >= window_size
+                    && x + window_size < width
+                    && y + window_size < height;
+
+                interior.then(|| {
+                    threshold_at(
+                        x - window_size,
+                        y - window_size,
+                        x + window_size,
+                        y + window_size,
+                    )
+                })
+            })
+            .collect();
+
+        let fallback = Self::median_threshold(thresholds.iter().flatten().copied().collect(), img);
+
+        thresholds
+            .into_iter()
+            .map(|threshold| threshold.unwrap_or(fallback))
+            .collect()
+    }
+
+    /// Median of a set of local thresholds, or Otsu's threshold of the whole
+    /// image if no local threshold could be computed
+    fn median_threshold(
+        mut threshold_values: Vec<u16>,
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    ) -> u16 {
+        if threshold_values.is_empty() {
+            return Self::calculate_otsu_threshold(img);
         }
 
-        threshold_values.sort();
-        threshold_values[threshold_values.len() / 2]
+        let middle = threshold_values.len() / 2;
+        *threshold_values.select_nth_unstable(middle).1
     }
+}
 
-    pub fn calculate_wolf_threshold(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> u16 {
-        let (width, height) = img.dimensions();
-        let mut min_img = ImageBuffer::new(width, height);
-        let mut max_img = ImageBuffer::new(width, height);
-        let window_size = calculate_window_size(img) as i32;
-        let k = 0.5;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for y in 0..height {
-            for x in 0..width {
-                let mut min_val = u16::MAX;
-                let mut max_val = u16::MIN;
-                for dy in -window_size..=window_size {
-                    for dx in -window_size..=window_size {
-                        let nx = x as i32 + dx;
-                        let ny = y as i32 + dy;
-                        if nx >= 0 && ny >= 0 && nx < width as i32 && ny < height as i32 {
-                            let pixel = img.get_pixel(nx as u32, ny as u32)[0];
-                            min_val = min_val.min(pixel);
-                            max_val = max_val.max(pixel);
-                        }
-                    }
-                }
-                min_img.put_pixel(x, y, Luma([min_val]));
-                max_img.put_pixel(x, y, Luma([max_val]));
-            }
+    #[test]
+    fn or_and_combine_known_masks() {
+        // Black on the left half and on the top half respectively
+        let left = GrayImage::from_fn(4, 4, |x, _| Luma([if x < 2 { 0 } else { 255 }]));
+        let top = GrayImage::from_fn(4, 4, |_, y| Luma([if y < 2 { 0 } else { 255 }]));
+
+        let either = ImageProcessor::combine_binary(&left, &top, CombineOp::Or).unwrap();
+        let both = ImageProcessor::combine_binary(&left, &top, CombineOp::And).unwrap();
+
+        assert_eq!(ImageProcessor::black_fraction(&either), 0.75);
+        assert_eq!(ImageProcessor::black_fraction(&both), 0.25);
+        assert_eq!(both.get_pixel(0, 0)[0], 0);
+        assert_eq!(both.get_pixel(3, 0)[0], 255);
+    }
+
+    #[test]
+    fn second_method_uses_its_own_threshold() {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_fn(256, 4, |x, _| Luma([x as u8])));
+        let manual = |threshold| ProcessingOptions {
+            threshold_source: ThresholdSource::Manual(threshold),
+            ..ProcessingOptions::default()
+        };
+        let options = ProcessingOptions {
+            combine: Some((
+                ImageProcessingMethod::Otsu,
+                CombineOp::Or,
+                Box::new(manual(49152)),
+            )),
+            ..manual(16384)
+        };
+
+        let combined =
+            ImageProcessor::process_dynamic_image(&img, ImageProcessingMethod::Otsu, &options)
+                .unwrap();
+
+        // The second, higher threshold decides, not the first one reused
+        let fraction = ImageProcessor::black_fraction(&combined);
+        assert!((fraction - 0.75).abs() < 0.02, "{}", fraction);
+    }
+
+    #[test]
+    fn masked_out_areas_turn_white() {
+        let tone: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::from_pixel(8, 4, Luma([1000]));
+        let mask = GrayImage::from_fn(8, 4, |x, _| Luma([if x < 4 { 255 } else { 0 }]));
+
+        let masked = ImageProcessor::apply_mask(&tone, &mask);
+
+        assert!((0..4).all(|x| masked.get_pixel(x, 2)[0] == 1000));
+        assert!((4..8).all(|x| masked.get_pixel(x, 2)[0] == u16::MAX));
+    }
+
+    #[test]
+    fn adaptive_mean_tracks_a_gradient() {
+        let img = ImageBuffer::from_fn(100, 10, |x, _| Luma([x as u16 * 600]));
+        let thresholds = ImageProcessor::calculate_adaptive_mean_threshold(&img, 3, 0);
+
+        for x in 3..97 {
+            let threshold = thresholds[(5 * 100 + x) as usize] as i32;
+            assert!((threshold - x * 600).abs() <= 1, "x = {x}");
         }
+        assert!(thresholds[5 * 100 + 10] < thresholds[5 * 100 + 90]);
+    }
+
+    #[test]
+    fn adaptive_mean_subtracts_c() {
+        let img = ImageBuffer::from_pixel(20, 20, Luma([30000u16]));
+        let thresholds = ImageProcessor::calculate_adaptive_mean_threshold(&img, 4, 2560);
 
-        let mut contrast_sum = 0.0;
-        let mut weight_sum = 0.0;
+        assert!(thresholds.iter().all(|&t| t == 30000 - 2560));
+    }
+
+    /// Faded stroke (20% gray) on a stained page (25% gray), with a mask of
+    /// which pixels belong to the stroke
+    fn low_contrast_fixture() -> (ImageBuffer<Luma<u16>, Vec<u16>>, Vec<bool>) {
+        let is_stroke = |x: u32| (29..31).contains(&x);
+        let img = ImageBuffer::from_fn(60, 60, |x, _| {
+            Luma([if is_stroke(x) { 13107u16 } else { 16384 }])
+        });
+        let strokes = (0..60 * 60).map(|idx| is_stroke(idx % 60)).collect();
+
+        (img, strokes)
+    }
+
+    /// Share of pixels a threshold map classifies like the fixture's mask
+    fn accuracy(
+        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+        thresholds: &[u16],
+        strokes: &[bool],
+    ) -> f64 {
+        let correct = img
+            .as_raw()
+            .iter()
+            .zip(thresholds)
+            .zip(strokes)
+            .filter(|((&value, &threshold), &stroke)| (value <= threshold) == stroke)
+            .count();
+
+        correct as f64 / strokes.len() as f64
+    }
+
+    #[test]
+    fn phansalkar_separates_low_contrast_better_than_sauvola() {
+        let (img, strokes) = low_contrast_fixture();
+        let phansalkar =
+            ImageProcessor::calculate_phansalkar_threshold(&img, &ProcessingOptions::default());
+        let sauvola = ImageProcessor::calculate_sauvola_threshold(&img);
+
+        assert_ne!(phansalkar, sauvola);
+
+        let phansalkar_accuracy = accuracy(&img, &phansalkar, &strokes);
+        assert_eq!(phansalkar_accuracy, 1.0);
+        assert!(phansalkar_accuracy > accuracy(&img, &sauvola, &strokes));
+    }
 
-        for pixel in img.pixels() {
-            let min_pixel = min_img.get_pixel(pixel[0] as u32, pixel[1] as u32)[0] as f64;
-            let max_pixel = max_img.get_pixel(pixel[0] as u32, pixel[1] as u32)[0] as f64;
-            let contrast = max_pixel - min_pixel;
-            contrast_sum += contrast * (pixel[0] as f64);
-            weight_sum += contrast;
+    #[test]
+    fn downscaled_otsu_threshold_stays_close() {
+        let img = ImageBuffer::from_fn(400, 300, |x, y| {
+            let shade = 0.5 + 0.4 * (x as f64 / 40.0).sin() * (y as f64 / 30.0).cos();
+            Luma([(shade * u16::MAX as f64) as u16])
+        });
+        let downscaled = downscale_for_analysis(&img, 200).unwrap();
+        assert_eq!(downscaled.dimensions(), (200, 150));
+
+        let otsu = |img: &ImageBuffer<Luma<u16>, Vec<u16>>| {
+            let narrowed = narrow_to_8bit(img);
+            let total_pixels = narrowed.width() * narrowed.height();
+            ImageProcessor::otsu_from_histogram(&compute_histogram(&narrowed), total_pixels) as i32
+        };
+
+        assert!((otsu(&img) - otsu(&downscaled)).abs() <= 3);
+    }
+
+    #[test]
+    fn every_method_handles_an_image_smaller_than_its_window() {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_fn(5, 5, |x, y| {
+            Luma([if (x + y) % 2 == 0 { 40 } else { 220 }])
+        }));
+        let options = ProcessingOptions {
+            bernsen_window: 15,
+            adaptive_window: Some(15),
+            ..ProcessingOptions::default()
+        };
+
+        for method in ImageProcessingMethod::ALL {
+            let binary = ImageProcessor::process_dynamic_image(&img, method, &options)
+                .unwrap_or_else(|err| panic!("{method:?}: {err}"));
+            assert_eq!(binary.dimensions(), (5, 5), "{method:?}");
         }
+    }
 
-        if weight_sum > 0.0 {
-            (contrast_sum / weight_sum * k) as u16
-        } else {
-            32768
+    #[test]
+    fn sauvola_adapts_to_a_shadowed_half() {
+        // A dark stroke on a bright left half and on a shadowed right half
+        let img = ImageBuffer::from_fn(120, 60, |x, _| {
+            Luma([match x {
+                30 | 31 => 10000u16,
+                90 | 91 => 4000,
+                x if x < 60 => 50000,
+                _ => 20000,
+            }])
+        });
+        let thresholds = ImageProcessor::calculate_sauvola_threshold(&img);
+        let binary =
+            ImageProcessor::binarize(&img, &Threshold::PerPixel(thresholds.clone())).unwrap();
+
+        assert_ne!(thresholds[30 * 120 + 15], thresholds[30 * 120 + 75]);
+        for x in (10..50).chain(70..110) {
+            let is_stroke = matches!(x, 30 | 31 | 90 | 91);
+            for y in 10..50 {
+                assert_eq!(binary.get_pixel(x, y)[0] == 0, is_stroke, "({x}, {y})");
+            }
         }
+
+        // A single global threshold blackens the whole shadowed half
+        assert!(ImageProcessor::calculate_otsu_threshold(&img) >= 20000);
     }
 
-    /// Calculates class statistics for given threshold
-    ///
-    /// # Arguments
-    /// * `histogram` - Pixel intensity distribution
-    /// * `threshold` - Current threshold to evaluate
-    /// * `total` - Total number of pixels in image
-    ///
-    /// # Returns
-    /// Tuple containing:
-    /// - `w0`: Background weight (fraction of pixels below threshold)
-    /// - `w1`: Foreground weight (fraction of pixels above threshold)
-    /// - `mu0`: Background mean intensity
-    /// - `mu1`: Foreground mean intensity
-    fn calculate_class_statistics(
-        histogram: &[u32; 65536],
-        threshold: usize,
-        total: u32,
-    ) -> (f64, f64, f64, f64) {
-        let mut sum0 = 0u32;
-        let mut sum1 = 0u32;
-        let mut count0 = 0u32;
-        let mut count1 = 0u32;
-
-        for (i, &count) in histogram.iter().enumerate() {
-            if i <= threshold {
-                sum0 += i as u32 * count;
-                count0 += count;
-            } else {
-                sum1 += i as u32 * count;
-                count1 += count;
+    #[test]
+    fn window_extremes_match_a_brute_force_scan() {
+        let img = ImageBuffer::from_fn(23, 17, |x, y| {
+            Luma([((x * 7919 + y * 104729) % 65536) as u16])
+        });
+
+        for radius in [0, 1, 3, 20] {
+            let (minima, maxima) = window_extremes(&img, radius);
+
+            for (x, y, _) in img.enumerate_pixels() {
+                let window: Vec<u16> = img
+                    .enumerate_pixels()
+                    .filter(|(wx, wy, _)| wx.abs_diff(x) <= radius && wy.abs_diff(y) <= radius)
+                    .map(|(_, _, pixel)| pixel[0])
+                    .collect();
+                let idx = (y * 23 + x) as usize;
+
+                assert_eq!(minima[idx], *window.iter().min().unwrap(), "r = {radius}");
+                assert_eq!(maxima[idx], *window.iter().max().unwrap(), "r = {radius}");
             }
         }
+    }
 
-        let w0 = count0 as f64 / total as f64;
-        let w1 = count1 as f64 / total as f64;
-        let mu0 = if count0 > 0 {
-            sum0 as f64 / count0 as f64
-        } else {
-            0.0
-        };
-        let mu1 = if count1 > 0 {
-            sum1 as f64 / count1 as f64
-        } else {
-            0.0
+    #[test]
+    fn bernsen_treats_low_contrast_patches_as_a_whole() {
+        // Faint texture on a light left half and on a dark right half
+        let img = ImageBuffer::from_fn(40, 20, |x, y| {
+            let base = if x < 20 { 50000u16 } else { 10000 };
+            Luma([base + ((x + y) % 2) as u16 * 1000])
+        });
+        let options = ProcessingOptions {
+            bernsen_window: 3,
+            ..ProcessingOptions::default()
         };
+        let thresholds = ImageProcessor::calculate_bernsen_threshold(&img, &options);
+
+        for y in 3..17 {
+            assert!((3..16).all(|x| thresholds[y * 40 + x] == 0));
+            assert!((24..37).all(|x| thresholds[y * 40 + x] == u16::MAX));
+        }
+
+        // The edge between the halves has enough contrast to be split
+        assert_eq!(thresholds[10 * 40 + 19], 30500);
+    }
+
+    #[test]
+    fn thin_images_fall_back_to_otsu() {
+        // Too thin for any window, so no local threshold can be computed
+        let img = ImageBuffer::from_fn(1, 40, |_, y| Luma([if y < 20 { 8000u16 } else { 56000 }]));
+        let otsu = ImageProcessor::calculate_otsu_threshold(&img);
+
+        let sauvola = ImageProcessor::calculate_sauvola_threshold(&img);
+        let bernsen =
+            ImageProcessor::calculate_bernsen_threshold(&img, &ProcessingOptions::default());
+
+        assert_eq!(sauvola.len(), 40);
+        assert!(sauvola.iter().chain(&bernsen).all(|&t| t == otsu));
+    }
+
+    #[test]
+    fn small_images_are_not_downscaled() {
+        let img = ImageBuffer::from_pixel(100, 50, Luma([0u16]));
 
-        (w0, w1, mu0, mu1)
+        assert!(downscale_for_analysis(&img, 100).is_none());
+        assert!(downscale_for_analysis(&img, 0).is_none());
     }
 }
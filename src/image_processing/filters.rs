@@ -0,0 +1,284 @@
+use crate::image_processing::compute_histogram;
+use image::{ImageBuffer, Luma};
+use rayon::prelude::*;
+
+/// Spreads the intensities of a grayscale image over the full 16-bit range by
+/// histogram equalization
+///
+/// Each pixel is mapped through the image's cumulative distribution, so equally
+/// sized intensity ranges end up holding roughly equal numbers of pixels. The
+/// darkest pixels become 0 and the brightest 65535. Single-intensity images are
+/// left unchanged.
+pub fn equalize_histogram(img: &mut ImageBuffer<Luma<u16>, Vec<u16>>) {
+    let histogram = compute_histogram(img);
+    let total = img.width() as u64 * img.height() as u64;
+
+    let mut cdf = Vec::with_capacity(histogram.len());
+    let mut running = 0u64;
+    for &count in &histogram {
+        running += count as u64;
+        cdf.push(running);
+    }
+
+    let cdf_min = cdf.iter().copied().find(|&count| count > 0).unwrap_or(0);
+    if total <= cdf_min {
+        return;
+    }
+
+    let range = (total - cdf_min) as f64;
+    let lookup: Vec<u16> = cdf
+        .iter()
+        .map(|&count| {
+            (count.saturating_sub(cdf_min) as f64 / range * u16::MAX as f64).round() as u16
+        })
+        .collect();
+
+    for pixel in img.pixels_mut() {
+        pixel[0] = lookup[pixel[0] as usize];
+    }
+}
+
+/// Number of intensity bins of each tile's histogram in [`clahe`]
+const CLAHE_BINS: usize = 1024;
+
+/// Contrast-limited adaptive histogram equalization (CLAHE)
+///
+/// The image is split into a `(columns, rows)` grid of tiles, and each tile
+/// gets its own equalization curve from its histogram. Bins holding more than
+/// `clip_limit` times the average bin count are clipped and the excess spread
+/// over every bin, which caps how much flat, noisy areas get amplified (a
+/// limit of 1 leaves the image almost unchanged, 2-4 is typical). Every pixel
+/// blends the curves of the four nearest tile centers bilinearly, so no seams
+/// show at tile boundaries.
+pub fn clahe(
+    img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    tiles: (u32, u32),
+    clip_limit: f64,
+) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return img.clone();
+    }
+
+    let columns = tiles.0.clamp(1, width);
+    let rows = tiles.1.clamp(1, height);
+    let bin_shift = 16 - CLAHE_BINS.trailing_zeros();
+
+    let curves: Vec<Vec<u16>> = (0..rows * columns)
+        .into_par_iter()
+        .map(|tile| {
+            let (column, row) = (tile % columns, tile / columns);
+            let (x0, x1) = (column * width / columns, (column + 1) * width / columns);
+            let (y0, y1) = (row * height / rows, (row + 1) * height / rows);
+
+            let mut histogram = vec![0u32; CLAHE_BINS];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    histogram[(img.get_pixel(x, y)[0] >> bin_shift) as usize] += 1;
+                }
+            }
+
+            let pixels = ((x1 - x0) * (y1 - y0)) as f64;
+            let limit = ((clip_limit * pixels / CLAHE_BINS as f64).ceil() as u32).max(1);
+            clip_histogram(&mut histogram, limit);
+
+            let total = histogram
+                .iter()
+                .map(|&count| count as u64)
+                .sum::<u64>()
+                .max(1);
+            let mut running = 0u64;
+            histogram
+                .iter()
+                .map(|&count| {
+                    running += count as u64;
+                    (running as f64 / total as f64 * u16::MAX as f64).round() as u16
+                })
+                .collect()
+        })
+        .collect();
+
+    // Fractional tile coordinates, 0 at the center of the first tile
+    let tile_position = |pixel: u32, size: u32, count: u32| {
+        let position = (pixel as f64 + 0.5) * count as f64 / size as f64 - 0.5;
+        let first = (position.floor().max(0.0) as u32).min(count - 1);
+        let second = (first + 1).min(count - 1);
+        (first, second, (position - first as f64).clamp(0.0, 1.0))
+    };
+
+    let mut output: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::new(width, height);
+    output
+        .par_chunks_mut(width as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let (top, bottom, wy) = tile_position(y as u32, height, rows);
+
+            for (x, value) in row.iter_mut().enumerate() {
+                let (left, right, wx) = tile_position(x as u32, width, columns);
+                let bin = (img.get_pixel(x as u32, y as u32)[0] >> bin_shift) as usize;
+                let curve =
+                    |column: u32, row: u32| curves[(row * columns + column) as usize][bin] as f64;
+
+                let upper = curve(left, top) * (1.0 - wx) + curve(right, top) * wx;
+                let lower = curve(left, bottom) * (1.0 - wx) + curve(right, bottom) * wx;
+                *value = (upper * (1.0 - wy) + lower * wy).round() as u16;
+            }
+        });
+
+    output
+}
+
+/// Caps every bin at `limit`, spreading the clipped counts evenly over all bins
+fn clip_histogram(histogram: &mut [u32], limit: u32) {
+    let mut excess = 0u64;
+    for count in histogram.iter_mut() {
+        if *count > limit {
+            excess += (*count - limit) as u64;
+            *count = limit;
+        }
+    }
+
+    let bins = histogram.len() as u64;
+    let (share, remainder) = (excess / bins, excess % bins);
+    for (index, count) in histogram.iter_mut().enumerate() {
+        *count += share as u32 + ((index as u64) < remainder) as u32;
+    }
+}
+
+/// Sharpens edges by unsharp masking: `img + amount * (img - blur(img, sigma))`
+///
+/// `sigma` is the standard deviation (in pixels) of the Gaussian blur, which
+/// sets how wide the emphasized edges are, and `amount` how much they are
+/// steepened. Results are clamped to the 16-bit range.
+pub fn unsharp(
+    img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    sigma: f64,
+    amount: f64,
+) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    let blurred = image::imageops::blur(img, sigma as f32);
+
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        let value = img.get_pixel(x, y)[0] as f64;
+        let detail = value - blurred.get_pixel(x, y)[0] as f64;
+
+        Luma([(value + amount * detail)
+            .round()
+            .clamp(0.0, u16::MAX as f64) as u16])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Horizontal gradient squeezed into the middle fifth of the 16-bit range
+    fn low_contrast_gradient() -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(256, 8, |x, _| Luma([26000 + x as u16 * 50]))
+    }
+
+    fn intensity_range(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> (u16, u16) {
+        img.pixels().fold((u16::MAX, 0), |(min, max), pixel| {
+            (min.min(pixel[0]), max.max(pixel[0]))
+        })
+    }
+
+    #[test]
+    fn equalizing_spreads_a_gradient_to_full_scale() {
+        let mut img = low_contrast_gradient();
+        equalize_histogram(&mut img);
+
+        assert_eq!(intensity_range(&img), (0, u16::MAX));
+
+        // Evenly filled bins map to evenly spaced intensities
+        let middle = img.get_pixel(128, 0)[0] as f64 / u16::MAX as f64;
+        assert!((middle - 0.5).abs() < 0.02, "{}", middle);
+    }
+
+    /// Faint checkerboard texture on a dark left and a light right half
+    fn faint_texture() -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(32, 32, |x, y| {
+            let base = if x < 16 { 20000 } else { 45000 };
+            Luma([if (x + y) % 2 == 0 {
+                base + 1000
+            } else {
+                base - 1000
+            }])
+        })
+    }
+
+    /// Average difference between horizontal neighbors within each half
+    fn local_contrast(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> f64 {
+        let differences: Vec<f64> = (0..img.height())
+            .flat_map(|y| {
+                (0..img.width() - 1).filter(|&x| x != 15).map(move |x| {
+                    (img.get_pixel(x, y)[0] as f64 - img.get_pixel(x + 1, y)[0] as f64).abs()
+                })
+            })
+            .collect();
+
+        differences.iter().sum::<f64>() / differences.len() as f64
+    }
+
+    #[test]
+    fn clahe_increases_local_contrast() {
+        let img = faint_texture();
+        let enhanced = clahe(&img, (2, 1), 40.0);
+
+        assert!(local_contrast(&enhanced) > 1.5 * local_contrast(&img));
+        assert!(enhanced.get_pixel(4, 4)[0] < enhanced.get_pixel(28, 4)[0]);
+    }
+
+    #[test]
+    fn clahe_keeps_the_order_of_intensities() {
+        let img = faint_texture();
+        let enhanced = clahe(&img, (2, 1), 4.0);
+
+        // Columns that only use the curve of their own tile
+        let columns = (0..8).chain(24..32);
+        for (x, y) in columns.flat_map(|x| (0..31).map(move |y| (x, y))) {
+            let before = (img.get_pixel(x, y)[0], img.get_pixel(x, y + 1)[0]);
+            let after = (enhanced.get_pixel(x, y)[0], enhanced.get_pixel(x, y + 1)[0]);
+
+            assert_eq!(before.0 < before.1, after.0 < after.1);
+        }
+    }
+
+    /// Dark to light edge softened into a ramp between x = 24 and x = 40
+    fn soft_edge() -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(64, 8, |x, _| {
+            let t = (x as f64 - 24.0).clamp(0.0, 16.0) / 16.0;
+            Luma([(20000.0 + t * 25000.0) as u16])
+        })
+    }
+
+    #[test]
+    fn unsharp_steepens_a_soft_edge() {
+        let img = soft_edge();
+        let sharpened = unsharp(&img, 3.0, 2.0);
+        let rise = |img: &ImageBuffer<Luma<u16>, Vec<u16>>, from: u32, to: u32| {
+            img.get_pixel(to, 4)[0] as f64 - img.get_pixel(from, 4)[0] as f64
+        };
+
+        // The shoulders of the ramp overshoot, so it rises more over the same span
+        assert!(rise(&sharpened, 24, 40) > 1.15 * rise(&img, 24, 40));
+        assert!(sharpened.get_pixel(24, 4)[0] < img.get_pixel(24, 4)[0]);
+        assert!(sharpened.get_pixel(40, 4)[0] > img.get_pixel(40, 4)[0]);
+    }
+
+    #[test]
+    fn unsharp_clamps_to_the_16_bit_range() {
+        let img = ImageBuffer::from_fn(16, 4, |x, _| Luma([if x < 8 { 0 } else { u16::MAX }]));
+        let sharpened = unsharp(&img, 2.0, 5.0);
+
+        assert_eq!(sharpened.get_pixel(7, 2)[0], 0);
+        assert_eq!(sharpened.get_pixel(8, 2)[0], u16::MAX);
+    }
+
+    #[test]
+    fn equalizing_keeps_a_uniform_image() {
+        let mut img = ImageBuffer::from_pixel(8, 8, Luma([1234u16]));
+        equalize_histogram(&mut img);
+
+        assert!(img.pixels().all(|pixel| pixel[0] == 1234));
+    }
+}
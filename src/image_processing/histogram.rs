@@ -0,0 +1,76 @@
+use image::{ImageBuffer, Luma, Primitive};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Counts the pixels of each intensity in a grayscale image
+///
+/// The histogram has one bin per representable value, so 256 bins for 8-bit
+/// images and 65536 for 16-bit ones, and its counts sum to the pixel count.
+pub fn compute_histogram<T>(img: &ImageBuffer<Luma<T>, Vec<T>>) -> Vec<u32>
+where
+    T: Primitive + Into<u32>,
+{
+    let mut histogram = vec![0u32; T::DEFAULT_MAX_VALUE.into() as usize + 1];
+
+    for pixel in img.pixels() {
+        histogram[pixel[0].into() as usize] += 1;
+    }
+
+    histogram
+}
+
+/// Writes a histogram to `path` as CSV, one `value,count,threshold` row per
+/// bin after a header
+///
+/// The `threshold` column is `1` on the bin of the chosen global threshold, if
+/// any, and `0` everywhere else.
+pub fn write_histogram_csv(
+    path: &str,
+    histogram: &[u32],
+    threshold: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "value,count,threshold")?;
+
+    for (value, count) in histogram.iter().enumerate() {
+        let marked = threshold == Some(value);
+        writeln!(writer, "{},{},{}", value, count, marked as u8)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_sums_to_the_pixel_count() {
+        let narrow = ImageBuffer::from_fn(30, 20, |x, y| Luma([(x * y) as u8]));
+        let wide = ImageBuffer::from_fn(30, 20, |x, y| Luma([(x * y * 100) as u16]));
+
+        let narrow_histogram = compute_histogram(&narrow);
+        let wide_histogram = compute_histogram(&wide);
+
+        assert_eq!(narrow_histogram.len(), 256);
+        assert_eq!(narrow_histogram.iter().sum::<u32>(), 600);
+        assert_eq!(wide_histogram.len(), 65536);
+        assert_eq!(wide_histogram.iter().sum::<u32>(), 600);
+    }
+
+    #[test]
+    fn csv_marks_the_threshold() {
+        let path =
+            std::env::temp_dir().join(format!("drawrs-histogram-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        write_histogram_csv(path, &[5, 0, 3], Some(1)).unwrap();
+        let csv = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(csv, "value,count,threshold\n0,5,0\n1,0,1\n2,3,0\n");
+    }
+}
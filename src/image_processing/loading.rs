@@ -0,0 +1,290 @@
+use crate::utils::timing;
+use image::{
+    codecs::gif::GifDecoder, AnimationDecoder, DynamicImage, ImageBuffer, ImageDecoder, ImageError,
+    ImageReader, ImageResult, Rgb, RgbaImage,
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Cursor, Read, Seek},
+    path::Path,
+};
+
+/// File extensions accepted by the image picker
+pub const SUPPORTED_EXTENSIONS: [&str; 8] =
+    ["png", "jpg", "jpeg", "bmp", "gif", "webp", "psd", "svg"];
+
+/// Minimum length (in pixels) of the longest side of a rasterized SVG
+const SVG_RASTER_SIZE: f32 = 2048.0;
+
+/// Loads input images from disk into a [`DynamicImage`]
+///
+/// Formats handled by the `image` crate are decoded directly and rotated or
+/// flipped according to their EXIF orientation. Photoshop documents are
+/// flattened through the `psd` crate, and SVGs are rasterized onto a white
+/// background with `resvg`.
+pub struct ImageLoader;
+
+impl ImageLoader {
+    /// Loads the image at `path`, choosing a decoder based on its extension
+    ///
+    /// # Returns
+    /// - `Ok(DynamicImage)`: The decoded image
+    /// - `Err`: If the file can't be read or its format isn't supported
+    pub fn load(path: &str) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let _timer = timing::stage("load");
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("psd") => Self::load_psd(path),
+            Some("svg") => Self::load_svg(path),
+            _ => Self::load_oriented(ImageReader::open(path)?).map_err(|e| match e {
+                ImageError::Unsupported(reason) => {
+                    format!("Unsupported image format for '{}': {}", path, reason).into()
+                }
+                other => other.into(),
+            }),
+        }
+    }
+
+    /// Whether the file at `path` has the given extension, ignoring case
+    pub fn has_extension(path: &str, extension: &str) -> bool {
+        Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    }
+
+    /// Decodes every frame of the animated GIF at `path`
+    ///
+    /// Frames come out at the full size of the animation, with earlier frames
+    /// already composited under the ones that only update part of it.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<DynamicImage>)`: The frames in playback order
+    /// - `Err`: If the file can't be read or isn't a GIF
+    pub fn load_gif_frames(path: &str) -> Result<Vec<DynamicImage>, Box<dyn std::error::Error>> {
+        let _timer = timing::stage("load");
+        let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+        let frames = decoder.into_frames().collect_frames()?;
+
+        Ok(frames
+            .into_iter()
+            .map(|frame| DynamicImage::ImageRgba8(frame.into_buffer()))
+            .collect())
+    }
+
+    /// Composites an image with transparency onto a solid `background`
+    ///
+    /// Converting to grayscale simply drops the alpha channel, so transparent
+    /// pixels would otherwise keep whatever (often black) color they hold and
+    /// get drawn. Images without an alpha channel are returned unchanged.
+    pub fn flatten_alpha(img: DynamicImage, background: Rgb<u8>) -> DynamicImage {
+        if !img.color().has_alpha() {
+            return img;
+        }
+
+        let rgba = img.to_rgba16();
+        let background = background.0.map(|channel| channel as f64 * 257.0);
+        let flattened = ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+            let pixel = rgba.get_pixel(x, y);
+            let alpha = pixel[3] as f64 / u16::MAX as f64;
+            let blend = |channel: usize| {
+                (pixel[channel] as f64 * alpha + background[channel] * (1.0 - alpha)).round() as u16
+            };
+
+            Rgb([blend(0), blend(1), blend(2)])
+        });
+
+        DynamicImage::ImageRgb16(flattened)
+    }
+
+    /// Decodes an image and applies its EXIF orientation, so photos taken in
+    /// portrait come out upright
+    fn load_oriented<R: BufRead + Seek>(reader: ImageReader<R>) -> ImageResult<DynamicImage> {
+        let mut decoder = reader.with_guessed_format()?.into_decoder()?;
+        let orientation = decoder.orientation()?;
+
+        let mut img = DynamicImage::from_decoder(decoder)?;
+        img.apply_orientation(orientation);
+
+        Ok(img)
+    }
+
+    fn load_psd(path: &str) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        let psd = psd::Psd::from_bytes(&bytes)
+            .map_err(|e| format!("Failed to read PSD file '{}': {}", path, e))?;
+
+        let rgba = RgbaImage::from_raw(psd.width(), psd.height(), psd.rgba())
+            .ok_or("PSD pixel data doesn't match its dimensions")?;
+
+        Ok(DynamicImage::ImageRgba8(rgba))
+    }
+
+    /// Parses the SVG file at `path` without rasterizing it
+    pub fn load_svg_tree(path: &str) -> Result<resvg::usvg::Tree, Box<dyn std::error::Error>> {
+        let data = std::fs::read(path)?;
+
+        resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())
+            .map_err(|e| format!("Failed to parse SVG file '{}': {}", path, e).into())
+    }
+
+    /// Rasterizes an SVG, upscaling small documents so their longest side is at
+    /// least [`SVG_RASTER_SIZE`] pixels to keep thin strokes intact
+    fn load_svg(path: &str) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        use resvg::tiny_skia;
+
+        let tree = Self::load_svg_tree(path)?;
+
+        let size = tree.size();
+        let scale = (SVG_RASTER_SIZE / size.width().max(size.height())).max(1.0);
+        let width = (size.width() * scale).ceil() as u32;
+        let height = (size.height() * scale).ceil() as u32;
+
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or("SVG has an invalid size")?;
+        pixmap.fill(tiny_skia::Color::WHITE);
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+
+        let rgba = RgbaImage::from_raw(width, height, pixmap.take())
+            .ok_or("Rasterized SVG doesn't match its dimensions")?;
+
+        Ok(DynamicImage::ImageRgba8(rgba))
+    }
+
+    /// Reads an image from the system clipboard
+    ///
+    /// # Returns
+    /// - `Ok(DynamicImage)`: The copied image
+    /// - `Err`: If the clipboard is unavailable, empty, or doesn't hold an image
+    pub fn load_clipboard() -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let _timer = timing::stage("load");
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| format!("Failed to access the clipboard: {}", e))?;
+
+        let image = clipboard.get_image().map_err(|e| match e {
+            arboard::Error::ContentNotAvailable => {
+                "The clipboard doesn't contain an image".to_string()
+            }
+            other => format!("Failed to read image from clipboard: {}", other),
+        })?;
+
+        let rgba = RgbaImage::from_raw(
+            image.width as u32,
+            image.height as u32,
+            image.bytes.into_owned(),
+        )
+        .ok_or("Clipboard image data doesn't match its dimensions")?;
+
+        Ok(DynamicImage::ImageRgba8(rgba))
+    }
+
+    /// Reads encoded image data piped through stdin
+    ///
+    /// The format is guessed from the data itself, so any format supported by
+    /// the `image` crate can be piped in.
+    pub fn load_stdin() -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let _timer = timing::stage("load");
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+
+        if bytes.is_empty() {
+            return Err("No image data was piped through stdin".into());
+        }
+
+        Ok(Self::load_oriented(ImageReader::new(Cursor::new(bytes)))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{codecs::jpeg::JpegEncoder, GrayImage, Luma};
+
+    /// Encodes a JPEG with an EXIF APP1 segment holding only the orientation tag
+    fn jpeg_with_orientation(img: &GrayImage, orientation: u8) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, 95)
+            .encode_image(img)
+            .unwrap();
+
+        #[rustfmt::skip]
+        let tiff = [
+            b'M', b'M', 0, 42, 0, 0, 0, 8, // big-endian header, IFD at offset 8
+            0, 1, // one entry
+            0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation, 0, 0, // orientation, SHORT
+            0, 0, 0, 0, // no next IFD
+        ];
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&(2 + 6 + tiff.len() as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+
+        // The segment goes right after the start-of-image marker
+        jpeg.splice(2..2, app1);
+        jpeg
+    }
+
+    #[test]
+    fn rotated_exif_photo_loads_upright() {
+        // Dark left half, which a 90 degree clockwise rotation moves to the top
+        let img = GrayImage::from_fn(16, 8, |x, _| Luma([if x < 8 { 0 } else { 255 }]));
+        let bytes = jpeg_with_orientation(&img, 6);
+
+        let loaded = ImageLoader::load_oriented(ImageReader::new(Cursor::new(bytes)))
+            .unwrap()
+            .to_luma8();
+
+        assert_eq!(loaded.dimensions(), (8, 16));
+        assert!(loaded.get_pixel(4, 2)[0] < 64);
+        assert!(loaded.get_pixel(4, 13)[0] > 192);
+    }
+
+    #[test]
+    fn photo_without_exif_is_unchanged() {
+        let img = GrayImage::from_fn(16, 8, |x, _| Luma([if x < 8 { 0 } else { 255 }]));
+        let mut bytes = Vec::new();
+        JpegEncoder::new_with_quality(&mut bytes, 95)
+            .encode_image(&img)
+            .unwrap();
+
+        let loaded = ImageLoader::load_oriented(ImageReader::new(Cursor::new(bytes))).unwrap();
+
+        assert_eq!((loaded.width(), loaded.height()), (16, 8));
+    }
+
+    /// PNG of an opaque, a fully transparent and a half transparent black pixel
+    fn transparent_png() -> DynamicImage {
+        let img = RgbaImage::from_fn(3, 1, |x, _| {
+            image::Rgba([0, 0, 0, [255, 0, 128][x as usize]])
+        });
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+
+        ImageLoader::load_oriented(ImageReader::new(Cursor::new(bytes))).unwrap()
+    }
+
+    #[test]
+    fn transparent_pixels_become_the_background() {
+        let flattened = ImageLoader::flatten_alpha(transparent_png(), Rgb([255, 255, 255]));
+        let gray = flattened.to_luma8();
+
+        assert_eq!(gray.get_pixel(0, 0)[0], 0);
+        assert_eq!(gray.get_pixel(1, 0)[0], 255);
+        assert!((120..=135).contains(&gray.get_pixel(2, 0)[0]));
+    }
+
+    #[test]
+    fn background_color_is_configurable() {
+        let flattened = ImageLoader::flatten_alpha(transparent_png(), Rgb([255, 0, 0]));
+
+        assert_eq!(flattened.to_rgb8().get_pixel(1, 0), &Rgb([255, 0, 0]));
+    }
+}
@@ -1,5 +1,17 @@
+mod cache;
 mod conversion;
+mod filters;
+mod histogram;
+mod loading;
+mod morphology;
+mod quantize;
 mod scaling;
 
-pub use conversion::ImageProcessor;
+pub use cache::ProcessingCache;
+pub use conversion::{ImageProcessor, MethodResult, ProcessingOptions, ThresholdSource};
+pub use filters::{clahe, equalize_histogram, unsharp};
+pub use histogram::{compute_histogram, write_histogram_csv};
+pub use loading::{ImageLoader, SUPPORTED_EXTENSIONS};
+pub use morphology::remove_small_components;
+pub use quantize::{quantize, without_background};
 pub use scaling::ImageScaler;
@@ -0,0 +1,93 @@
+use image::{ImageBuffer, Luma, Primitive};
+
+/// Turns every 8-connected blob of black pixels smaller than `min_area` pixels
+/// white
+///
+/// Binarization leaves specks of a few pixels on noisy or dithered sources,
+/// which would otherwise be drawn as stray dots. Unlike the line length filter,
+/// this looks at the area of the 2D blob before any lines are traced, so thin
+/// but long strokes are kept.
+pub fn remove_small_components<T: Primitive>(
+    img: &mut ImageBuffer<Luma<T>, Vec<T>>,
+    min_area: u32,
+) {
+    let (width, height) = img.dimensions();
+    let black = T::DEFAULT_MIN_VALUE;
+    let mut visited = vec![false; width as usize * height as usize];
+    let mut component = Vec::new();
+    let mut stack = Vec::new();
+
+    for start_y in 0..height {
+        for start_x in 0..width {
+            let start = (start_y * width + start_x) as usize;
+            if visited[start] || img.get_pixel(start_x, start_y)[0] != black {
+                continue;
+            }
+
+            visited[start] = true;
+            stack.push((start_x, start_y));
+            component.clear();
+
+            while let Some((x, y)) = stack.pop() {
+                component.push((x, y));
+
+                for dy in -1i64..=1 {
+                    for dx in -1i64..=1 {
+                        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                        if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                            continue;
+                        }
+
+                        let (nx, ny) = (nx as u32, ny as u32);
+                        let index = (ny * width + nx) as usize;
+                        if !visited[index] && img.get_pixel(nx, ny)[0] == black {
+                            visited[index] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+
+            if (component.len() as u64) < min_area as u64 {
+                for &(x, y) in &component {
+                    img.put_pixel(x, y, Luma([T::DEFAULT_MAX_VALUE]));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_pixel_speck_is_removed_and_large_region_survives() {
+        let mut img = ImageBuffer::from_pixel(20, 20, Luma([255u8]));
+        img.put_pixel(2, 2, Luma([0]));
+        img.put_pixel(3, 3, Luma([0]));
+        for y in 10..16 {
+            for x in 10..16 {
+                img.put_pixel(x, y, Luma([0]));
+            }
+        }
+
+        remove_small_components(&mut img, 4);
+
+        assert_eq!(img.get_pixel(2, 2)[0], 255);
+        assert_eq!(img.get_pixel(3, 3)[0], 255);
+        assert_eq!(img.pixels().filter(|pixel| pixel[0] == 0).count(), 36);
+    }
+
+    #[test]
+    fn thin_long_stroke_is_kept() {
+        let mut img = ImageBuffer::from_pixel(30, 5, Luma([u16::MAX]));
+        for x in 0..30 {
+            img.put_pixel(x, 2, Luma([0]));
+        }
+
+        remove_small_components(&mut img, 10);
+
+        assert!((0..30).all(|x| img.get_pixel(x, 2)[0] == 0));
+    }
+}
@@ -0,0 +1,164 @@
+use image::{DynamicImage, GrayImage, Pixel, Rgb};
+use rayon::prelude::*;
+
+/// Maximum number of pixels the palette is fitted on
+const MAX_SAMPLES: usize = 20_000;
+
+/// Number of k-means refinement rounds
+const ITERATIONS: usize = 12;
+
+/// Largest RGB distance from the background at which a palette color counts
+/// as the background itself
+const BACKGROUND_DISTANCE: f64 = 40.0;
+
+/// Reduces an image to at most `n` colors and splits it into one mask per color
+///
+/// The palette is found with k-means on an evenly spaced sample of the pixels,
+/// seeded from colors spread across the brightness range so the result is
+/// deterministic. Each mask is black where a pixel is closest to its color and
+/// white elsewhere, so it can be drawn like any binarized image. Colors that
+/// end up with no pixels are dropped, and the rest are ordered from darkest to
+/// lightest.
+pub fn quantize(img: &DynamicImage, n: usize) -> Vec<(Rgb<u8>, GrayImage)> {
+    let rgb = img.to_rgb8();
+    let pixels: Vec<[f64; 3]> = rgb
+        .pixels()
+        .map(|pixel| pixel.0.map(|channel| channel as f64))
+        .collect();
+
+    if pixels.is_empty() || n == 0 {
+        return Vec::new();
+    }
+
+    let stride = pixels.len().div_ceil(MAX_SAMPLES);
+    let mut samples: Vec<[f64; 3]> = pixels.iter().step_by(stride).copied().collect();
+    samples.sort_by(|a, b| luma(a).total_cmp(&luma(b)));
+
+    let n = n.min(samples.len());
+    let mut palette: Vec<[f64; 3]> = (0..n)
+        .map(|cluster| samples[(cluster * 2 + 1) * samples.len() / (n * 2)])
+        .collect();
+
+    for _ in 0..ITERATIONS {
+        let mut sums = vec![([0.0; 3], 0usize); n];
+        for sample in &samples {
+            let (sum, count) = &mut sums[nearest(&palette, sample)];
+            for (total, value) in sum.iter_mut().zip(sample) {
+                *total += value;
+            }
+            *count += 1;
+        }
+
+        for (color, (sum, count)) in palette.iter_mut().zip(sums) {
+            if count > 0 {
+                *color = sum.map(|channel| channel / count as f64);
+            }
+        }
+    }
+
+    let assignments: Vec<usize> = pixels
+        .par_iter()
+        .map(|pixel| nearest(&palette, pixel))
+        .collect();
+
+    let mut passes: Vec<(Rgb<u8>, GrayImage)> = palette
+        .iter()
+        .enumerate()
+        .filter_map(|(cluster, color)| {
+            let mask: Vec<u8> = assignments
+                .iter()
+                .map(|&assigned| if assigned == cluster { 0 } else { u8::MAX })
+                .collect();
+
+            if !mask.contains(&0) {
+                return None;
+            }
+
+            let mask = GrayImage::from_raw(rgb.width(), rgb.height(), mask)?;
+            Some((Rgb(color.map(|channel| channel.round() as u8)), mask))
+        })
+        .collect();
+
+    passes.sort_by_key(|(color, _)| color.to_luma().0[0]);
+    passes
+}
+
+/// Drops the passes whose color is within [`BACKGROUND_DISTANCE`] of
+/// `background`, since drawing the paper color over the paper only wastes time
+pub fn without_background(
+    passes: Vec<(Rgb<u8>, GrayImage)>,
+    background: Rgb<u8>,
+) -> Vec<(Rgb<u8>, GrayImage)> {
+    let background = background.0.map(|channel| channel as f64);
+
+    passes
+        .into_iter()
+        .filter(|(color, _)| {
+            let distance_sq: f64 = color
+                .0
+                .iter()
+                .zip(&background)
+                .map(|(&a, b)| (a as f64 - b).powi(2))
+                .sum();
+
+            distance_sq > BACKGROUND_DISTANCE * BACKGROUND_DISTANCE
+        })
+        .collect()
+}
+
+fn luma(color: &[f64; 3]) -> f64 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+/// Index of the palette color closest to `color`
+fn nearest(palette: &[[f64; 3]], color: &[f64; 3]) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| {
+            let distance: f64 = candidate
+                .iter()
+                .zip(color)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum();
+            (index, distance)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    /// Page whose left half is painted red
+    fn red_on_white() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(20, 20, |x, _| {
+            if x < 10 {
+                Rgb([200, 20, 20])
+            } else {
+                Rgb([250, 250, 250])
+            }
+        }))
+    }
+
+    #[test]
+    fn splits_one_mask_per_color() {
+        let passes = quantize(&red_on_white(), 2);
+
+        assert_eq!(passes.len(), 2);
+        let (red, mask) = &passes[0];
+        assert_eq!(*red, Rgb([200, 20, 20]));
+        assert_eq!(mask.get_pixel(2, 10)[0], 0);
+        assert_eq!(mask.get_pixel(15, 10)[0], u8::MAX);
+    }
+
+    #[test]
+    fn background_pass_is_skipped() {
+        let passes = without_background(quantize(&red_on_white(), 2), Rgb([255, 255, 255]));
+
+        let colors: Vec<_> = passes.iter().map(|(color, _)| *color).collect();
+        assert_eq!(colors, vec![Rgb([200, 20, 20])]);
+    }
+}
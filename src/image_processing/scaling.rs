@@ -1,23 +1,129 @@
-use crate::choices::ScalingMode;
-use image::{imageops::FilterType, DynamicImage, ImageBuffer, Luma};
+use crate::{choices::ScalingMode, utils::geometry::Point};
+use image::{imageops::FilterType, GrayImage, ImageBuffer, Luma, Primitive};
+use log::info;
 
 /// Provides image scaling operations with various resizing methods
 ///
 /// Supports multiple scaling modes to fit images into target regions while preserving
-/// aspect ratio or filling space as needed. All operations work on 16-bit grayscale images.
+/// aspect ratio or filling space as needed. All operations work on 8- or 16-bit grayscale images.
 pub struct ImageScaler;
 
+/// Largest relative difference between the aspect ratios of a mask and the image
+/// it masks, see [`ImageScaler::fit_mask`]
+const MAX_MASK_ASPECT_ERROR: f64 = 0.02;
+
 impl ImageScaler {
+    /// Checks that a crop rectangle lies within an image of the given size
+    ///
+    /// Both corners are inclusive and may be given in any order.
+    ///
+    /// # Returns
+    /// - `Ok((x, y, width, height))`: The crop rectangle
+    /// - `Err`: A message describing why the crop is invalid
+    pub fn crop_bounds(
+        image_size: (u32, u32),
+        crop: (Point, Point),
+    ) -> Result<(u32, u32, u32, u32), String> {
+        let (a, b) = crop;
+        let (left, right) = (a.x.min(b.x), a.x.max(b.x));
+        let (top, bottom) = (a.y.min(b.y), a.y.max(b.y));
+
+        if left < 0 || top < 0 || right >= image_size.0 as i32 || bottom >= image_size.1 as i32 {
+            return Err(format!(
+                "Crop ({}, {}) -> ({}, {}) lies outside of the {}x{} image",
+                a.x, a.y, b.x, b.y, image_size.0, image_size.1
+            ));
+        }
+
+        Ok((
+            left as u32,
+            top as u32,
+            (right - left + 1) as u32,
+            (bottom - top + 1) as u32,
+        ))
+    }
+
+    /// Resizes a mask to `size` so it lines up pixel for pixel with the image
+    /// it masks
+    ///
+    /// Nearest-neighbor sampling keeps the mask's edges hard.
+    ///
+    /// # Returns
+    /// - `Ok(GrayImage)`: The mask at `size`
+    /// - `Err`: A message describing the mismatch if the mask's aspect ratio
+    ///   differs from the image's by more than [`MAX_MASK_ASPECT_ERROR`], which
+    ///   would stretch it out of place
+    pub fn fit_mask(mask: &GrayImage, size: (u32, u32)) -> Result<GrayImage, String> {
+        if mask.dimensions() == size {
+            return Ok(mask.clone());
+        }
+
+        let (mask_width, mask_height) = mask.dimensions();
+        let mask_aspect = mask_width as f64 / mask_height.max(1) as f64;
+        let image_aspect = size.0 as f64 / size.1.max(1) as f64;
+        if (mask_aspect / image_aspect - 1.0).abs() > MAX_MASK_ASPECT_ERROR {
+            return Err(format!(
+                "The {}x{} mask doesn't have the aspect ratio of the {}x{} image",
+                mask_width, mask_height, size.0, size.1
+            ));
+        }
+
+        info!(
+            "Scaling mask from {}x{} to {}x{}",
+            mask_width, mask_height, size.0, size.1
+        );
+        Ok(image::imageops::resize(
+            mask,
+            size.0,
+            size.1,
+            FilterType::Nearest,
+        ))
+    }
+
+    /// Splits a `(x, y, width, height)` rectangle into a `(columns, rows)` grid of
+    /// inclusive crops, ordered row by row
+    ///
+    /// Tile boundaries are spread evenly, so tiles differ in size by at most one
+    /// pixel. The grid is clamped to at least one tile per axis and at most one
+    /// tile per pixel.
+    pub fn tile_crops(
+        (x, y, width, height): (u32, u32, u32, u32),
+        (columns, rows): (u32, u32),
+    ) -> Vec<(Point, Point)> {
+        let columns = columns.clamp(1, width.max(1));
+        let rows = rows.clamp(1, height.max(1));
+
+        (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    let left = x + column * width / columns;
+                    let right = x + (column + 1) * width / columns - 1;
+                    let top = y + row * height / rows;
+                    let bottom = y + (row + 1) * height / rows - 1;
+
+                    (
+                        Point::new(left as i32, top as i32),
+                        Point::new(right as i32, bottom as i32),
+                    )
+                })
+            })
+            .collect()
+    }
+
     /// Scales an image to fit within a specified region using the selected scaling mode
     ///
     /// # Arguments
-    /// * `img` - Input 16-bit grayscale image to scale
+    /// * `img` - Input grayscale image to scale
+    /// * `crop` - Optional corners (in image coordinates) of the part of `img` to
+    ///   keep; everything outside of it is discarded before scaling
     /// * `start_pos` - (x, y) coordinates of region start point (top-left corner)
     /// * `end_pos` - (x, y) coordinates of region end point (bottom-right corner)
     /// * `scaling_mode` - [`ScalingMode`] strategy to use for resizing
     ///
     /// # Returns
-    /// New image buffer sized to the region dimensions (width = |end_x - start_x|, height = |end_y - start_y|)
+    /// New image buffer sized to the region dimensions (width = |end_x - start_x|, height = |end_y - start_y|),
+    /// except along the free axis of `FitWidth`/`FitHeight`, or an error if `crop` doesn't lie within
+    /// the image
     ///
     /// # Note
     /// Region dimensions are enforced to be at least 10x10 pixels. Coordinates can be in any order
@@ -27,6 +133,9 @@ impl ImageScaler {
     /// - **Stretch**: Ignores aspect ratio, stretches to exact region size
     /// - **Fit**: Maintains aspect ratio, fits entirely within region with letterboxing
     /// - **Fill**: Maintains aspect ratio, fills entire region with cropping
+    /// - **FitWidth**: Maintains aspect ratio, matches the region's width exactly and lets the
+    ///   height extend past or fall short of the region
+    /// - **FitHeight**: Same as `FitWidth` with the axes swapped
     /// - **Center**: No scaling, centers original image with padding
     /// - **Tile**: Repeats image in both directions like a mosaic
     ///
@@ -34,17 +143,36 @@ impl ImageScaler {
     /// ```ignore
     /// let scaled = ImageScaler::scale_image_to_region(
     ///     &input_img,
+    ///     None,
     ///     (100, 100),
     ///     (300, 200),
     ///     ScalingMode::Fit
-    /// );
+    /// )?;
     /// ```
-    pub fn scale_image_to_region(
-        img: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    pub fn scale_image_to_region<T: Primitive + 'static>(
+        img: &ImageBuffer<Luma<T>, Vec<T>>,
+        crop: Option<(Point, Point)>,
         start_pos: (i32, i32),
         end_pos: (i32, i32),
         scaling_mode: ScalingMode,
-    ) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    ) -> Result<ImageBuffer<Luma<T>, Vec<T>>, String> {
+        let Some(crop) = crop else {
+            return Ok(Self::scale(img, start_pos, end_pos, scaling_mode));
+        };
+
+        let (x, y, width, height) = Self::crop_bounds(img.dimensions(), crop)?;
+        let cropped = image::imageops::crop_imm(img, x, y, width, height).to_image();
+
+        Ok(Self::scale(&cropped, start_pos, end_pos, scaling_mode))
+    }
+
+    fn scale<T: Primitive + 'static>(
+        img: &ImageBuffer<Luma<T>, Vec<T>>,
+        start_pos: (i32, i32),
+        end_pos: (i32, i32),
+        scaling_mode: ScalingMode,
+    ) -> ImageBuffer<Luma<T>, Vec<T>> {
+        let white = Luma([T::DEFAULT_MAX_VALUE]);
         let region_width = (end_pos.0 - start_pos.0).unsigned_abs();
         let region_height = (end_pos.1 - start_pos.1).unsigned_abs();
 
@@ -54,30 +182,28 @@ impl ImageScaler {
         let img_width = img.width();
         let img_height = img.height();
 
-        println!(
+        info!(
             "Original image: {}x{}, Target region: {}x{}",
             img_width, img_height, region_width, region_height
         );
 
         match scaling_mode {
-            ScalingMode::Stretch => DynamicImage::ImageLuma16(img.clone())
-                .resize_exact(region_width, region_height, FilterType::Lanczos3)
-                .to_luma16(),
+            ScalingMode::Stretch => {
+                image::imageops::resize(img, region_width, region_height, FilterType::Lanczos3)
+            }
 
             ScalingMode::Fit => {
                 let scale_x = region_width as f64 / img_width as f64;
                 let scale_y = region_height as f64 / img_height as f64;
                 let scale = scale_x.min(scale_y);
 
-                let new_width = (img_width as f64 * scale) as u32;
-                let new_height = (img_height as f64 * scale) as u32;
+                let new_width = ((img_width as f64 * scale) as u32).max(1);
+                let new_height = ((img_height as f64 * scale) as u32).max(1);
 
-                let scaled_img = DynamicImage::ImageLuma16(img.clone())
-                    .resize(new_width, new_height, FilterType::Lanczos3)
-                    .to_luma16();
+                let scaled_img =
+                    image::imageops::resize(img, new_width, new_height, FilterType::Lanczos3);
 
-                let mut canvas =
-                    ImageBuffer::from_pixel(region_width, region_height, Luma([255u16]));
+                let mut canvas = ImageBuffer::from_pixel(region_width, region_height, white);
                 let offset_x = (region_width - new_width) / 2;
                 let offset_y = (region_height - new_height) / 2;
 
@@ -98,9 +224,8 @@ impl ImageScaler {
                 let new_width = (img_width as f64 * scale) as u32;
                 let new_height = (img_height as f64 * scale) as u32;
 
-                let scaled_img = DynamicImage::ImageLuma16(img.clone())
-                    .resize(new_width, new_height, FilterType::Lanczos3)
-                    .to_luma16();
+                let scaled_img =
+                    image::imageops::resize(img, new_width, new_height, FilterType::Lanczos3);
 
                 let crop_x = if new_width > region_width {
                     (new_width - region_width) / 2
@@ -120,16 +245,29 @@ impl ImageScaler {
                     if source_x < new_width && source_y < new_height {
                         *pixel = *scaled_img.get_pixel(source_x, source_y);
                     } else {
-                        *pixel = Luma([255u16]);
+                        *pixel = white;
                     }
                 }
 
                 result
             }
 
+            ScalingMode::FitWidth => {
+                let scale = region_width as f64 / img_width as f64;
+                let new_height = ((img_height as f64 * scale).round() as u32).max(1);
+
+                image::imageops::resize(img, region_width, new_height, FilterType::Lanczos3)
+            }
+
+            ScalingMode::FitHeight => {
+                let scale = region_height as f64 / img_height as f64;
+                let new_width = ((img_width as f64 * scale).round() as u32).max(1);
+
+                image::imageops::resize(img, new_width, region_height, FilterType::Lanczos3)
+            }
+
             ScalingMode::Center => {
-                let mut canvas =
-                    ImageBuffer::from_pixel(region_width, region_height, Luma([255u16]));
+                let mut canvas = ImageBuffer::from_pixel(region_width, region_height, white);
                 let offset_x = if region_width > img_width {
                     (region_width - img_width) / 2
                 } else {
@@ -166,3 +304,27 @@ impl ImageScaler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaled_size(scaling_mode: ScalingMode, region: (i32, i32)) -> (u32, u32) {
+        let img = GrayImage::new(200, 100);
+        ImageScaler::scale_image_to_region(&img, None, (0, 0), region, scaling_mode)
+            .unwrap()
+            .dimensions()
+    }
+
+    #[test]
+    fn fit_width_matches_the_width_and_keeps_the_aspect() {
+        assert_eq!(scaled_size(ScalingMode::FitWidth, (400, 50)), (400, 200));
+        assert_eq!(scaled_size(ScalingMode::FitWidth, (100, 500)), (100, 50));
+    }
+
+    #[test]
+    fn fit_height_matches_the_height_and_keeps_the_aspect() {
+        assert_eq!(scaled_size(ScalingMode::FitHeight, (50, 300)), (600, 300));
+        assert_eq!(scaled_size(ScalingMode::FitHeight, (900, 20)), (40, 20));
+    }
+}
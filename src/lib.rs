@@ -1,4 +1,7 @@
 pub mod choices;
+pub mod cli;
 pub mod drawing;
+#[cfg(feature = "gui")]
+pub mod gui;
 pub mod image_processing;
 pub mod utils;
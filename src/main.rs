@@ -1,10 +1,7 @@
-mod choices;
-mod drawing;
-mod image_processing;
-mod utils;
-
-use drawing::app::DrawingApp;
+use drawrs::drawing::app::DrawingApp;
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
     DrawingApp::run();
 }
@@ -1,3 +1,7 @@
+use crate::utils::screen::ScreenRegion;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Point {
     pub x: i32,
@@ -15,3 +19,281 @@ impl Point {
         dx * dx + dy * dy
     }
 }
+
+/// Moves `end` to the closest position whose region with `start` has the
+/// aspect ratio `width:height`
+///
+/// The region keeps the direction it was dragged in; only its size changes.
+pub fn lock_aspect_ratio(start: (i32, i32), end: (i32, i32), aspect: (u32, u32)) -> (i32, i32) {
+    let (aspect_w, aspect_h) = (aspect.0.max(1) as f64, aspect.1.max(1) as f64);
+    let dx = (end.0 - start.0) as f64;
+    let dy = (end.1 - start.1) as f64;
+
+    let t =
+        (dx.abs() * aspect_w + dy.abs() * aspect_h) / (aspect_w * aspect_w + aspect_h * aspect_h);
+    let width = (t * aspect_w).round() as i32;
+    let height = (t * aspect_h).round() as i32;
+
+    (
+        start.0 + if dx < 0.0 { -width } else { width },
+        start.1 + if dy < 0.0 { -height } else { height },
+    )
+}
+
+/// Orders the corners of a region so the first is the top-left and the second
+/// the bottom-right one
+///
+/// # Errors
+/// Returns a description of the problem if the region is narrower or shorter
+/// than `min_span` pixels.
+pub fn normalize_region(
+    start: (i32, i32),
+    end: (i32, i32),
+    min_span: i32,
+) -> Result<ScreenRegion, String> {
+    let top_left = (start.0.min(end.0), start.1.min(end.1));
+    let bottom_right = (start.0.max(end.0), start.1.max(end.1));
+
+    let width = bottom_right.0 - top_left.0;
+    let height = bottom_right.1 - top_left.1;
+    if width < min_span || height < min_span {
+        return Err(format!(
+            "Region ({}, {}) -> ({}, {}) is only {}x{} pixels, it must be at least {}x{}",
+            start.0, start.1, end.0, end.1, width, height, min_span, min_span
+        ));
+    }
+
+    Ok((top_left, bottom_right))
+}
+
+/// Closed polygon given by its vertices, e.g. to confine a drawing to a
+/// non-rectangular area
+///
+/// Parses from and prints as space-separated `x,y` pairs, e.g.
+/// `"0,0 100,0 50,80"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Polygon {
+    vertices: Vec<(f64, f64)>,
+}
+
+impl Polygon {
+    /// Builds a polygon from at least three vertices
+    pub fn new(vertices: Vec<(f64, f64)>) -> Result<Self, String> {
+        if vertices.len() < 3 {
+            return Err(format!(
+                "A polygon needs at least 3 vertices, got {}",
+                vertices.len()
+            ));
+        }
+
+        Ok(Polygon { vertices })
+    }
+
+    /// Same polygon with every coordinate multiplied by `factor`
+    pub fn scaled(&self, factor: f64) -> Self {
+        Polygon {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|&(x, y)| (x * factor, y * factor))
+                .collect(),
+        }
+    }
+
+    /// Whether `point` lies inside the polygon, by the even-odd rule
+    ///
+    /// Casts a ray to the right of the point and counts the edges it crosses,
+    /// so self-intersecting polygons leave their overlapping parts out.
+    pub fn contains(&self, point: Point) -> bool {
+        self.contains_xy(point.x as f64, point.y as f64)
+    }
+
+    /// Parts of `line` inside the polygon, split wherever its segments cross
+    /// the polygon's border
+    ///
+    /// Unlike filtering the points with [`contains`](Self::contains), this
+    /// also clips lines given only by their end points, e.g. hatching. A
+    /// single-point line (a dot) is kept if it lies inside.
+    pub fn clip_line(&self, line: &[Point]) -> Vec<Vec<Point>> {
+        if line.len() == 1 {
+            return if self.contains(line[0]) {
+                vec![line.to_vec()]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let mut parts = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+
+        for segment in line.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+            let at = |t: f64| (a.x as f64 + t * dx, a.y as f64 + t * dy);
+
+            let mut cuts = vec![0.0, 1.0];
+            cuts.extend(self.crossings(a, b));
+            cuts.sort_by(f64::total_cmp);
+
+            for span in cuts.windows(2) {
+                let (mid_x, mid_y) = at((span[0] + span[1]) / 2.0);
+                if !self.contains_xy(mid_x, mid_y) {
+                    parts.push(std::mem::take(&mut current));
+                    continue;
+                }
+
+                let round = |(x, y): (f64, f64)| Point::new(x.round() as i32, y.round() as i32);
+                let (from, to) = (round(at(span[0])), round(at(span[1])));
+                if current.last() != Some(&from) {
+                    parts.push(std::mem::take(&mut current));
+                    current.push(from);
+                }
+                if to != from {
+                    current.push(to);
+                }
+            }
+        }
+
+        parts.push(current);
+        parts.retain(|part| part.len() >= 2);
+        parts
+    }
+
+    /// Positions (from 0 at `a` to 1 at `b`) where the segment from `a` to
+    /// `b` crosses an edge of the polygon
+    fn crossings(&self, a: Point, b: Point) -> Vec<f64> {
+        let (ax, ay) = (a.x as f64, a.y as f64);
+        let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+        let mut previous = self.vertices[self.vertices.len() - 1];
+        let mut crossings = Vec::new();
+
+        for &(x, y) in &self.vertices {
+            let (px, py) = previous;
+            let (ex, ey) = (x - px, y - py);
+            let denominator = dx * ey - dy * ex;
+
+            if denominator != 0.0 {
+                let t = ((px - ax) * ey - (py - ay) * ex) / denominator;
+                let u = ((px - ax) * dy - (py - ay) * dx) / denominator;
+                if t > 0.0 && t < 1.0 && (0.0..=1.0).contains(&u) {
+                    crossings.push(t);
+                }
+            }
+            previous = (x, y);
+        }
+
+        crossings
+    }
+
+    /// [`contains`](Self::contains) for a point between pixels
+    fn contains_xy(&self, px: f64, py: f64) -> bool {
+        let mut inside = false;
+        let mut previous = self.vertices[self.vertices.len() - 1];
+
+        for &(x, y) in &self.vertices {
+            let (prev_x, prev_y) = previous;
+            if (y > py) != (prev_y > py)
+                && px < prev_x + (py - prev_y) / (y - prev_y) * (x - prev_x)
+            {
+                inside = !inside;
+            }
+            previous = (x, y);
+        }
+
+        inside
+    }
+}
+
+impl FromStr for Polygon {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let vertices = s
+            .split_whitespace()
+            .map(|pair| {
+                let (x, y) = pair
+                    .split_once(',')
+                    .ok_or_else(|| format!("Invalid vertex '{}', expected x,y", pair))?;
+                let coordinate = |value: &str| {
+                    value
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid coordinate '{}'", value))
+                };
+
+                Ok((coordinate(x)?, coordinate(y)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Polygon::new(vertices)
+    }
+}
+
+impl fmt::Display for Polygon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self
+            .vertices
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect();
+
+        write!(f, "{}", pairs.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverted_region_is_normalized() {
+        let region = normalize_region((300, 400), (100, 50), 10);
+
+        assert_eq!(region, Ok(((100, 50), (300, 400))));
+    }
+
+    #[test]
+    fn degenerate_region_is_rejected() {
+        assert!(normalize_region((100, 100), (100, 100), 10).is_err());
+        assert!(normalize_region((100, 100), (500, 105), 10).is_err());
+        assert!(normalize_region((100, 100), (110, 110), 10).is_ok());
+    }
+
+    fn square() -> Polygon {
+        "5,0 15,0 15,10 5,10".parse().unwrap()
+    }
+
+    #[test]
+    fn segment_is_cut_at_the_border() {
+        let hatch = [Point::new(0, 5), Point::new(20, 5)];
+
+        assert_eq!(
+            square().clip_line(&hatch),
+            vec![vec![Point::new(5, 5), Point::new(15, 5)]]
+        );
+    }
+
+    #[test]
+    fn line_leaving_and_reentering_is_split() {
+        // U shape open at the top between x = 10 and x = 20
+        let cup: Polygon = "0,0 10,0 10,10 20,10 20,0 30,0 30,20 0,20".parse().unwrap();
+        let line: Vec<Point> = (2..=28).map(|x| Point::new(x, 5)).collect();
+
+        let parts = cup.clip_line(&line);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].first(), Some(&Point::new(2, 5)));
+        assert_eq!(parts[0].last(), Some(&Point::new(10, 5)));
+        assert_eq!(parts[1].first(), Some(&Point::new(20, 5)));
+        assert_eq!(parts[1].last(), Some(&Point::new(28, 5)));
+    }
+
+    #[test]
+    fn dots_outside_are_dropped() {
+        let inside = [Point::new(10, 5)];
+        let outside = [Point::new(2, 5)];
+
+        assert_eq!(square().clip_line(&inside), vec![inside.to_vec()]);
+        assert!(square().clip_line(&outside).is_empty());
+    }
+}
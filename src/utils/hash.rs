@@ -0,0 +1,53 @@
+/// 64-bit FNV-1a hash of whatever bytes are written to it
+///
+/// Unlike `DefaultHasher`, its output is fixed rather than up to the Rust
+/// release, so it can key files that outlive the binary that wrote them. Values
+/// should be written as explicit bytes (e.g. `to_le_bytes`) rather than through
+/// `Hash`, whose output for a type isn't guaranteed to stay the same either.
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    pub fn new() -> Self {
+        StableHasher(Self::OFFSET_BASIS)
+    }
+
+    /// Adds `bytes` to the hash
+    pub fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    /// The hash of everything written so far
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_fnv_1a_reference_values() {
+        assert_eq!(StableHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(
+            StableHasher::new().write(b"a").finish(),
+            0xaf63_dc4c_8601_ec8c
+        );
+        assert_eq!(
+            StableHasher::new().write(b"foobar").finish(),
+            0x8594_4171_f739_67e8
+        );
+    }
+}
@@ -1,2 +1,7 @@
 pub mod duration;
 pub mod geometry;
+pub mod hash;
+pub mod polling;
+pub mod screen;
+pub mod status;
+pub mod timing;
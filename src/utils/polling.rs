@@ -0,0 +1,39 @@
+use std::{thread, time::Duration};
+
+/// Sleep interval for polling loops that backs off while nothing happens
+///
+/// Every idle poll doubles the interval up to `max`, and any activity drops it
+/// straight back to `min`, so waiting for a key barely uses the CPU while
+/// staying responsive once the user starts interacting.
+pub struct PollBackoff {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl PollBackoff {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        PollBackoff {
+            min,
+            max: max.max(min),
+            current: min,
+        }
+    }
+
+    /// Sleeps before the next poll, resetting the interval first if there was
+    /// any `activity` since the last one
+    pub fn wait(&mut self, activity: bool) {
+        if activity {
+            self.current = self.min;
+        }
+
+        thread::sleep(self.current);
+        self.current = (self.current * 2).min(self.max);
+    }
+}
+
+impl Default for PollBackoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(5), Duration::from_millis(60))
+    }
+}
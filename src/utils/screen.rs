@@ -0,0 +1,147 @@
+use {
+    enigo::{Enigo, Mouse},
+    std::{error::Error, str::FromStr},
+};
+
+/// A region on screen as its top-left and bottom-right corners
+pub type ScreenRegion = ((i32, i32), (i32, i32));
+
+/// A region given either in absolute screen pixels or relative to the screen
+/// size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionSpec {
+    /// Top-left and bottom-right corners in pixels
+    Pixels(ScreenRegion),
+
+    /// Edges as percentages (0-100) of the screen's width and height, e.g.
+    /// `25, 25, 75, 75` for the middle half of the screen
+    Percent {
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+    },
+}
+
+impl RegionSpec {
+    /// Resolves the region against a display of the given size (in pixels)
+    ///
+    /// # Errors
+    /// Fails if a percentage is outside 0-100.
+    pub fn resolve(&self, (width, height): (i32, i32)) -> Result<ScreenRegion, String> {
+        match *self {
+            RegionSpec::Pixels(region) => Ok(region),
+            RegionSpec::Percent {
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                for percent in [left, top, right, bottom] {
+                    if !(0.0..=100.0).contains(&percent) {
+                        return Err(format!("{}% is not between 0% and 100%", percent));
+                    }
+                }
+
+                let x = |percent: f64| ((width - 1) as f64 * percent / 100.0).round() as i32;
+                let y = |percent: f64| ((height - 1) as f64 * percent / 100.0).round() as i32;
+
+                Ok(((x(left), y(top)), (x(right), y(bottom))))
+            }
+        }
+    }
+}
+
+impl FromStr for RegionSpec {
+    type Err = String;
+
+    /// Parses the four edges `LEFT,TOP,RIGHT,BOTTOM`, either all in pixels
+    /// (`100,100,900,700`) or all as percentages (`25%,25%,75%,75%`)
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let edges: Vec<&str> = value.split(',').map(str::trim).collect();
+        let [left, top, right, bottom] = edges[..] else {
+            return Err("expected four comma-separated edges".to_string());
+        };
+
+        if edges.iter().all(|edge| edge.ends_with('%')) {
+            let percent = |edge: &str| {
+                edge.trim_end_matches('%')
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a percentage", edge))
+            };
+
+            Ok(RegionSpec::Percent {
+                left: percent(left)?,
+                top: percent(top)?,
+                right: percent(right)?,
+                bottom: percent(bottom)?,
+            })
+        } else {
+            let pixels = |edge: &str| {
+                edge.parse::<i32>()
+                    .map_err(|_| format!("'{}' is not a pixel position", edge))
+            };
+
+            Ok(RegionSpec::Pixels((
+                (pixels(left)?, pixels(top)?),
+                (pixels(right)?, pixels(bottom)?),
+            )))
+        }
+    }
+}
+
+/// Size (in pixels) of the primary monitor
+pub fn primary_monitor_size(enigo: &Enigo) -> Result<(i32, i32), Box<dyn Error>> {
+    Ok(enigo.main_display()?)
+}
+
+/// Region covering a whole display of the given size
+pub fn full_screen_region((width, height): (i32, i32)) -> ScreenRegion {
+    ((0, 0), (width - 1, height - 1))
+}
+
+/// Region covered by the currently focused window
+///
+/// # Errors
+/// Fails on platforms where the focused window can't be queried.
+pub fn active_window_region() -> Result<ScreenRegion, Box<dyn Error>> {
+    let window = active_win_pos_rs::get_active_window()
+        .map_err(|_| "The focused window can't be queried on this platform")?;
+    let bounds = window.position;
+
+    let start = (bounds.x.round() as i32, bounds.y.round() as i32);
+    let end = (
+        (bounds.x + bounds.width).round() as i32 - 1,
+        (bounds.y + bounds.height).round() as i32 - 1,
+    );
+
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_parses_pixels_and_percentages() {
+        assert_eq!(
+            "100,50,900,700".parse::<RegionSpec>(),
+            Ok(RegionSpec::Pixels(((100, 50), (900, 700))))
+        );
+
+        let spec: RegionSpec = "25%, 25%, 75%, 75%".parse().unwrap();
+        assert_eq!(
+            spec,
+            RegionSpec::Percent {
+                left: 25.0,
+                top: 25.0,
+                right: 75.0,
+                bottom: 75.0,
+            }
+        );
+        assert_eq!(spec.resolve((1001, 801)), Ok(((250, 200), (750, 600))));
+
+        assert!("25%,25%,75%,75".parse::<RegionSpec>().is_err());
+        assert!("1,2,3".parse::<RegionSpec>().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use serde::Serialize;
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+#[cfg(not(test))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(test))]
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// Tests run in parallel, each on its own thread, so each one gets its own
+// output mode and events rather than seeing those of the others
+#[cfg(test)]
+thread_local! {
+    static JSON: Cell<bool> = const { Cell::new(false) };
+
+    /// Every event emitted since the last [`test_mode`], for tests to check
+    /// what a run reported
+    static EMITTED: RefCell<Vec<StatusEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Machine-readable status update printed as one JSON object per line in
+/// `--json` mode
+///
+/// Every event is tagged with its kind in the `event` field, e.g.
+/// `{"event":"paths_generated","lines":12,"points":3400}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StatusEvent {
+    /// Human-readable message that has no dedicated event
+    Message { text: String },
+
+    /// The image was loaded
+    ImageLoaded { width: u32, height: u32 },
+
+    /// Drawing paths were extracted from the image
+    PathsGenerated { lines: usize, points: usize },
+
+    /// The pen started drawing, skipping `resume_from` lines, with `seed`
+    /// driving the shuffled order and jitter
+    DrawingStarted {
+        lines: usize,
+        points: usize,
+        resume_from: usize,
+        seed: u64,
+    },
+
+    /// Drawing of the `frame`th of `total` frames of an animation (1-based) is
+    /// about to start
+    FrameStarted { frame: usize, total: usize },
+
+    /// Drawing of the `tile`th of `total` tiles (1-based) of a drawing split
+    /// into a grid is about to start
+    TileStarted { tile: usize, total: usize },
+
+    /// A line was drawn
+    Progress { line: u64, total: u64 },
+
+    /// Every line was drawn
+    DrawingCompleted { lines: usize, points: usize },
+
+    /// The drawing was cancelled before `line`
+    DrawingCancelled { line: usize },
+
+    /// A pipeline stage finished, see [`timing`](crate::utils::timing)
+    StageTiming { stage: String, millis: f64 },
+
+    /// `prompt` was answered with its default, `answer`, since nobody is
+    /// there to answer it in JSON mode
+    PromptDefaulted { prompt: String, answer: String },
+
+    /// The run stopped because it needed to ask `prompt`, which has no default
+    /// to answer it with in JSON mode
+    PromptRequired { prompt: String },
+}
+
+/// Switches status output to newline-delimited JSON for the rest of the run
+pub fn enable_json() {
+    set_json(true);
+}
+
+#[cfg(not(test))]
+fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+fn set_json(json: bool) {
+    JSON.with(|mode| mode.set(json));
+}
+
+/// Whether status is printed as JSON
+#[cfg(not(test))]
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Whether status is printed as JSON on this thread
+#[cfg(test)]
+pub fn is_json() -> bool {
+    JSON.with(Cell::get)
+}
+
+/// Prints `event` as a JSON line in JSON mode, does nothing otherwise
+pub fn emit(event: StatusEvent) {
+    if !is_json() {
+        return;
+    }
+
+    #[cfg(test)]
+    EMITTED.with(|emitted| emitted.borrow_mut().push(event.clone()));
+
+    match serde_json::to_string(&event) {
+        Ok(line) => println!("{}", line),
+        Err(e) => log::warn!("Failed to serialize status event: {}", e),
+    }
+}
+
+/// Events emitted so far on this thread, see [`emit`]
+#[cfg(test)]
+pub fn emitted() -> Vec<StatusEvent> {
+    EMITTED.with(|emitted| emitted.borrow().clone())
+}
+
+/// Output mode of a test, switched back to human output when dropped
+#[cfg(test)]
+#[must_use]
+pub struct TestMode(());
+
+#[cfg(test)]
+impl Drop for TestMode {
+    fn drop(&mut self) {
+        set_json(false);
+    }
+}
+
+/// Switches this thread to JSON output if `json` is set, or human output
+/// otherwise, with no events emitted yet, until the returned guard is dropped
+///
+/// Tests that don't call it get human output and their own events, whatever
+/// the tests running alongside them do.
+#[cfg(test)]
+pub fn test_mode(json: bool) -> TestMode {
+    EMITTED.with(|emitted| emitted.borrow_mut().clear());
+    set_json(json);
+
+    TestMode(())
+}
+
+/// `answer` in JSON mode, where nobody is there to answer `prompt`, after
+/// emitting a [`StatusEvent::PromptDefaulted`]; `None` otherwise, when the
+/// prompt should be asked
+pub fn default_answer<T: Debug>(prompt: &str, answer: T) -> Option<T> {
+    if !is_json() {
+        return None;
+    }
+
+    emit(StatusEvent::PromptDefaulted {
+        prompt: prompt.to_string(),
+        answer: format!("{:?}", answer),
+    });
+    Some(answer)
+}
+
+/// Exits the process if `prompt` is about to be asked in JSON mode, where
+/// nobody is there to answer it, after emitting a
+/// [`StatusEvent::PromptRequired`]
+///
+/// For prompts without a default, like coordinates or the image file, which
+/// have to come from the command line or the GUI instead. Tests panic instead
+/// of exiting, failing only the test that got there.
+pub fn require_interactive(prompt: &str) {
+    if !is_json() {
+        return;
+    }
+
+    emit(StatusEvent::PromptRequired {
+        prompt: prompt.to_string(),
+    });
+
+    #[cfg(not(test))]
+    std::process::exit(2);
+    #[cfg(test)]
+    panic!("'{}' has to be answered interactively", prompt);
+}
+
+/// Prints a human-readable message, wrapped in a [`StatusEvent::Message`] in
+/// JSON mode
+pub fn say(text: impl Into<String>) {
+    let text = text.into();
+
+    if is_json() {
+        emit(StatusEvent::Message { text });
+    } else {
+        println!("{}", text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_only_applies_to_its_own_thread() {
+        let _mode = test_mode(true);
+        say("here");
+
+        let elsewhere = std::thread::spawn(|| {
+            say("elsewhere");
+            (is_json(), emitted())
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(elsewhere, (false, Vec::new()));
+        assert_eq!(
+            emitted(),
+            vec![StatusEvent::Message {
+                text: "here".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "'Image file' has to be answered interactively")]
+    fn required_prompt_fails_the_test_instead_of_exiting() {
+        let _mode = test_mode(true);
+        require_interactive("Image file");
+    }
+
+    #[test]
+    fn every_event_is_one_parseable_json_line() {
+        let events = [
+            (
+                StatusEvent::Message {
+                    text: "line one\nline two".to_string(),
+                },
+                "message",
+            ),
+            (
+                StatusEvent::ImageLoaded {
+                    width: 640,
+                    height: 480,
+                },
+                "image_loaded",
+            ),
+            (
+                StatusEvent::PathsGenerated {
+                    lines: 12,
+                    points: 3400,
+                },
+                "paths_generated",
+            ),
+            (
+                StatusEvent::DrawingStarted {
+                    lines: 12,
+                    points: 3400,
+                    resume_from: 0,
+                    seed: u64::MAX,
+                },
+                "drawing_started",
+            ),
+            (
+                StatusEvent::FrameStarted { frame: 1, total: 3 },
+                "frame_started",
+            ),
+            (
+                StatusEvent::TileStarted { tile: 2, total: 4 },
+                "tile_started",
+            ),
+            (StatusEvent::Progress { line: 5, total: 12 }, "progress"),
+            (
+                StatusEvent::DrawingCompleted {
+                    lines: 12,
+                    points: 3400,
+                },
+                "drawing_completed",
+            ),
+            (
+                StatusEvent::DrawingCancelled { line: 7 },
+                "drawing_cancelled",
+            ),
+            (
+                StatusEvent::StageTiming {
+                    stage: "trace".to_string(),
+                    millis: 1.5,
+                },
+                "stage_timing",
+            ),
+            (
+                StatusEvent::PromptDefaulted {
+                    prompt: "Number of colors".to_string(),
+                    answer: "4".to_string(),
+                },
+                "prompt_defaulted",
+            ),
+            (
+                StatusEvent::PromptRequired {
+                    prompt: "Number of colors".to_string(),
+                },
+                "prompt_required",
+            ),
+        ];
+
+        for (event, tag) in events {
+            let line = serde_json::to_string(&event).unwrap();
+            assert!(!line.contains('\n'), "{} spans several lines", tag);
+
+            let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(parsed["event"], tag);
+        }
+    }
+}
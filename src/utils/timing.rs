@@ -0,0 +1,72 @@
+use crate::utils::status::{self, StatusEvent};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STAGES: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+/// Starts recording stage timings for the rest of the run
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Times a pipeline stage until the returned guard is dropped
+///
+/// Does nothing unless timings were [`enable`]d, so stages can be
+/// instrumented unconditionally.
+pub fn stage(name: &'static str) -> StageTimer {
+    StageTimer {
+        name,
+        started: ENABLED.load(Ordering::Relaxed).then(Instant::now),
+    }
+}
+
+/// Prints every recorded stage in the order it finished, with the total
+///
+/// In JSON mode each stage is emitted as a `stage_timing` event instead.
+pub fn report() {
+    let stages = STAGES.lock().unwrap_or_else(|e| e.into_inner());
+    if stages.is_empty() {
+        return;
+    }
+
+    if status::is_json() {
+        for (name, duration) in stages.iter() {
+            status::emit(StatusEvent::StageTiming {
+                stage: name.to_string(),
+                millis: duration.as_secs_f64() * 1000.0,
+            });
+        }
+        return;
+    }
+
+    println!("Stage timings:");
+    for (name, duration) in stages.iter() {
+        println!("  {:<20} {:>10.2?}", name, duration);
+    }
+
+    let total: Duration = stages.iter().map(|(_, duration)| *duration).sum();
+    println!("  {:<20} {:>10.2?}", "total", total);
+}
+
+/// Guard returned by [`stage`] that records the elapsed time when dropped
+pub struct StageTimer {
+    name: &'static str,
+    started: Option<Instant>,
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        if let Some(started) = self.started {
+            STAGES
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push((self.name, started.elapsed()));
+        }
+    }
+}